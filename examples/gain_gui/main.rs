@@ -0,0 +1,240 @@
+//! A gain plugin with a webview editor, showing how the pieces fit together:
+//!
+//! - The page in `ui/` is embedded into the plugin and served over a custom
+//!   protocol, so the plugin is a single file.
+//! - The gain slider is bound to the `gain` parameter through
+//!   `window.plugin.params`, and the smoothed gain the audio thread applies is
+//!   shown through `window.plugin.displayValues`.
+//! - Resetting the gain and loading a file dropped onto the drop zone go
+//!   through JSON messages handled by [`GainEditor`].
+//! - The window can be resized with the handle in its corner, and the chosen
+//!   theme is kept in the plugin's state with `window.plugin.saveUiState`.
+//!
+//! Build it with `cargo build --example gain_gui` and load the resulting
+//! library as a CLAP plugin.
+
+use std::{
+    borrow::Cow,
+    sync::{atomic::Ordering, Arc},
+};
+
+use atomic_float::AtomicF32;
+use nih_plug::prelude::*;
+use nih_plug_webview::{
+    wry::http::{header::CONTENT_TYPE, Request, Response},
+    Context, DropEvent, EditorHandler, PageLoadEvent, ResizePolicy, WebviewConfig, WebviewEditor,
+    WebviewSource, WebviewState,
+};
+use serde::{Deserialize, Serialize};
+
+/// The custom protocol the embedded page is served over.
+const PROTOCOL: &str = "gui";
+
+/// The files of the page in `ui/`, by their path on the custom protocol.
+const ASSETS: &[(&str, &[u8], &str)] = &[
+    ("/index.html", include_bytes!("ui/index.html"), "text/html"),
+    ("/main.js", include_bytes!("ui/main.js"), "text/javascript"),
+    ("/style.css", include_bytes!("ui/style.css"), "text/css"),
+];
+
+struct Gain {
+    params: Arc<GainParams>,
+    /// The smoothed gain of the last processed sample, shown by the editor.
+    smoothed_gain: Arc<AtomicF32>,
+}
+
+#[derive(Params)]
+struct GainParams {
+    /// The editor's size, zoom and UI state, saved with the plugin's state.
+    #[persist = "editor-state"]
+    editor_state: Arc<WebviewState>,
+
+    #[id = "gain"]
+    gain: FloatParam,
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(GainParams::default()),
+            smoothed_gain: Arc::new(AtomicF32::new(1.0)),
+        }
+    }
+}
+
+impl Default for GainParams {
+    fn default() -> Self {
+        Self {
+            editor_state: WebviewState::new(420, 300),
+            gain: FloatParam::new(
+                "Gain",
+                util::db_to_gain(0.0),
+                FloatRange::Skewed {
+                    min: util::db_to_gain(-30.0),
+                    max: util::db_to_gain(30.0),
+                    factor: FloatRange::gain_skew_factor(-30.0, 30.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+            .with_string_to_value(formatters::s2v_f32_gain_to_db()),
+        }
+    }
+}
+
+/// Messages from the page, e.g. `{ "type": "resetGain" }`.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum PageMessage {
+    /// Sets the gain back to its default.
+    ResetGain,
+}
+
+/// Messages to the page.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum EditorMessage {
+    /// A file was dropped onto the drop zone.
+    FileLoaded { name: String },
+}
+
+/// Handles the page's messages. Parameter changes made with the slider don't
+/// pass through here, `window.plugin.params` takes care of them.
+struct GainEditor;
+
+impl EditorHandler for GainEditor {
+    type EditorTx = EditorMessage;
+    type EditorRx = PageMessage;
+
+    fn init(&mut self, _cx: &mut Context<Self>) {}
+
+    fn on_frame(&mut self, _cx: &mut Context<Self>) {}
+
+    fn on_message(&mut self, cx: &mut Context<Self>, message: PageMessage) {
+        match message {
+            PageMessage::ResetGain => {
+                let Some(info) = cx.param_info("gain") else { return };
+                // A single change is still a gesture to the host.
+                let changed = cx.begin_gesture_by_id("gain").and_then(|()| {
+                    cx.set_param_normalized_by_id("gain", info.default_normalized)?;
+                    cx.end_gesture_by_id("gain")
+                });
+                if let Err(err) = changed {
+                    nih_error!("Could not reset the gain: {err}");
+                }
+            }
+        }
+    }
+
+    fn on_page_load(&mut self, _cx: &mut Context<Self>, event: PageLoadEvent) {
+        if let PageLoadEvent::Finished { .. } = event {
+            nih_log!("The editor's page has loaded.");
+        }
+    }
+
+    fn on_file_drop(&mut self, cx: &mut Context<Self>, event: DropEvent) {
+        // Only drops onto the page's `data-plugin-dropzone="file"` element.
+        let DropEvent::Dropped { paths, zone: Some(zone), .. } = event else { return };
+        if zone != "file" {
+            return;
+        }
+
+        let Some(name) = paths.first().and_then(|path| path.file_name()) else { return };
+        let message = EditorMessage::FileLoaded { name: name.to_string_lossy().into_owned() };
+        if let Err(err) = cx.send_message(message) {
+            nih_error!("Could not report the dropped file: {err}");
+        }
+    }
+}
+
+/// Serves [`ASSETS`] over [`PROTOCOL`].
+fn serve_asset(request: Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
+    let path = match request.uri().path() {
+        "/" => "/index.html",
+        path => path,
+    };
+
+    match ASSETS.iter().find(|(asset, ..)| *asset == path) {
+        Some((_, body, mime)) => {
+            Response::builder().header(CONTENT_TYPE, *mime).body(Cow::Borrowed(*body)).unwrap()
+        }
+        None => Response::builder().status(404).body(Cow::Borrowed(&[][..])).unwrap(),
+    }
+}
+
+impl Plugin for Gain {
+    const NAME: &'static str = "Gain GUI (webview)";
+    const VENDOR: &'static str = "nih-plug-webview";
+    const URL: &'static str = "https://github.com/toiglak/nih-plug-webview";
+    const EMAIL: &'static str = "info@example.com";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+        ..AudioIOLayout::const_default()
+    }];
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        let source = WebviewSource::CustomProtocol {
+            url_path: "index.html".to_string(),
+            protocol: PROTOCOL.to_string(),
+        };
+        let editor = WebviewEditor::new_with_webview(
+            Self::NAME.to_string(),
+            source,
+            self.params.editor_state.clone(),
+            GainEditor,
+            None,
+            |builder| builder.with_custom_protocol(PROTOCOL.to_string(), serve_asset),
+        )
+        .with_config(WebviewConfig {
+            resize_policy: ResizePolicy::Free,
+            background_color: Some((24, 24, 27, 255)),
+            ..WebviewConfig::default()
+        })
+        .with_param_sync(self.params.clone())
+        .with_display_value("smoothedGain", self.smoothed_gain.clone(), 1e-4);
+
+        Some(Box::new(editor))
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        let mut gain = self.params.gain.value();
+        for channel_samples in buffer.iter_samples() {
+            gain = self.params.gain.smoothed.next();
+            for sample in channel_samples {
+                *sample *= gain;
+            }
+        }
+        self.smoothed_gain.store(gain, Ordering::Relaxed);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for Gain {
+    const CLAP_ID: &'static str = "com.github.nih-plug-webview.gain-gui";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("A gain plugin with a webview editor");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    const CLAP_FEATURES: &'static [ClapFeature] =
+        &[ClapFeature::AudioEffect, ClapFeature::Stereo, ClapFeature::Utility];
+}
+
+nih_export_clap!(Gain);
@@ -0,0 +1,89 @@
+// The editor's page. `window.plugin` is set up by nih_plug_webview before
+// this script runs.
+const plugin = window.plugin;
+
+const slider = document.getElementById("gain");
+const gainText = document.getElementById("gain-text");
+const meterFill = document.getElementById("meter-fill");
+const themeButton = document.getElementById("theme");
+
+// The gain slider, bound to the `gain` parameter. Dragging it is one
+// automation gesture, during which the host's updates don't move it.
+let dragging = false;
+plugin.params.subscribe("gain", (update) => {
+  if (!dragging) slider.value = update.normalized;
+  gainText.textContent = update.text;
+});
+
+slider.addEventListener("pointerdown", () => {
+  dragging = true;
+  plugin.params.beginGesture("gain");
+});
+slider.addEventListener("input", () => plugin.params.set("gain", Number(slider.value)));
+slider.addEventListener("pointerup", () => {
+  dragging = false;
+  plugin.params.endGesture("gain");
+});
+
+// The gain the audio thread actually applies, which glides to the slider's
+// value. Shown on a scale from -30 dB to +30 dB.
+plugin.displayValues.subscribe(({ id, value }) => {
+  if (id !== "smoothedGain" || value === null) return;
+  const db = 20 * Math.log10(Math.max(value, 1e-6));
+  const fraction = Math.min(Math.max((db + 30) / 60, 0), 1);
+  meterFill.style.width = `${fraction * 100}%`;
+});
+
+document.getElementById("reset").addEventListener("click", () => {
+  plugin.send({ type: "resetGain" });
+});
+
+// Files dropped onto the drop zone are loaded by the editor, which reports
+// back with the file's name.
+plugin.listenJson((message) => {
+  if (message.type === "fileLoaded") {
+    document.getElementById("file").textContent = message.name;
+  }
+});
+
+// The theme is saved with the plugin's state.
+const applyTheme = (theme) => {
+  document.documentElement.classList.toggle("light", theme === "light");
+  themeButton.textContent = theme === "light" ? "Dark" : "Light";
+};
+let uiState = { theme: "dark" };
+plugin.loadUiState().then((saved) => {
+  if (saved) uiState = saved;
+  applyTheme(uiState.theme);
+});
+themeButton.addEventListener("click", () => {
+  uiState = { ...uiState, theme: uiState.theme === "light" ? "dark" : "light" };
+  applyTheme(uiState.theme);
+  plugin.saveUiState(uiState);
+});
+
+// Resizes the window while the handle in the corner is dragged. Requests are
+// only sent once per animation frame.
+const handle = document.getElementById("resize-handle");
+let pendingSize = null;
+handle.addEventListener("pointerdown", (event) => {
+  handle.setPointerCapture(event.pointerId);
+});
+handle.addEventListener("pointermove", (event) => {
+  if (!handle.hasPointerCapture(event.pointerId)) return;
+  if (pendingSize === null) {
+    requestAnimationFrame(() => {
+      plugin.resizeWindow(pendingSize.width, pendingSize.height);
+      pendingSize = null;
+    });
+  }
+  pendingSize = {
+    width: Math.max(Math.round(event.clientX), 320),
+    height: Math.max(Math.round(event.clientY), 240),
+  };
+});
+
+// The slider is usable once every parameter value has arrived.
+plugin.onReady(() => {
+  slider.disabled = false;
+});
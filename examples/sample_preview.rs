@@ -0,0 +1,230 @@
+//! A pass-through plugin whose editor previews audio files dropped onto it.
+//!
+//! The dropped file is played by an `<audio>` element, which is served from
+//! disk over a protocol added with `WebviewEditor::with_async_protocol`. The
+//! file is read on a worker thread, so large files don't freeze the editor,
+//! and range requests are answered so that seeking works.
+//!
+//! Build it with `cargo build --example sample_preview` and load the
+//! resulting library as a CLAP plugin.
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use nih_plug::prelude::*;
+use nih_plug_webview::{
+    custom_protocol_url,
+    wry::http::{
+        header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, RANGE},
+        Request, Response,
+    },
+    Context, DropEvent, EditorHandler, WebviewEditor, WebviewSource, WebviewState,
+};
+use serde::Serialize;
+
+/// The protocol the dropped file is served over.
+const PROTOCOL: &str = "preview";
+
+/// The most bytes read for a single request. `<audio>` elements ask for the
+/// rest of the file and simply make another request for what's missing.
+const MAX_RANGE: u64 = 1024 * 1024;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <body style="margin: 0; font-family: sans-serif; background: #18181b; color: #e4e4e7">
+    <div data-plugin-dropzone="file"
+         style="margin: 16px; padding: 32px; border: 2px dashed #52525b; text-align: center">
+      <p id="name">Drop an audio file here</p>
+      <audio id="player" controls style="width: 100%"></audio>
+    </div>
+    <script>
+      window.plugin.listenJson((message) => {
+        if (message.type !== "preview") return;
+        document.getElementById("name").textContent = message.name;
+        document.getElementById("player").src = message.url;
+      });
+    </script>
+  </body>
+</html>"#;
+
+struct SamplePreview {
+    params: Arc<SamplePreviewParams>,
+}
+
+#[derive(Params)]
+struct SamplePreviewParams {
+    #[persist = "editor-state"]
+    editor_state: Arc<WebviewState>,
+}
+
+impl Default for SamplePreview {
+    fn default() -> Self {
+        Self { params: Arc::new(SamplePreviewParams { editor_state: WebviewState::new(480, 200) }) }
+    }
+}
+
+/// Messages to the page.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum EditorMessage {
+    /// A file was dropped and can be played from `url`.
+    Preview { name: String, url: String },
+}
+
+/// Remembers the dropped file for the protocol handler.
+struct PreviewEditor {
+    file: Arc<Mutex<Option<PathBuf>>>,
+    /// Changes the file's URL with every drop, so the webview doesn't play a
+    /// cached earlier file.
+    drops: u64,
+}
+
+impl EditorHandler for PreviewEditor {
+    type EditorTx = EditorMessage;
+    type EditorRx = ();
+
+    fn init(&mut self, _cx: &mut Context<Self>) {}
+
+    fn on_frame(&mut self, _cx: &mut Context<Self>) {}
+
+    fn on_message(&mut self, _cx: &mut Context<Self>, _message: ()) {}
+
+    fn on_file_drop(&mut self, cx: &mut Context<Self>, event: DropEvent) {
+        let DropEvent::Dropped { paths, .. } = event else { return };
+        let Some(path) = paths.into_iter().next() else { return };
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+            return;
+        };
+
+        *self.file.lock().unwrap() = Some(path);
+        self.drops += 1;
+        let url = custom_protocol_url(PROTOCOL, &self.drops.to_string());
+        if let Err(err) = cx.send_message(EditorMessage::Preview { name, url }) {
+            nih_error!("Could not show the dropped file: {err}");
+        }
+    }
+}
+
+/// Serves the requested range of the file at `path`.
+fn serve_range(path: &Path, range: Option<&str>) -> Response<Cow<'static, [u8]>> {
+    let respond = |status: u16| Response::builder().status(status);
+    let Ok(mut file) = File::open(path) else {
+        return respond(404).body(Cow::Borrowed(&[][..])).unwrap();
+    };
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    let response = respond(200).header(CONTENT_TYPE, mime.as_ref()).header(ACCEPT_RANGES, "bytes");
+
+    // Without a range, e.g. when the file is empty, the whole file is sent.
+    let Some((start, end)) = range.and_then(|range| parse_range(range, file_len(&file)?)) else {
+        let mut body = Vec::new();
+        return match file.read_to_end(&mut body) {
+            Ok(_) => response.body(Cow::Owned(body)).unwrap(),
+            Err(_) => respond(500).body(Cow::Borrowed(&[][..])).unwrap(),
+        };
+    };
+
+    let len = file_len(&file).unwrap_or(0);
+    if start > end || end >= len {
+        return respond(416)
+            .header(CONTENT_RANGE, format!("bytes */{len}"))
+            .body(Cow::Borrowed(&[][..]))
+            .unwrap();
+    }
+    let end = end.min(start + MAX_RANGE - 1);
+
+    let mut body = vec![0; (end + 1 - start) as usize];
+    if file.seek(SeekFrom::Start(start)).and_then(|_| file.read_exact(&mut body)).is_err() {
+        return respond(500).body(Cow::Borrowed(&[][..])).unwrap();
+    }
+    response
+        .status(206)
+        .header(CONTENT_RANGE, format!("bytes {start}-{end}/{len}"))
+        .body(Cow::Owned(body))
+        .unwrap()
+}
+
+/// The length of a non-empty file.
+fn file_len(file: &File) -> Option<u64> {
+    file.metadata().ok().map(|metadata| metadata.len()).filter(|&len| len > 0)
+}
+
+/// Parses `bytes=<start>-<end>`, `bytes=<start>-` or `bytes=-<suffix length>`
+/// into the first and last byte, for a file of `len > 0` bytes.
+fn parse_range(range: &str, len: u64) -> Option<(u64, u64)> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    match (start.trim().parse::<u64>().ok(), end.trim().parse::<u64>().ok()) {
+        (Some(start), end) => Some((start, end.unwrap_or(u64::MAX).min(len - 1))),
+        (None, Some(suffix)) => Some((len.saturating_sub(suffix), len - 1)),
+        (None, None) => None,
+    }
+}
+
+impl Plugin for SamplePreview {
+    const NAME: &'static str = "Sample Preview (webview)";
+    const VENDOR: &'static str = "nih-plug-webview";
+    const URL: &'static str = "https://github.com/toiglak/nih-plug-webview";
+    const EMAIL: &'static str = "info@example.com";
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+        ..AudioIOLayout::const_default()
+    }];
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        let file = Arc::new(Mutex::new(None::<PathBuf>));
+        let handler = PreviewEditor { file: file.clone(), drops: 0 };
+        let editor = WebviewEditor::new(
+            Self::NAME.to_string(),
+            WebviewSource::HTML(PAGE.to_string()),
+            self.params.editor_state.clone(),
+            handler,
+            None,
+        )
+        // Runs on the editor's thread, so only the request is looked at here.
+        .with_async_protocol(PROTOCOL, move |request: Request<Vec<u8>>| {
+            let path = file.lock().unwrap().clone();
+            let range = request.headers().get(RANGE).and_then(|range| range.to_str().ok());
+            let range = range.map(str::to_string);
+            move || match path {
+                Some(path) => serve_range(&path, range.as_deref()),
+                None => Response::builder().status(404).body(Cow::Borrowed(&[][..])).unwrap(),
+            }
+        });
+
+        Some(Box::new(editor))
+    }
+
+    fn process(
+        &mut self,
+        _buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for SamplePreview {
+    const CLAP_ID: &'static str = "com.github.nih-plug-webview.sample-preview";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("Previews audio files in a webview editor");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+    const CLAP_FEATURES: &'static [ClapFeature] =
+        &[ClapFeature::AudioEffect, ClapFeature::Stereo, ClapFeature::Utility];
+}
+
+nih_export_clap!(SamplePreview);
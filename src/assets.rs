@@ -0,0 +1,201 @@
+//! Bytes published by the editor for the page to load by URL, see
+//! [`Context::publish_asset`](crate::Context::publish_asset).
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use serde::Serialize;
+use wry::http::{
+    header::{HeaderValue, CONTENT_TYPE},
+    Request, Response, StatusCode,
+};
+
+use crate::custom_protocol_url;
+
+/// The URL an asset is served at, returned by
+/// [`Context::publish_asset`](crate::Context::publish_asset). Each
+/// publication gets a new URL, so the page doesn't show a cached earlier
+/// version. Serializes as a string, so it can be sent to the page as is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct AssetUrl(String);
+
+impl AssetUrl {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for AssetUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<AssetUrl> for String {
+    fn from(url: AssetUrl) -> String {
+        url.0
+    }
+}
+
+/// Returned when an asset can't be published.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AssetError {
+    /// Names may only contain ASCII letters, digits, `-`, `_`, `.` and `/`.
+    InvalidName(String),
+    /// The MIME type can't be sent in a `Content-Type` header, e.g. because
+    /// it contains a line break.
+    InvalidMime(String),
+    /// Publishing the asset would exceed
+    /// [`WebviewConfig::max_asset_bytes`](crate::WebviewConfig::max_asset_bytes)
+    /// with the assets already published.
+    TooLarge { size: usize, available: usize },
+}
+
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetError::InvalidName(name) => write!(f, "invalid asset name {name:?}"),
+            AssetError::InvalidMime(mime) => write!(f, "invalid asset MIME type {mime:?}"),
+            AssetError::TooLarge { size, available } => write!(
+                f,
+                "the asset has {size} bytes, but only {available} bytes are left for assets"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+struct Asset {
+    mime: HeaderValue,
+    bytes: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Assets {
+    by_name: HashMap<String, Asset>,
+    /// The sum of the assets' sizes.
+    total: usize,
+    /// Counts publications, to give each its own URL.
+    version: u64,
+}
+
+/// The published assets, kept for as long as the
+/// [`WebviewEditor`](crate::WebviewEditor) so they outlive the editor window.
+#[derive(Default)]
+pub(crate) struct AssetStore {
+    assets: Mutex<Assets>,
+}
+
+impl AssetStore {
+    /// Publishes `bytes` under `name`, replacing an earlier asset of that
+    /// name, without exceeding `max_bytes` in total.
+    pub fn publish(
+        &self,
+        name: &str,
+        mime: &str,
+        bytes: Vec<u8>,
+        max_bytes: usize,
+    ) -> Result<AssetUrl, AssetError> {
+        let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/');
+        if name.is_empty() || !name.chars().all(valid) {
+            return Err(AssetError::InvalidName(name.to_string()));
+        }
+        let mime =
+            HeaderValue::from_str(mime).map_err(|_| AssetError::InvalidMime(mime.to_string()))?;
+
+        let mut assets = self.assets.lock().unwrap_or_else(PoisonError::into_inner);
+        let replaced = assets.by_name.get(name).map_or(0, |asset| asset.bytes.len());
+        let available = max_bytes.saturating_sub(assets.total - replaced);
+        if bytes.len() > available {
+            return Err(AssetError::TooLarge { size: bytes.len(), available });
+        }
+
+        assets.total = assets.total - replaced + bytes.len();
+        assets.version += 1;
+        let version = assets.version;
+        assets.by_name.insert(name.to_string(), Asset { mime, bytes });
+        Ok(AssetUrl(custom_protocol_url(crate::ASSET_PROTOCOL, &format!("{name}?v={version}"))))
+    }
+
+    /// Removes the asset, returning whether there was one.
+    pub fn revoke(&self, name: &str) -> bool {
+        let mut assets = self.assets.lock().unwrap_or_else(PoisonError::into_inner);
+        match assets.by_name.remove(name) {
+            Some(asset) => {
+                assets.total -= asset.bytes.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Answers a request to the [`ASSET_PROTOCOL`](crate::ASSET_PROTOCOL).
+    pub fn serve(&self, request: Request<Vec<u8>>) -> Response<Cow<'static, [u8]>> {
+        let name = request.uri().path().trim_start_matches('/');
+        // The page is served from a different origin, and every publication
+        // has its own URL.
+        let response = Response::builder()
+            .header("Access-Control-Allow-Origin", "*")
+            .header("Cache-Control", "no-store");
+        let assets = self.assets.lock().unwrap_or_else(PoisonError::into_inner);
+        match assets.by_name.get(name) {
+            Some(asset) => response
+                .header(CONTENT_TYPE, asset.mime.clone())
+                .body(Cow::Owned(asset.bytes.clone())),
+            None => response.status(404).body(Cow::Borrowed(&[][..])),
+        }
+        .unwrap_or_else(|_| {
+            let mut response = Response::new(Cow::Borrowed(&[][..]));
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publish_rejects_invalid_names_and_mime_types() {
+        let store = AssetStore::default();
+        assert_eq!(
+            store.publish("wave form.png", "image/png", vec![0], 16),
+            Err(AssetError::InvalidName("wave form.png".to_string()))
+        );
+        assert_eq!(
+            store.publish("waveform.png", "image/png\n", vec![0], 16),
+            Err(AssetError::InvalidMime("image/png\n".to_string()))
+        );
+        assert!(store.publish("waveform.png", "image/png", vec![0], 16).is_ok());
+    }
+
+    #[test]
+    fn publish_counts_replaced_and_revoked_assets() {
+        let store = AssetStore::default();
+        assert!(store.publish("a", "image/png", vec![0; 10], 16).is_ok());
+        assert_eq!(
+            store.publish("b", "image/png", vec![0; 10], 16),
+            Err(AssetError::TooLarge { size: 10, available: 6 })
+        );
+        // Replacing `a` frees its bytes first.
+        assert!(store.publish("a", "image/png", vec![0; 16], 16).is_ok());
+        assert!(store.revoke("a"));
+        assert!(!store.revoke("a"));
+        assert!(store.publish("b", "image/png", vec![0; 16], 16).is_ok());
+    }
+
+    #[test]
+    fn each_publication_gets_a_new_url() {
+        let store = AssetStore::default();
+        let first = store.publish("a", "image/png", vec![0], 16).unwrap();
+        let second = store.publish("a", "image/png", vec![0], 16).unwrap();
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,73 @@
+//! A realtime-safe channel for sending values like meter levels or MIDI
+//! events from the audio thread to the editor.
+
+use std::{any::Any, sync::Arc};
+
+use crossbeam::queue::ArrayQueue;
+
+/// Creates a channel holding up to `capacity` values. The sender goes to the
+/// audio thread, the receiver is handed to
+/// [`WebviewEditor::with_editor_channel`](crate::WebviewEditor::with_editor_channel).
+///
+/// The received values are passed to
+/// [`EditorHandler::on_audio_message`](crate::EditorHandler::on_audio_message)
+/// on every frame. If the editor falls behind, the oldest values are
+/// overwritten. Values sent while the editor is closed are discarded when it
+/// opens.
+pub fn editor_channel<T: Send + 'static>(capacity: usize) -> (EditorSender<T>, EditorReceiver<T>) {
+    let queue = Arc::new(ArrayQueue::new(capacity.max(1)));
+    (EditorSender { queue: queue.clone() }, EditorReceiver { queue })
+}
+
+/// The sending end of an [`editor_channel`]. Sending never blocks or
+/// allocates, so this can be used from the audio thread.
+pub struct EditorSender<T> {
+    queue: Arc<ArrayQueue<T>>,
+}
+
+impl<T> Clone for EditorSender<T> {
+    fn clone(&self) -> Self {
+        EditorSender { queue: self.queue.clone() }
+    }
+}
+
+impl<T: Send> EditorSender<T> {
+    /// Sends a value to the editor. If the channel is full, the oldest value
+    /// is dropped to make room and `false` is returned.
+    pub fn send(&self, value: T) -> bool {
+        self.queue.force_push(value).is_none()
+    }
+}
+
+/// The receiving end of an [`editor_channel`].
+pub struct EditorReceiver<T> {
+    queue: Arc<ArrayQueue<T>>,
+}
+
+/// An [`EditorReceiver`] with the value type erased.
+pub(crate) trait AudioQueue: Send + Sync {
+    /// Passes every queued value to `f`.
+    fn drain(&self, f: &mut dyn FnMut(AudioMessage));
+    /// Drops every queued value.
+    fn clear(&self);
+}
+
+/// A value received from an [`editor_channel`], see
+/// [`EditorHandler::on_audio_message`](crate::EditorHandler::on_audio_message).
+pub type AudioMessage = Box<dyn Any + Send>;
+
+impl<T: Send + 'static> AudioQueue for EditorReceiver<T> {
+    fn drain(&self, f: &mut dyn FnMut(AudioMessage)) {
+        // Bounded so that a sender outpacing the editor can't stall it.
+        for _ in 0..self.queue.capacity() {
+            match self.queue.pop() {
+                Some(value) => f(Box::new(value)),
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&self) {
+        while self.queue.pop().is_some() {}
+    }
+}
@@ -0,0 +1,224 @@
+//! Splitting large messages between the editor and `lib.js` into chunks.
+//!
+//! None of the webviews document how large an IPC message or an evaluated
+//! script may be, and very large ones have been seen to fail intermittently.
+//! So messages larger than [`CHUNK_SIZE`] are sent in numbered chunks
+//! carrying a transfer id, in both directions, and reassembled before they
+//! are handled.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use nih_plug::nih_warn;
+
+/// The largest message the editor sends in one piece, in bytes. `lib.js`
+/// uses the same limit in UTF-16 code units for its own messages.
+pub(crate) const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Transfers without a new chunk for this long are discarded.
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Splits `message` into pieces of at most [`CHUNK_SIZE`] bytes, without
+/// splitting any characters.
+pub(crate) fn split(message: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = message;
+    while rest.len() > CHUNK_SIZE {
+        let mut end = CHUNK_SIZE;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks.push(rest);
+    chunks
+}
+
+struct Transfer {
+    chunks: Vec<Option<String>>,
+    received: usize,
+    last_chunk: Instant,
+}
+
+/// Reassembles the chunked messages posted by `lib.js`.
+pub(crate) struct Reassembler {
+    transfers: HashMap<u64, Transfer>,
+    /// The bytes held by all transfers.
+    size: usize,
+    max_size: usize,
+}
+
+impl Reassembler {
+    pub fn new(max_size: usize) -> Reassembler {
+        Reassembler { transfers: HashMap::new(), size: 0, max_size }
+    }
+
+    /// Adds a chunk, returning the whole message once its last chunk arrived.
+    pub fn push(&mut self, id: u64, index: usize, count: usize, data: String) -> Option<String> {
+        self.expire();
+
+        if index >= count {
+            nih_warn!("Ignoring chunk {index} of a message with {count} chunks.");
+            return None;
+        }
+        // Every chunk but the last is close to `CHUNK_SIZE`, so this bounds
+        // the message before any chunk was stored.
+        let too_large = count.saturating_sub(1).saturating_mul(CHUNK_SIZE / 2) > self.max_size;
+        if too_large || self.size + data.len() > self.max_size {
+            nih_warn!(
+                "Discarding a chunked message from the webview, as it exceeds \
+                 `WebviewConfig::max_message_size`."
+            );
+            self.remove(id);
+            return None;
+        }
+
+        let transfer = self.transfers.entry(id).or_insert_with(|| Transfer {
+            chunks: vec![None; count],
+            received: 0,
+            last_chunk: Instant::now(),
+        });
+        if transfer.chunks.len() != count || transfer.chunks[index].is_some() {
+            nih_warn!("Ignoring an inconsistent chunk of message {id}.");
+            return None;
+        }
+        self.size += data.len();
+        transfer.chunks[index] = Some(data);
+        transfer.received += 1;
+        transfer.last_chunk = Instant::now();
+
+        if transfer.received < count {
+            return None;
+        }
+        let transfer = self.remove(id)?;
+        Some(transfer.chunks.into_iter().flatten().collect())
+    }
+
+    /// Discards the transfers which have been abandoned.
+    pub fn expire(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<u64> = self
+            .transfers
+            .iter()
+            .filter(|(_, transfer)| now - transfer.last_chunk > TRANSFER_TIMEOUT)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in expired {
+            nih_warn!("Discarding the incomplete chunked message {id}.");
+            self.remove(id);
+        }
+    }
+
+    fn remove(&mut self, id: u64) -> Option<Transfer> {
+        let transfer = self.transfers.remove(&id)?;
+        self.size -= transfer.chunks.iter().flatten().map(String::len).sum::<usize>();
+        Some(transfer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A message of 3-byte characters, so that `CHUNK_SIZE` falls inside one.
+    fn multibyte_message(len: usize) -> String {
+        "€".repeat(len / "€".len()) + "🎛"
+    }
+
+    fn push_all(reassembler: &mut Reassembler, id: u64, chunks: &[&str]) -> Option<String> {
+        let order: Vec<usize> = (0..chunks.len()).rev().collect();
+        push_in_order(reassembler, id, chunks, &order)
+    }
+
+    fn push_in_order(
+        reassembler: &mut Reassembler,
+        id: u64,
+        chunks: &[&str],
+        order: &[usize],
+    ) -> Option<String> {
+        let mut message = None;
+        for &i in order {
+            assert!(message.is_none(), "complete before chunk {i}");
+            message = reassembler.push(id, i, chunks.len(), chunks[i].to_string());
+        }
+        message
+    }
+
+    #[test]
+    fn splits_without_breaking_characters() {
+        assert_eq!(split(""), [""]);
+        assert_eq!(split("short"), ["short"]);
+
+        for len in [CHUNK_SIZE - 1, CHUNK_SIZE, CHUNK_SIZE + 1, 3 * CHUNK_SIZE + 2] {
+            let message = multibyte_message(len);
+            let chunks = split(&message);
+            assert!(chunks.iter().all(|chunk| chunk.len() <= CHUNK_SIZE));
+            assert!(chunks.iter().all(|chunk| !chunk.is_empty()));
+            assert_eq!(chunks.concat(), message);
+        }
+    }
+
+    #[test]
+    fn reassembles_chunks_in_any_order() {
+        let message = multibyte_message(3 * CHUNK_SIZE + 2);
+        let chunks = split(&message);
+        assert_eq!(chunks.len(), 4);
+
+        let mut reassembler = Reassembler::new(usize::MAX);
+        for (id, order) in [[0, 1, 2, 3], [3, 2, 1, 0], [2, 0, 3, 1]].iter().enumerate() {
+            let received = push_in_order(&mut reassembler, id as u64, &chunks, order);
+            assert_eq!(received.as_deref(), Some(message.as_str()));
+        }
+        assert_eq!(reassembler.size, 0);
+    }
+
+    #[test]
+    fn interleaved_transfers_are_kept_apart() {
+        let (a, b) = (multibyte_message(2 * CHUNK_SIZE), "b".repeat(2 * CHUNK_SIZE + 1));
+        let (a_chunks, b_chunks) = (split(&a), split(&b));
+        let mut reassembler = Reassembler::new(usize::MAX);
+
+        assert_eq!(reassembler.push(1, 0, a_chunks.len(), a_chunks[0].to_string()), None);
+        assert_eq!(push_all(&mut reassembler, 2, &b_chunks), Some(b));
+        assert_eq!(reassembler.push(1, 1, a_chunks.len(), a_chunks[1].to_string()), None);
+        assert_eq!(reassembler.push(1, 2, a_chunks.len(), a_chunks[2].to_string()), Some(a));
+    }
+
+    #[test]
+    fn ignores_duplicate_and_inconsistent_chunks() {
+        let mut reassembler = Reassembler::new(usize::MAX);
+        assert_eq!(reassembler.push(1, 0, 2, "ab".to_string()), None);
+        // A duplicate neither completes nor replaces the first chunk.
+        assert_eq!(reassembler.push(1, 0, 2, "xx".to_string()), None);
+        // Neither does a chunk claiming another count.
+        assert_eq!(reassembler.push(1, 1, 3, "xx".to_string()), None);
+        assert_eq!(reassembler.push(1, 2, 2, "xx".to_string()), None);
+        assert_eq!(reassembler.size, 2);
+
+        assert_eq!(reassembler.push(1, 1, 2, "cd".to_string()), Some("abcd".to_string()));
+    }
+
+    #[test]
+    fn discards_messages_exceeding_the_limit() {
+        let mut reassembler = Reassembler::new(10);
+        // Too many chunks for the limit, before any were stored.
+        assert_eq!(reassembler.push(1, 0, 1000, "a".to_string()), None);
+        assert_eq!(reassembler.push(1, 0, usize::MAX, "a".to_string()), None);
+        assert_eq!(reassembler.push(1, 0, (1 << 47) + 1, "a".to_string()), None);
+        assert!(reassembler.transfers.is_empty());
+
+        assert_eq!(reassembler.push(2, 0, 3, "aaaa".to_string()), None);
+        assert_eq!(reassembler.push(2, 1, 3, "bbbb".to_string()), None);
+        // The last chunk would exceed it, so the whole transfer is dropped.
+        assert_eq!(reassembler.push(2, 2, 3, "ccc".to_string()), None);
+        assert!(reassembler.transfers.is_empty());
+        assert_eq!(reassembler.size, 0);
+
+        // Later messages within the limit still arrive.
+        assert_eq!(reassembler.push(3, 0, 1, "fits".to_string()), Some("fits".to_string()));
+    }
+}
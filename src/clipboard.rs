@@ -0,0 +1,97 @@
+//! The system clipboard, see
+//! [`Context::clipboard_write_text`](crate::Context::clipboard_write_text).
+//! Requires the `clipboard` feature.
+
+use std::cell::RefCell;
+
+use crate::workdir::random_id;
+
+/// Opened on first use and kept for as long as the editor window is open, as
+/// text copied on X11 is only available while the clipboard is.
+#[derive(Default)]
+pub(crate) struct Clipboard {
+    inner: RefCell<Option<arboard::Clipboard>>,
+}
+
+impl Clipboard {
+    fn with<R>(
+        &self,
+        f: impl FnOnce(&mut arboard::Clipboard) -> Result<R, arboard::Error>,
+    ) -> Result<R, arboard::Error> {
+        let mut inner = self.inner.borrow_mut();
+        let mut clipboard = match inner.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new()?,
+        };
+        let result = f(&mut clipboard);
+        *inner = Some(clipboard);
+        result
+    }
+
+    pub fn write_text(&self, text: &str) -> Result<(), String> {
+        self.with(|clipboard| clipboard.set_text(text)).map_err(|err| err.to_string())
+    }
+
+    /// `None` if the clipboard is empty or holds something other than text.
+    pub fn read_text(&self) -> Result<Option<String>, String> {
+        match self.with(|clipboard| clipboard.get_text()) {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+/// Tells reads by `window.plugin.clipboard.readText` apart from ones the page
+/// posts to `window.ipc` itself, as remote content could otherwise read the
+/// clipboard without any user input. `lib.js` gets the key through its
+/// initialization script, which the page can't read, and only sends it once
+/// per trusted click or key press.
+pub(crate) struct ReadGate {
+    key: u64,
+}
+
+impl Default for ReadGate {
+    fn default() -> ReadGate {
+        // Kept within the integers a JS number holds exactly.
+        ReadGate { key: random_id() >> 11 }
+    }
+}
+
+impl ReadGate {
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Whether a `clipboardRead` request carrying `key` came from `lib.js`.
+    pub fn admits(&self, key: Option<u64>) -> bool {
+        key == Some(self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::WebviewEvent;
+
+    #[test]
+    fn reads_need_the_key() {
+        let gate = ReadGate::default();
+        assert!(gate.key() < 1 << 53);
+        assert!(gate.admits(Some(gate.key())));
+        assert!(!gate.admits(Some(gate.key() ^ 1)));
+        assert!(!gate.admits(None));
+    }
+
+    #[test]
+    fn activity_does_not_open_the_gate() {
+        let gate = ReadGate::default();
+        // What remote content can post through `window.ipc` without any user
+        // input.
+        let activity = WebviewEvent::parse(r#"{"t":"activity"}"#).unwrap();
+        assert_eq!(activity, WebviewEvent::Activity);
+        let read = WebviewEvent::parse(r#"{"t":"clipboardRead","payload":{"id":1}}"#).unwrap();
+        let WebviewEvent::ClipboardRead { id: 1, key } = read else { panic!("{read:?}") };
+        assert!(!gate.admits(key));
+    }
+}
@@ -0,0 +1,187 @@
+//! Facts about the editor for bug reports, see
+//! [`Context::diagnostics_report`](crate::Context::diagnostics_report) and
+//! [`Context::open_diagnostics`](crate::Context::open_diagnostics).
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{ConsoleLevel, HostInfo};
+
+/// The number of console messages kept for the report.
+const CONSOLE_HISTORY: usize = 200;
+
+/// The version of wry the crate is built against, which it can't look up at
+/// runtime. Must match `Cargo.toml`.
+const WRY_VERSION: &str = "0.35.2";
+
+/// The webview implementation wry uses on this platform.
+const WEBVIEW: &str = if cfg!(target_os = "windows") {
+    "WebView2"
+} else if cfg!(target_os = "macos") {
+    "WKWebView"
+} else {
+    "WebKitGTK"
+};
+
+#[derive(Clone, Serialize)]
+pub(crate) struct ConsoleLine {
+    level: ConsoleLevel,
+    message: String,
+}
+
+/// The latest console messages from the page, including the ones which were
+/// not logged because too many arrived at once.
+#[derive(Default)]
+pub(crate) struct ConsoleHistory {
+    lines: RefCell<VecDeque<ConsoleLine>>,
+}
+
+impl ConsoleHistory {
+    pub fn push(&self, level: ConsoleLevel, message: &str) {
+        let mut lines = self.lines.borrow_mut();
+        if lines.len() == CONSOLE_HISTORY {
+            lines.pop_front();
+        }
+        lines.push_back(ConsoleLine { level, message: message.to_string() });
+    }
+}
+
+/// The facts the report is made of, gathered by the editor window.
+pub(crate) struct Facts<'a> {
+    pub host: HostInfo,
+    pub user_agent: Option<String>,
+    pub gpu_acceleration: String,
+    pub workdir: Option<&'a Path>,
+    pub last_error: Option<String>,
+    pub console: &'a ConsoleHistory,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report<'a> {
+    crate_version: &'static str,
+    wry_version: &'static str,
+    webview: &'static str,
+    user_agent: Option<String>,
+    gpu_acceleration: String,
+    host: HostInfo,
+    workdir: Option<PathBuf>,
+    workdir_writable: bool,
+    last_error: Option<String>,
+    console: &'a VecDeque<ConsoleLine>,
+}
+
+/// Returns the report as pretty-printed JSON.
+pub(crate) fn report(facts: Facts) -> String {
+    let report = Report {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        wry_version: WRY_VERSION,
+        webview: WEBVIEW,
+        user_agent: facts.user_agent,
+        gpu_acceleration: facts.gpu_acceleration,
+        host: facts.host,
+        workdir: facts.workdir.map(Path::to_path_buf),
+        workdir_writable: facts.workdir.is_some_and(is_writable),
+        last_error: facts.last_error,
+        console: &facts.console.lines.borrow(),
+    };
+    serde_json::to_string_pretty(&report).expect("Can't convert JSON to string.")
+}
+
+/// Whether a file can be created in `dir`.
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(".nih-plug-webview-probe");
+    let writable = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    writable
+}
+
+/// The page showing `report`. It posts `"close"` when it's dismissed.
+pub(crate) fn page(report: &str) -> String {
+    // The report can't end the script it's embedded in.
+    let report = report.replace("</", "<\\/");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+  body {{ margin: 0; padding: 16px; font: 13px sans-serif; background: #1e1e1e; color: #ddd; }}
+  h1 {{ font-size: 16px; margin: 0 0 12px; }}
+  table {{ border-collapse: collapse; margin-bottom: 12px; }}
+  td {{ padding: 2px 12px 2px 0; vertical-align: top; word-break: break-all; }}
+  td:first-child {{ color: #999; white-space: nowrap; }}
+  pre {{ max-height: 40vh; overflow: auto; background: #111; padding: 8px; white-space: pre-wrap; }}
+  .warn {{ color: #e5c07b; }} .error {{ color: #e06c75; }}
+  button {{ margin-right: 8px; }}
+</style>
+</head>
+<body>
+<h1>Diagnostics</h1>
+<table id="facts"></table>
+<pre id="console"></pre>
+<button id="copy">Copy report</button>
+<button id="close">Close</button>
+<script>
+  const report = {report};
+  try {{
+    const gl = document.createElement("canvas").getContext("webgl");
+    const info = gl && gl.getExtension("WEBGL_debug_renderer_info");
+    report.renderer = info ? gl.getParameter(info.UNMASKED_RENDERER_WEBGL) : null;
+  }} catch (_) {{
+    report.renderer = null;
+  }}
+
+  const facts = [
+    ["Crate version", report.crateVersion],
+    ["wry version", report.wryVersion],
+    ["Webview", report.webview],
+    ["User agent", report.userAgent],
+    ["Renderer", report.renderer],
+    ["GPU acceleration", report.gpuAcceleration],
+    ["Plugin API", report.host.pluginApi],
+    ["OS", report.host.os],
+    ["Data directory", report.workdir],
+    ["Writable", String(report.workdirWritable)],
+    ["Last error", report.lastError],
+  ];
+  const table = document.getElementById("facts");
+  for (const [name, value] of facts) {{
+    const row = table.insertRow();
+    row.insertCell().textContent = name;
+    row.insertCell().textContent = value === null || value === undefined ? "-" : value;
+  }}
+
+  const consoleLines = document.getElementById("console");
+  if (report.console.length === 0) consoleLines.textContent = "No console messages captured.";
+  for (const line of report.console) {{
+    const span = document.createElement("span");
+    span.className = line.level;
+    span.textContent = `[${{line.level}}] ${{line.message}}\n`;
+    consoleLines.appendChild(span);
+  }}
+
+  document.getElementById("copy").addEventListener("click", () => {{
+    const text = JSON.stringify(report, null, 2);
+    const fallback = () => {{
+      const area = document.createElement("textarea");
+      area.value = text;
+      document.body.appendChild(area);
+      area.select();
+      document.execCommand("copy");
+      area.remove();
+    }};
+    if (navigator.clipboard) navigator.clipboard.writeText(text).catch(fallback);
+    else fallback();
+  }});
+  document.getElementById("close").addEventListener("click", () => window.ipc.postMessage("close"));
+</script>
+</body>
+</html>"#
+    )
+}
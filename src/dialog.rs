@@ -0,0 +1,123 @@
+//! Native file dialogs, see [`Context::pick_file`](crate::Context::pick_file).
+//! Requires the `file-dialog` feature.
+
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Poll, Wake, Waker},
+};
+
+use baseview::Window;
+use serde::Deserialize;
+
+/// Options for [`Context::pick_file`](crate::Context::pick_file). Also
+/// accepted by `window.plugin.pickFile(options)`, with the fields in
+/// camelCase.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FileDialogOptions {
+    /// Whether to open, save or pick a folder.
+    pub mode: FileDialogMode,
+    /// Allows selecting multiple files. Only applies to
+    /// [`FileDialogMode::Open`].
+    pub multiple: bool,
+    /// The file types to offer, e.g. `"Audio"` with `["wav", "flac"]`.
+    pub filters: Vec<FileFilter>,
+    /// The directory the dialog starts in.
+    pub directory: Option<PathBuf>,
+    /// The file name suggested by a save dialog.
+    pub file_name: Option<String>,
+    /// The dialog's title.
+    pub title: Option<String>,
+}
+
+/// See [`FileDialogOptions::mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileDialogMode {
+    #[default]
+    Open,
+    Save,
+    Folder,
+}
+
+/// See [`FileDialogOptions::filters`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+type DialogFuture = Pin<Box<dyn Future<Output = Option<Vec<PathBuf>>> + Send>>;
+
+/// Opens a dialog parented to `window` and calls `on_result` from a background
+/// thread once it's closed, with `None` if it was cancelled.
+pub(crate) fn open(
+    window: &Window,
+    options: FileDialogOptions,
+    on_result: impl FnOnce(Option<Vec<PathBuf>>) + Send + 'static,
+) {
+    let mut dialog = rfd::AsyncFileDialog::new().set_parent(window);
+    for filter in &options.filters {
+        dialog = dialog.add_filter(&filter.name, &filter.extensions);
+    }
+    if let Some(directory) = &options.directory {
+        dialog = dialog.set_directory(directory);
+    }
+    if let Some(file_name) = options.file_name {
+        dialog = dialog.set_file_name(file_name);
+    }
+    if let Some(title) = options.title {
+        dialog = dialog.set_title(title);
+    }
+
+    // The dialogs are created here, as macOS requires that to happen on the
+    // main thread. Only waiting for the result happens in the background.
+    let single = |handle: Option<rfd::FileHandle>| handle.map(|h| vec![h.path().to_path_buf()]);
+    let future: DialogFuture = match (options.mode, options.multiple) {
+        (FileDialogMode::Open, false) => {
+            let future = dialog.pick_file();
+            Box::pin(async move { single(future.await) })
+        }
+        (FileDialogMode::Open, true) => {
+            let future = dialog.pick_files();
+            Box::pin(async move {
+                let handles = future.await?;
+                Some(handles.iter().map(|h| h.path().to_path_buf()).collect())
+            })
+        }
+        (FileDialogMode::Save, _) => {
+            let future = dialog.save_file();
+            Box::pin(async move { single(future.await) })
+        }
+        (FileDialogMode::Folder, _) => {
+            let future = dialog.pick_folder();
+            Box::pin(async move { single(future.await) })
+        }
+    };
+
+    std::thread::spawn(move || on_result(block_on(future)));
+}
+
+/// Runs a future to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = std::task::Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Values written by the audio thread and shown by the editor, like a
+//! parameter's smoothed value, see
+//! [`WebviewEditor::with_display_value`](crate::WebviewEditor::with_display_value).
+
+use std::{
+    cell::Cell,
+    sync::{atomic::Ordering, Arc},
+};
+
+use atomic_float::AtomicF32;
+use serde::Serialize;
+
+/// A value added with
+/// [`WebviewEditor::with_display_value`](crate::WebviewEditor::with_display_value).
+pub(crate) struct DisplayTap {
+    pub id: String,
+    pub value: Arc<AtomicF32>,
+    /// Changes up to this size are not reported.
+    pub epsilon: f32,
+}
+
+/// A changed value, as sent to `lib.js`.
+#[derive(Serialize)]
+pub(crate) struct DisplayUpdate<'a> {
+    id: &'a str,
+    value: f32,
+}
+
+/// The values last reported for an editor window's taps, in the same order.
+pub(crate) struct DisplayValues {
+    last: Vec<Cell<f32>>,
+}
+
+impl DisplayValues {
+    pub fn new(taps: &[DisplayTap]) -> DisplayValues {
+        DisplayValues { last: taps.iter().map(|_| Cell::new(f32::NAN)).collect() }
+    }
+
+    /// Reads every tap and returns the ones which changed by more than their
+    /// epsilon since they were last reported. Only allocates if one changed.
+    pub fn sample<'a>(&self, taps: &'a [DisplayTap]) -> Vec<DisplayUpdate<'a>> {
+        let mut updates = Vec::new();
+        for (tap, last) in taps.iter().zip(&self.last) {
+            let value = tap.value.load(Ordering::Relaxed);
+            let changed =
+                value.is_nan() != last.get().is_nan() || (value - last.get()).abs() > tap.epsilon;
+            if changed {
+                last.set(value);
+                updates.push(DisplayUpdate { id: &tap.id, value });
+            }
+        }
+        updates
+    }
+
+    /// The value last reported for the tap `id`.
+    pub fn get(&self, taps: &[DisplayTap], id: &str) -> Option<f32> {
+        let index = taps.iter().position(|tap| tap.id == id)?;
+        Some(self.last[index].get()).filter(|value| !value.is_nan())
+    }
+
+    /// Reports every value again on the next frame, e.g. after the page
+    /// reloaded.
+    pub fn reset(&self) {
+        self.last.iter().for_each(|last| last.set(f32::NAN));
+    }
+}
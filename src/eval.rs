@@ -0,0 +1,178 @@
+//! Evaluating scripts with their results passed back to Rust, see
+//! [`Context::eval_with_result`](crate::Context::eval_with_result).
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+};
+
+use crossbeam::channel::{Receiver, Sender};
+use serde::Deserialize;
+use wry::WebView;
+
+/// Returned when a script evaluated with
+/// [`Context::eval_with_result`](crate::Context::eval_with_result) fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EvalError {
+    /// The script threw, with the stringified exception.
+    Exception(String),
+    /// The webview could not be constructed.
+    NoWebview,
+    /// The editor was closed before the result arrived.
+    Closed,
+    /// The webview returned something which is not a valid result.
+    InvalidResult(String),
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::Exception(err) => write!(f, "the script threw: {err}"),
+            EvalError::NoWebview => write!(f, "there is no webview to evaluate the script in"),
+            EvalError::Closed => write!(f, "the editor was closed before the script finished"),
+            EvalError::InvalidResult(result) => write!(f, "invalid script result: {result}"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+type EvalCallback = Box<dyn FnOnce(Result<String, EvalError>)>;
+
+/// The callbacks of scripts whose results haven't arrived yet. The webview
+/// reports results from whichever thread it likes, so they're passed through a
+/// channel and the callbacks are only called from [`PendingEvals::dispatch`]
+/// on the editor thread.
+pub(crate) struct PendingEvals {
+    callbacks: RefCell<HashMap<u64, EvalCallback>>,
+    next_id: Cell<u64>,
+    results: (Sender<(u64, String)>, Receiver<(u64, String)>),
+}
+
+impl PendingEvals {
+    pub fn new() -> PendingEvals {
+        PendingEvals {
+            callbacks: RefCell::new(HashMap::new()),
+            next_id: Cell::new(0),
+            results: crossbeam::channel::unbounded(),
+        }
+    }
+
+    /// Evaluates `script` and calls `callback` with its result once it arrives.
+    pub fn eval(&self, webview: Option<&WebView>, script: &str, callback: EvalCallback) {
+        let Some(webview) = webview else {
+            callback(Err(EvalError::NoWebview));
+            return;
+        };
+
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.callbacks.borrow_mut().insert(id, callback);
+
+        let results = self.results.0.clone();
+        let evaluated =
+            webview.evaluate_script_with_callback(&wrap_script(script), move |result| {
+                let _ = results.send((id, result));
+            });
+        if let Err(err) = evaluated {
+            if let Some(callback) = self.callbacks.borrow_mut().remove(&id) {
+                callback(Err(EvalError::InvalidResult(err.to_string())));
+            }
+        }
+    }
+
+    /// Calls the callbacks of the scripts whose results have arrived.
+    pub fn dispatch(&self) {
+        while let Ok((id, result)) = self.results.1.try_recv() {
+            // Removed before calling it so the callback can evaluate scripts
+            // of its own.
+            let callback = self.callbacks.borrow_mut().remove(&id);
+            if let Some(callback) = callback {
+                callback(parse_result(&result));
+            }
+        }
+    }
+}
+
+impl Drop for PendingEvals {
+    fn drop(&mut self) {
+        for (_, callback) in self.callbacks.get_mut().drain() {
+            callback(Err(EvalError::Closed));
+        }
+    }
+}
+
+/// Wraps a script so that its completion value, or the exception it throws,
+/// is returned as a JSON string. The webviews only report the former and
+/// differ in how they encode it.
+fn wrap_script(script: &str) -> String {
+    let script = serde_json::to_string(script).expect("Can't convert JSON to string.");
+    format!(
+        "(() => {{ try {{ const value = (0, eval)({script}); \
+         return JSON.stringify({{ ok: value === undefined ? null : value }}); }} \
+         catch (err) {{ return JSON.stringify({{ err: String(err) }}); }} }})()"
+    )
+}
+
+/// Parses the result of a script wrapped with [`wrap_script`] into the JSON
+/// of its completion value.
+fn parse_result(result: &str) -> Result<String, EvalError> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    enum Outcome {
+        Ok(serde_json::Value),
+        Err(String),
+    }
+
+    // The webview encodes the returned string as JSON once more.
+    let outcome = serde_json::from_str::<String>(result)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Outcome>(&json).ok());
+    match outcome {
+        Some(Outcome::Ok(value)) => Ok(value.to_string()),
+        Some(Outcome::Err(err)) => Err(EvalError::Exception(err)),
+        None => Err(EvalError::InvalidResult(result.to_string())),
+    }
+}
+
+/// The result of [`Context::eval_future`](crate::Context::eval_future), for
+/// awaiting it from another thread.
+pub struct EvalFuture {
+    pub(crate) state: Arc<Mutex<EvalState>>,
+}
+
+#[derive(Default)]
+pub(crate) struct EvalState {
+    pub(crate) result: Option<Result<String, EvalError>>,
+    pub(crate) waker: Option<Waker>,
+}
+
+impl EvalState {
+    pub(crate) fn resolve(state: &Mutex<EvalState>, result: Result<String, EvalError>) {
+        let mut state = state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Future for EvalFuture {
+    type Output = Result<String, EvalError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+//! The messages `lib.js` posts to the editor, see [`WebviewEvent`].
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::MESSAGE_VERB;
+
+/// A message posted by `lib.js`, sent as a `{ "t": verb, "payload": ... }`
+/// envelope. The editor parses every message from the page with
+/// [`WebviewEvent::parse`], which can also be used by IPC handlers layered on
+/// top of the crate's own, e.g. in [`WebviewEditor::new_with_webview`](crate::WebviewEditor::new_with_webview).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "t", content = "payload", rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum WebviewEvent {
+    /// `window.plugin.send(message)`, for
+    /// [`EditorHandler::on_message`](crate::EditorHandler::on_message).
+    /// `undefined` arrives as `null`.
+    #[serde(skip)]
+    Message(Value),
+    /// Sent once when `lib.js` is loaded, with the webview's
+    /// `navigator.userAgent` and the decimal separator of its locale.
+    #[serde(rename_all = "camelCase")]
+    Hello {
+        version: u32,
+        #[serde(default)]
+        user_agent: Option<String>,
+        #[serde(default)]
+        decimal_separator: Option<char>,
+    },
+    /// The answer to the editor checking that `lib.js` runs in a page which
+    /// finished loading.
+    BridgeProbe,
+    /// `window.plugin.resizeWindow(width, height)`. Non-finite numbers arrive as
+    /// `null`, hence the `Option`s.
+    Resize { id: u64, width: Option<f64>, height: Option<f64> },
+    /// `window.plugin.params.set(id, normalized)`.
+    SetParam { id: String, normalized: f32 },
+    /// `window.plugin.params.beginGesture(id)`.
+    BeginGesture { id: String },
+    /// `window.plugin.params.endGesture(id)`.
+    EndGesture { id: String },
+    /// `window.plugin.params.verifyManifest(hash)`.
+    VerifyParamManifest { id: u64, hash: String },
+    /// A key event the page did not handle.
+    #[serde(rename_all = "camelCase")]
+    Key {
+        down: bool,
+        key: String,
+        code: String,
+        key_code: u32,
+        repeat: bool,
+        shift: bool,
+        ctrl: bool,
+        alt: bool,
+        meta: bool,
+    },
+    /// `window.plugin.pickFile(options)`. The options are only parsed with
+    /// the `file-dialog` feature.
+    PickFile {
+        id: u64,
+        #[serde(default)]
+        options: Value,
+    },
+    /// `window.plugin.fetch(url, options)`. The request is only parsed with
+    /// the `http` feature.
+    Fetch {
+        id: u64,
+        #[serde(default)]
+        request: Value,
+    },
+    /// `window.plugin.presets.saveToFile(name)`.
+    SavePreset { id: u64, name: String },
+    /// `window.plugin.presets.loadFromFile()`.
+    LoadPreset { id: u64 },
+    /// `window.plugin.channel(name).send(message)`.
+    Channel {
+        name: String,
+        #[serde(default)]
+        message: Value,
+    },
+    /// `window.plugin.peers.broadcast(message)`.
+    PeerBroadcast { message: String },
+    /// A right-click the page did not handle.
+    ContextMenu { x: f64, y: f64, element: String },
+    /// `window.plugin.saveUiState(value)`. `undefined` arrives as a missing
+    /// field.
+    SaveUiState {
+        #[serde(default)]
+        value: Value,
+    },
+    /// `window.plugin.loadUiState()`.
+    LoadUiState { id: u64 },
+    /// `window.plugin.clipboard.writeText(text)`.
+    ClipboardWrite { id: u64, text: String },
+    /// `window.plugin.clipboard.readText()`. `key` is only known to `lib.js`,
+    /// which sends it once per trusted click or key press, so a read the page
+    /// posted itself can be refused.
+    ClipboardRead {
+        id: u64,
+        #[serde(default)]
+        key: Option<u64>,
+    },
+    /// Sent on F12 or Cmd+Option+I.
+    OpenDevtools,
+    /// `window.plugin.openDiagnostics()`.
+    OpenDiagnostics,
+    /// A `console` call, uncaught error or unhandled promise rejection, if
+    /// [`WebviewConfig::capture_console`](crate::WebviewConfig::capture_console)
+    /// is enabled.
+    Console { level: ConsoleLevel, message: String },
+    /// The drop zone under the cursor changed during a native file drag.
+    DropZone { zone: Option<String> },
+    /// Sent when the page gains or loses the keyboard focus.
+    Focus { focused: bool },
+    /// Tab moved the focus out of the page, with
+    /// [`WebviewConfig::keyboard_navigation`](crate::WebviewConfig::keyboard_navigation).
+    FocusHost,
+    /// Sent regularly under
+    /// [`WebviewConfig::watchdog`](crate::WebviewConfig::watchdog).
+    Heartbeat,
+    /// Pointer, keyboard or wheel input, or `window.plugin.reportActivity()`.
+    /// Sent at most every 250 ms.
+    Activity,
+    /// A drag on a `data-plugin-capture` element started or ended.
+    PointerCapture { captured: bool },
+    /// `window.plugin.cursor.hide()` and `show()`, and the page losing focus.
+    SetCursorVisible { visible: bool },
+    /// `window.plugin.cursor.setPosition(x, y)`. Non-finite numbers arrive as
+    /// `null`, hence the `Option`s.
+    SetCursorPosition { x: Option<f64>, y: Option<f64> },
+    /// A piece of a message too large to be posted at once. The message is
+    /// parsed and handled once all `count` chunks of the transfer `id`
+    /// arrived.
+    Chunk { id: u64, index: usize, count: usize, data: String },
+    /// A verb not known to this version of the crate, e.g. from a newer copy
+    /// of `lib.js` or from the page posting its own envelopes. Passed on to
+    /// [`EditorHandler::on_unknown_ipc`](crate::EditorHandler::on_unknown_ipc).
+    #[serde(skip)]
+    Unknown { verb: String, payload: Value },
+    /// Marks a verb not listed above while deserializing, replaced with
+    /// [`WebviewEvent::Unknown`] by [`WebviewEvent::from_envelope`].
+    #[doc(hidden)]
+    #[serde(other)]
+    UnknownVerb,
+}
+
+/// The level of a message logged in the page, see
+/// [`EditorHandler::on_console`](crate::EditorHandler::on_console). Uncaught
+/// errors and unhandled promise rejections are reported as
+/// [`ConsoleLevel::Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsoleLevel {
+    Debug,
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+/// Returned by [`WebviewEvent::parse`] for messages which are not valid
+/// envelopes.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The message is not JSON.
+    Json(serde_json::Error),
+    /// The message is not an envelope, e.g. because the page posts to
+    /// `window.ipc` directly or uses an outdated copy of `lib.js`.
+    MissingEnvelope,
+    /// The payload does not match the verb.
+    InvalidPayload { verb: String, error: serde_json::Error },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Json(err) => write!(f, "the message is not valid JSON: {err}"),
+            ParseError::MissingEnvelope => write!(f, "the message is not an envelope"),
+            ParseError::InvalidPayload { verb, error } => {
+                write!(f, "invalid `{verb}` message: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Json(err) => Some(err),
+            ParseError::MissingEnvelope => None,
+            ParseError::InvalidPayload { error, .. } => Some(error),
+        }
+    }
+}
+
+impl WebviewEvent {
+    /// Parses a message as posted to `window.ipc` by `lib.js`.
+    pub fn parse(message: &str) -> Result<WebviewEvent, ParseError> {
+        let envelope = serde_json::from_str(message).map_err(ParseError::Json)?;
+        WebviewEvent::from_envelope(envelope)
+    }
+
+    /// Like [`WebviewEvent::parse`], for an already parsed message.
+    pub fn from_envelope(mut envelope: Value) -> Result<WebviewEvent, ParseError> {
+        let verb = match envelope.get("t").and_then(Value::as_str) {
+            Some(verb) => verb.to_owned(),
+            None => return Err(ParseError::MissingEnvelope),
+        };
+        if verb == MESSAGE_VERB {
+            return Ok(WebviewEvent::Message(take_payload(&mut envelope)));
+        }
+        match WebviewEvent::deserialize(&envelope) {
+            Ok(WebviewEvent::UnknownVerb) => {
+                Ok(WebviewEvent::Unknown { verb, payload: take_payload(&mut envelope) })
+            }
+            Ok(event) => Ok(event),
+            Err(error) => Err(ParseError::InvalidPayload { verb, error }),
+        }
+    }
+}
+
+fn take_payload(envelope: &mut Value) -> Value {
+    envelope.get_mut("payload").map(Value::take).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::chunk::{self, Reassembler};
+
+    #[test]
+    fn parses_envelopes() {
+        let cases = [
+            (
+                r#"{"t":"message","payload":{"gain":0.5}}"#,
+                WebviewEvent::Message(json!({ "gain": 0.5 })),
+            ),
+            (r#"{"t":"message"}"#, WebviewEvent::Message(Value::Null)),
+            (
+                r#"{"t":"hello","payload":{"version":1,"userAgent":"Test","decimalSeparator":","}}"#,
+                WebviewEvent::Hello {
+                    version: 1,
+                    user_agent: Some("Test".to_string()),
+                    decimal_separator: Some(','),
+                },
+            ),
+            (
+                r#"{"t":"hello","payload":{"version":1}}"#,
+                WebviewEvent::Hello { version: 1, user_agent: None, decimal_separator: None },
+            ),
+            (r#"{"t":"bridgeProbe"}"#, WebviewEvent::BridgeProbe),
+            (
+                r#"{"t":"resize","payload":{"id":3,"width":400,"height":null}}"#,
+                WebviewEvent::Resize { id: 3, width: Some(400.0), height: None },
+            ),
+            (
+                r#"{"t":"setParam","payload":{"id":"gain","normalized":0.25}}"#,
+                WebviewEvent::SetParam { id: "gain".to_string(), normalized: 0.25 },
+            ),
+            (
+                r#"{"t":"channel","payload":{"name":"meters"}}"#,
+                WebviewEvent::Channel { name: "meters".to_string(), message: Value::Null },
+            ),
+            (
+                r#"{"t":"console","payload":{"level":"warn","message":"careful"}}"#,
+                WebviewEvent::Console { level: ConsoleLevel::Warn, message: "careful".to_string() },
+            ),
+            (
+                r#"{"t":"chunk","payload":{"id":1,"index":0,"count":2,"data":"{\"t\""}}"#,
+                WebviewEvent::Chunk { id: 1, index: 0, count: 2, data: r#"{"t""#.to_string() },
+            ),
+        ];
+        for (message, event) in cases {
+            assert_eq!(WebviewEvent::parse(message).unwrap(), event, "{message}");
+        }
+    }
+
+    #[test]
+    fn relayed_worker_messages_arrive_in_order() {
+        // What `createWorkerPort` posts for a worker: any structured-clone
+        // value, and large ones in chunks like the page's own messages.
+        let big = "€".repeat(chunk::CHUNK_SIZE);
+        let relayed = [
+            r#"{"t":"message","payload":{"n":1}}"#.to_string(),
+            r#"{"t":"message","payload":"two"}"#.to_string(),
+            format!(r#"{{"t":"message","payload":{{"big":"{big}"}}}}"#),
+            r#"{"t":"message","payload":[3,{"lone":"a\ufffdb"}]}"#.to_string(),
+        ];
+        let expected = [
+            json!({ "n": 1 }),
+            json!("two"),
+            json!({ "big": big }),
+            json!([3, { "lone": "a\u{fffd}b" }]),
+        ];
+
+        let mut reassembler = Reassembler::new(usize::MAX);
+        let mut received = Vec::new();
+        for (id, message) in relayed.iter().enumerate() {
+            let chunks = chunk::split(message);
+            for (index, data) in chunks.iter().enumerate() {
+                let whole = reassembler.push(id as u64, index, chunks.len(), data.to_string());
+                received.extend(whole.map(|message| WebviewEvent::parse(&message).unwrap()));
+            }
+        }
+        let expected: Vec<_> = expected.into_iter().map(WebviewEvent::Message).collect();
+        assert_eq!(received, expected);
+    }
+
+    #[test]
+    fn keeps_unknown_verbs_and_their_payloads() {
+        assert_eq!(
+            WebviewEvent::parse(r#"{"t":"fromTheFuture","payload":[1,2]}"#).unwrap(),
+            WebviewEvent::Unknown { verb: "fromTheFuture".to_string(), payload: json!([1, 2]) }
+        );
+        // Verbs are case sensitive, and the hidden variants aren't verbs.
+        for verb in ["Hello", "unknown", "unknownVerb"] {
+            let message = format!(r#"{{"t":"{verb}"}}"#);
+            assert_eq!(
+                WebviewEvent::parse(&message).unwrap(),
+                WebviewEvent::Unknown { verb: verb.to_string(), payload: Value::Null }
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_messages_which_are_not_envelopes() {
+        assert!(matches!(WebviewEvent::parse("{"), Err(ParseError::Json(_))));
+        assert!(matches!(WebviewEvent::parse(""), Err(ParseError::Json(_))));
+        for message in [r#""hello""#, "[]", "null", "{}", r#"{"t":1}"#, r#"{"payload":{}}"#] {
+            assert!(
+                matches!(WebviewEvent::parse(message), Err(ParseError::MissingEnvelope)),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_payloads_which_dont_match_the_verb() {
+        for message in [
+            r#"{"t":"setParam","payload":{"id":"gain"}}"#,
+            r#"{"t":"setParam","payload":{"id":"gain","normalized":"loud"}}"#,
+            r#"{"t":"resize","payload":"big"}"#,
+            r#"{"t":"key","payload":{}}"#,
+            r#"{"t":"console","payload":{"level":"shout","message":""}}"#,
+        ] {
+            match WebviewEvent::parse(message) {
+                Err(ParseError::InvalidPayload { verb, .. }) => {
+                    assert!(message.contains(&format!(r#""t":"{verb}""#)), "{message}")
+                }
+                result => panic!("{message} parsed as {result:?}"),
+            }
+        }
+    }
+}
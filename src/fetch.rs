@@ -0,0 +1,413 @@
+//! HTTP requests made on background threads, see
+//! [`Context::fetch`](crate::Context::fetch). Requires the `http` feature.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    io::Read,
+    time::Duration,
+};
+
+use crossbeam::channel::{Receiver, Sender};
+use nih_plug::nih_error;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// A request for [`Context::fetch`](crate::Context::fetch).
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    pub url: String,
+    /// `"GET"`, `"POST"`, ...
+    pub method: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+    /// How long connecting and each read may take. Defaults to 30 seconds.
+    pub timeout: Duration,
+    /// How many redirects are followed. Defaults to 5, `0` doesn't follow
+    /// any and returns the redirect itself.
+    pub max_redirects: u32,
+    /// The largest body read, in bytes. Larger responses fail with
+    /// [`FetchError::TooLarge`]. Defaults to 8 MiB.
+    pub max_size: usize,
+}
+
+impl FetchRequest {
+    /// A `GET` request for `url`.
+    pub fn get(url: impl Into<String>) -> FetchRequest {
+        FetchRequest {
+            url: url.into(),
+            method: "GET".to_string(),
+            headers: Vec::new(),
+            body: None,
+            timeout: Duration::from_secs(30),
+            max_redirects: 5,
+            max_size: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// The response to a [`FetchRequest`]. Responses with error statuses like
+/// 404 are responses too, not [`FetchError`]s.
+#[derive(Debug, Clone)]
+pub struct FetchResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl FetchResponse {
+    /// The body as text, with invalid UTF-8 replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Returned when a [`FetchRequest`] could not be completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FetchError {
+    /// The body was larger than [`FetchRequest::max_size`].
+    TooLarge { max_size: usize },
+    /// More redirects than [`FetchRequest::max_redirects`].
+    TooManyRedirects,
+    /// The URL is not allowed by
+    /// [`WebviewConfig::fetch_allowed_urls`](crate::WebviewConfig::fetch_allowed_urls).
+    /// Only for requests from the page.
+    NotAllowed(String),
+    /// The request could not be sent or the response not be read, e.g.
+    /// because of an invalid URL, a timeout or no network connection.
+    Transport(String),
+    /// The editor was closed before the response arrived.
+    Closed,
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::TooLarge { max_size } => {
+                write!(f, "the response is larger than {max_size} bytes")
+            }
+            FetchError::TooManyRedirects => write!(f, "too many redirects"),
+            FetchError::NotAllowed(url) => write!(f, "the page may not fetch {url}"),
+            FetchError::Transport(err) => write!(f, "{err}"),
+            FetchError::Closed => write!(f, "the editor was closed before the response arrived"),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+type FetchResult = Result<FetchResponse, FetchError>;
+
+/// `window.plugin.fetch(url, options)`, with the [`FetchRequest`] fields in
+/// camelCase, the timeout in milliseconds and the body as a string. The page
+/// may lower the timeout and the limits, but not raise them above the
+/// [`FetchRequest::get`] defaults.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PageRequest {
+    url: String,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    #[serde(default)]
+    max_redirects: Option<u32>,
+    #[serde(default)]
+    max_size: Option<usize>,
+}
+
+impl PageRequest {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn into_request(self) -> FetchRequest {
+        let defaults = FetchRequest::get(self.url);
+        FetchRequest {
+            method: self.method.unwrap_or(defaults.method),
+            headers: self.headers.into_iter().collect(),
+            body: self.body.map(String::into_bytes),
+            timeout: self
+                .timeout_ms
+                .map_or(defaults.timeout, |ms| Duration::from_millis(ms).min(defaults.timeout)),
+            max_redirects: self
+                .max_redirects
+                .map_or(defaults.max_redirects, |n| n.min(defaults.max_redirects)),
+            max_size: self.max_size.map_or(defaults.max_size, |n| n.min(defaults.max_size)),
+            ..defaults
+        }
+    }
+}
+
+/// What `window.plugin.fetch` resolves to, or rejects with for `Error`.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum PageReply {
+    Response { status: u16, headers: Vec<(String, String)>, body: String },
+    Error { error: String },
+}
+
+/// Whether `url` starts with one of the `prefixes` of
+/// [`WebviewConfig::fetch_allowed_urls`](crate::WebviewConfig::fetch_allowed_urls).
+/// Both are normalized first, so `https://example.com/api/../admin` doesn't
+/// start with `https://example.com/api/`, and `https://example.com` doesn't
+/// allow `https://example.com.evil.org`. A prefix ending in the middle of a
+/// path segment only matches that whole segment.
+pub(crate) fn is_allowed_url(prefixes: &[String], url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else { return false };
+    prefixes.iter().any(|prefix| {
+        let prefix = Url::parse(prefix).map_or_else(|_| prefix.clone(), String::from);
+        match url.as_str().strip_prefix(prefix.as_str()) {
+            Some(rest) => {
+                prefix.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '?', '#'])
+            }
+            None => false,
+        }
+    })
+}
+
+impl From<FetchResult> for PageReply {
+    fn from(result: FetchResult) -> PageReply {
+        match result {
+            Ok(response) => PageReply::Response {
+                status: response.status,
+                body: response.text(),
+                headers: response.headers,
+            },
+            Err(err) => PageReply::Error { error: err.to_string() },
+        }
+    }
+}
+
+enum FetchCallback {
+    Handler(Box<dyn FnOnce(FetchResult)>),
+    /// Replies to the `lib.js` request with this id.
+    Page(u64),
+}
+
+/// Which URLs a request may be redirected to.
+enum Redirects {
+    Any,
+    /// Only to the [`is_allowed_url`] prefixes, for requests from the page.
+    Allowed(Vec<String>),
+}
+
+/// The callbacks of requests whose responses haven't arrived yet. They're
+/// only called from [`PendingFetches::dispatch`] on the editor thread.
+pub(crate) struct PendingFetches {
+    callbacks: RefCell<HashMap<u64, FetchCallback>>,
+    next_id: Cell<u64>,
+    results: (Sender<(u64, FetchResult)>, Receiver<(u64, FetchResult)>),
+}
+
+impl PendingFetches {
+    pub fn new() -> PendingFetches {
+        PendingFetches {
+            callbacks: RefCell::new(HashMap::new()),
+            next_id: Cell::new(0),
+            results: crossbeam::channel::unbounded(),
+        }
+    }
+
+    /// Sends `request` and calls `callback` with the response once it
+    /// arrived.
+    pub fn fetch(&self, request: FetchRequest, callback: Box<dyn FnOnce(FetchResult)>) {
+        self.start(request, FetchCallback::Handler(callback), Redirects::Any);
+    }
+
+    /// Sends `request` and replies to the `lib.js` request `id` once the
+    /// response arrived. Redirects are only followed to the `allowed_urls`.
+    pub fn fetch_for_page(&self, request: FetchRequest, id: u64, allowed_urls: Vec<String>) {
+        self.start(request, FetchCallback::Page(id), Redirects::Allowed(allowed_urls));
+    }
+
+    fn start(&self, request: FetchRequest, callback: FetchCallback, redirects: Redirects) {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.callbacks.borrow_mut().insert(id, callback);
+
+        let results = self.results.0.clone();
+        let spawned = std::thread::Builder::new().name("nih-plug-webview fetch".to_string()).spawn(
+            move || {
+                let _ = results.send((id, send(request, redirects)));
+            },
+        );
+        if let Err(err) = spawned {
+            nih_error!("Could not start a thread for a fetch: {err}");
+            let _ = self.results.0.send((id, Err(FetchError::Transport(err.to_string()))));
+        }
+    }
+
+    /// Calls the callbacks of the responses which arrived, and passes the
+    /// ones for the page to `reply`.
+    pub fn dispatch(&self, mut reply: impl FnMut(u64, PageReply)) {
+        while let Ok((id, result)) = self.results.1.try_recv() {
+            // Taken out first, as the callback may start another request.
+            let callback = self.callbacks.borrow_mut().remove(&id);
+            match callback {
+                Some(FetchCallback::Handler(callback)) => callback(result),
+                Some(FetchCallback::Page(id)) => reply(id, result.into()),
+                None => {}
+            }
+        }
+    }
+}
+
+impl Drop for PendingFetches {
+    fn drop(&mut self) {
+        for (_, callback) in self.callbacks.get_mut().drain() {
+            if let FetchCallback::Handler(callback) = callback {
+                callback(Err(FetchError::Closed));
+            }
+        }
+    }
+}
+
+/// Sends `request`, blocking until the whole response was read. Redirects
+/// are followed here rather than by ureq, so each one can be checked.
+fn send(request: FetchRequest, redirects: Redirects) -> FetchResult {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(request.timeout)
+        .timeout_read(request.timeout)
+        .redirects(0)
+        .build();
+
+    let mut url = Url::parse(&request.url).map_err(|err| FetchError::Transport(err.to_string()))?;
+    let mut method = request.method;
+    let mut request_headers = request.headers;
+    let mut request_body = request.body;
+    let mut redirected = 0;
+    let response = loop {
+        let mut call = agent.request(&method, url.as_str());
+        for (name, value) in &request_headers {
+            call = call.set(name, value);
+        }
+        let result = match &request_body {
+            Some(body) => call.send_bytes(body),
+            None => call.call(),
+        };
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(FetchError::Transport(err.to_string())),
+        };
+
+        let status = response.status();
+        let location = match status {
+            301 | 302 | 303 | 307 | 308 => response.header("Location").map(str::to_string),
+            _ => None,
+        };
+        // With `max_redirects` of 0 the redirect itself is returned.
+        let Some(location) = location.filter(|_| request.max_redirects > 0) else {
+            break response;
+        };
+        if redirected == request.max_redirects {
+            return Err(FetchError::TooManyRedirects);
+        }
+        let next = url.join(&location).map_err(|err| FetchError::Transport(err.to_string()))?;
+        if let Redirects::Allowed(allowed_urls) = &redirects {
+            if !is_allowed_url(allowed_urls, next.as_str()) {
+                return Err(FetchError::NotAllowed(next.into()));
+            }
+        }
+
+        // Like browsers, only 307 and 308 repeat a `POST` as it was.
+        if (status == 303 && method != "HEAD") || (matches!(status, 301 | 302) && method == "POST")
+        {
+            method = "GET".to_string();
+            request_body = None;
+        }
+        // Credentials aren't passed on to other servers.
+        if next.origin() != url.origin() {
+            request_headers.retain(|(name, _)| {
+                !name.eq_ignore_ascii_case("authorization") && !name.eq_ignore_ascii_case("cookie")
+            });
+        }
+        url = next;
+        redirected += 1;
+    };
+
+    let status = response.status();
+    let mut headers = Vec::new();
+    for name in response.headers_names() {
+        for value in response.all(&name) {
+            headers.push((name.clone(), value.to_string()));
+        }
+    }
+
+    // One byte more than allowed tells a body which is too large apart.
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take((request.max_size as u64).saturating_add(1))
+        .read_to_end(&mut body)
+        .map_err(|err| FetchError::Transport(err.to_string()))?;
+    if body.len() > request.max_size {
+        return Err(FetchError::TooLarge { max_size: request.max_size });
+    }
+
+    Ok(FetchResponse { status, headers, body })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page_request(timeout_ms: u64, max_redirects: u32, max_size: usize) -> FetchRequest {
+        PageRequest {
+            url: "https://example.com/api/".to_string(),
+            method: None,
+            headers: HashMap::new(),
+            body: None,
+            timeout_ms: Some(timeout_ms),
+            max_redirects: Some(max_redirects),
+            max_size: Some(max_size),
+        }
+        .into_request()
+    }
+
+    #[test]
+    fn page_requests_cant_raise_the_limits() {
+        let defaults = FetchRequest::get("https://example.com/api/");
+        let request = page_request(u64::MAX, u32::MAX, usize::MAX);
+        assert_eq!(request.timeout, defaults.timeout);
+        assert_eq!(request.max_redirects, defaults.max_redirects);
+        assert_eq!(request.max_size, defaults.max_size);
+    }
+
+    #[test]
+    fn page_requests_can_lower_the_limits() {
+        let request = page_request(500, 0, 1024);
+        assert_eq!(request.timeout, Duration::from_millis(500));
+        assert_eq!(request.max_redirects, 0);
+        assert_eq!(request.max_size, 1024);
+    }
+
+    #[test]
+    fn allowed_urls_match_whole_origins_and_segments() {
+        let prefixes =
+            ["https://example.com".to_string(), "https://api.example.com/v1".to_string()];
+        for url in [
+            "https://example.com",
+            "https://example.com/anything",
+            "https://api.example.com/v1",
+            "https://api.example.com/v1/presets?page=2",
+        ] {
+            assert!(is_allowed_url(&prefixes, url), "{url}");
+        }
+        for url in [
+            "https://example.com.evil.org/",
+            "https://example.com@evil.org/",
+            "http://example.com/",
+            "https://api.example.com/v10",
+            "https://api.example.com/v1/../admin",
+            "not a url",
+        ] {
+            assert!(!is_allowed_url(&prefixes, url), "{url}");
+        }
+    }
+}
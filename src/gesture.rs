@@ -0,0 +1,78 @@
+//! Keeps automation gestures balanced, see
+//! [`Context::begin_gesture`](crate::Context::begin_gesture).
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use nih_plug::{
+    nih_warn,
+    prelude::{GuiContext, ParamPtr},
+};
+
+/// An open automation gesture, returned by
+/// [`Context::begin_gesture`](crate::Context::begin_gesture). Pass it to
+/// [`Context::set_param`](crate::Context::set_param) to change the parameter,
+/// and to [`Context::end_gesture`](crate::Context::end_gesture) to end the
+/// gesture.
+#[derive(Debug)]
+#[must_use = "the gesture stays open until the token is passed to `Context::end_gesture`"]
+pub struct GestureToken {
+    pub(crate) id: String,
+}
+
+impl GestureToken {
+    /// The ID of the parameter the gesture is for.
+    pub fn param_id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// The gestures currently open, by parameter ID. Hosts may ignore the
+/// automation or even assert on a gesture being started twice or ended
+/// without being started, so the calls to the host are filtered here.
+pub(crate) struct GestureTracker {
+    context: Arc<dyn GuiContext>,
+    open: Mutex<HashMap<String, ParamPtr>>,
+}
+
+impl GestureTracker {
+    pub fn new(context: Arc<dyn GuiContext>) -> GestureTracker {
+        GestureTracker { context, open: Mutex::new(HashMap::new()) }
+    }
+
+    /// Starts a gesture unless one is already open for the parameter.
+    pub fn begin(&self, id: &str, ptr: ParamPtr) {
+        let mut open = self.open.lock().unwrap();
+        if open.insert(id.to_string(), ptr).is_none() {
+            // SAFETY: The pointer is kept valid by the `ParamMap`.
+            unsafe { self.context.raw_begin_set_parameter(ptr) };
+        }
+    }
+
+    /// Ends the parameter's gesture, if one is open.
+    pub fn end(&self, id: &str) {
+        match self.open.lock().unwrap().remove(id) {
+            // SAFETY: The pointer is kept valid by the `ParamMap`.
+            Some(ptr) => unsafe { self.context.raw_end_set_parameter(ptr) },
+            None => nih_warn!("Ignoring the end of a gesture for `{id}` which was never started."),
+        }
+    }
+
+    /// Ends every open gesture, e.g. when the editor is closed in the middle
+    /// of one.
+    pub fn end_all(&self) {
+        for (_, ptr) in self.open.lock().unwrap().drain() {
+            // SAFETY: The pointer is kept valid by the `ParamMap`.
+            unsafe { self.context.raw_end_set_parameter(ptr) };
+        }
+    }
+
+    /// The IDs of the parameters with an open gesture.
+    pub fn open_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.open.lock().unwrap().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+}
@@ -0,0 +1,806 @@
+// Injected into every page loaded by `nih_plug_webview` before any of the
+// page's own scripts run. Sets up `window.plugin`, the bridge between the
+// frontend and the `EditorHandler`.
+(() => {
+  // The version of the message format below. Must match `PROTOCOL_VERSION`
+  // in `lib.rs`.
+  const PROTOCOL_VERSION = 1;
+
+  // Messages longer than this many UTF-16 code units are posted in chunks,
+  // see `chunk.rs`. Incomplete chunked messages from the editor are dropped
+  // after `CHUNK_TIMEOUT` milliseconds.
+  const CHUNK_SIZE = 256 * 1024;
+  const CHUNK_TIMEOUT = 5000;
+
+  // User input is reported for `Context::is_idle` at most once per this many
+  // milliseconds.
+  const ACTIVITY_INTERVAL = 250;
+
+  // `clipboard.readText` is only allowed this many milliseconds after a click
+  // or key press.
+  const CLIPBOARD_READ_WINDOW = 5000;
+
+  // Options from the `WebviewConfig`, set by a script injected right before
+  // this one.
+  const config = window.__nihPlugWebviewConfig || {};
+  delete window.__nihPlugWebviewConfig;
+
+  // The languages from `WebviewConfig::accept_language`.
+  if (config.languages && config.languages.length > 0) {
+    const languages = Object.freeze(config.languages.slice());
+    Object.defineProperty(navigator, "languages", { get: () => languages, configurable: true });
+    Object.defineProperty(navigator, "language", { get: () => languages[0], configurable: true });
+  }
+
+  const listeners = [];
+  const jsonListeners = [];
+  const channelListeners = new Map();
+  const paramListeners = [];
+  const paramValues = {};
+  const displayListeners = [];
+  const displayValues = {};
+  const activeGestures = new Set();
+  const pendingReplies = new Map();
+  const readyListeners = [];
+  const incomingChunks = new Map();
+  // The ports returned by `createWorkerPort({ listen: true })`.
+  const listeningPorts = new Set();
+  let nextRequestId = 0;
+  let nextTransferId = 0;
+  // The splash screen's element while it's shown.
+  let splash = null;
+  // The drop zone under the cursor during a native file drag, and the latest
+  // cursor position waiting for the next animation frame.
+  let activeDropZone = null;
+  let dropHoverPoint = null;
+  // When the last trusted click or key press happened, see
+  // `CLIPBOARD_READ_WINDOW`.
+  let lastUserInput = -Infinity;
+  // The drag on a `data-plugin-capture` element in progress, as `{ element,
+  // pointerId }`.
+  let pointerCapture = null;
+
+  // Ends the drag, dispatching `type` at `(x, y)` to its element if the
+  // webview won't.
+  const endPointerCapture = (type, x, y) => {
+    if (!pointerCapture) return;
+    const { element, pointerId } = pointerCapture;
+    pointerCapture = null;
+    post("pointerCapture", { captured: false });
+    if (type) element.dispatchEvent(capturedPointerEvent(type, pointerId, x, y, 0));
+    if (element.hasPointerCapture(pointerId)) element.releasePointerCapture(pointerId);
+  };
+
+  const capturedPointerEvent = (type, pointerId, x, y, buttons) =>
+    new PointerEvent(type, {
+      bubbles: true,
+      cancelable: true,
+      pointerId,
+      pointerType: "mouse",
+      isPrimary: true,
+      clientX: x,
+      clientY: y,
+      button: type === "pointermove" ? -1 : 0,
+      buttons,
+    });
+
+  const outsideViewport = (x, y) =>
+    x < 0 || y < 0 || x >= window.innerWidth || y >= window.innerHeight;
+
+  const setDropZone = (zone) => {
+    if (zone === activeDropZone) return;
+    if (activeDropZone) activeDropZone.classList.remove("plugin-dropzone-active");
+    activeDropZone = zone;
+    if (zone) zone.classList.add("plugin-dropzone-active");
+    post("dropZone", { zone: zone ? zone.dataset.pluginDropzone : null });
+  };
+
+  const updateDropZone = () => {
+    if (dropHoverPoint === null) return;
+    const { x, y } = dropHoverPoint;
+    dropHoverPoint = null;
+    const element = document.elementFromPoint(x, y);
+    setDropZone(element ? element.closest("[data-plugin-dropzone]") : null);
+  };
+
+  // The editor's protocol version once it answered `hello`.
+  let editorVersion = null;
+
+  // Lone surrogates, e.g. from an interrupted IME composition, are not valid
+  // in the JSON the editor parses, which would lose the whole message. They
+  // are replaced with U+FFFD in every string.
+  const LONE_SURROGATE = /[\ud800-\udbff][\udc00-\udfff]|[\ud800-\udfff]/g;
+  const wellFormed = (value) => {
+    if (typeof value !== "string") return value;
+    if (value.toWellFormed) return value.toWellFormed();
+    return value.replace(LONE_SURROGATE, (match) => (match.length === 2 ? match : "\ufffd"));
+  };
+
+  // Captured before the page's scripts run, which could otherwise replace it
+  // to see `config.clipboardKey`.
+  const stringify = JSON.stringify;
+
+  // Every message is sent as `{ t: verb, payload }`. Messages for
+  // `EditorHandler::on_message` use the `message` verb, all other verbs are
+  // handled by the editor itself.
+  const post = (verb, payload) => {
+    const message = stringify({ t: verb, payload }, (_key, value) => wellFormed(value));
+    if (message.length <= CHUNK_SIZE) {
+      window.ipc.postMessage(message);
+      return;
+    }
+
+    // A chunk ending between the halves of a surrogate pair would not be
+    // valid UTF-16.
+    const chunks = [];
+    for (let start = 0; start < message.length; ) {
+      let end = Math.min(start + CHUNK_SIZE, message.length);
+      const last = message.charCodeAt(end - 1);
+      if (end < message.length && last >= 0xd800 && last <= 0xdbff) end--;
+      chunks.push(message.slice(start, end));
+      start = end;
+    }
+    const id = nextTransferId++;
+    chunks.forEach((data, index) => {
+      const payload = { id, index, count: chunks.length, data };
+      window.ipc.postMessage(stringify({ t: "chunk", payload }));
+    });
+  };
+
+  // Sends a built-in message and returns a promise resolving to the reply.
+  const request = (verb, args) =>
+    new Promise((resolve) => {
+      const id = nextRequestId++;
+      pendingReplies.set(id, resolve);
+      post(verb, { id, ...args });
+    });
+
+  // The replies to preset requests are `"done"`, `"cancelled"` or
+  // `{ error }`, turned into `true`, `false` or a rejected promise.
+  const presetRequest = (verb, args) =>
+    request(verb, args).then((reply) => {
+      if (reply && reply.error) throw new Error(reply.error);
+      return reply === "done";
+    });
+
+  // The replies to clipboard requests are `{ text }` or `{ error }`.
+  const clipboardRequest = (verb, args) =>
+    request(verb, args).then((reply) => {
+      if (reply.error) throw new Error(reply.error);
+      return reply;
+    });
+
+  // Sets a parameter to a normalized value. Outside of a gesture, this is
+  // wrapped in its own begin/end gesture pair.
+  const setParam = (id, normalized) => {
+    const inGesture = activeGestures.has(id);
+    if (!inGesture) post("beginGesture", { id });
+    post("setParam", { id, normalized });
+    if (!inGesture) post("endGesture", { id });
+  };
+
+  window.plugin = {
+    // The version of this script's protocol. Compare with the argument of
+    // `onReady` callbacks to detect a mismatched `nih_plug_webview` version.
+    version: PROTOCOL_VERSION,
+
+    // What's known about the host, `{ pluginApi, hostName, os }`, see
+    // `HostInfo`. Available before the page's own scripts run.
+    host: config.host || null,
+
+    // `"standalone"` or `"hosted"`, see `Context::execution_mode`. `null`
+    // until `onReady` callbacks are called.
+    executionMode: null,
+
+    // Calls `callback` with the editor's protocol version once the editor
+    // has answered, and every synced parameter value has arrived. Called
+    // right away if that already happened.
+    onReady: (callback) => {
+      if (editorVersion !== null) callback(editorVersion);
+      else readyListeners.push(callback);
+    },
+
+    // Sends a message to `EditorHandler::on_message`.
+    send: (message) => post("message", message),
+
+    // Returns a `MessagePort` for a Web Worker or iframe, which can't reach
+    // the editor on their own. Transfer it with `worker.postMessage(port,
+    // [port])`. Every message posted to the port arrives in
+    // `EditorHandler::on_message`, in order with the ones sent with `send`.
+    // With `{ listen: true }`, the port also receives the messages sent with
+    // `Context::send_message`, already parsed. There's no event for a port
+    // being closed on the other end, so ports are kept until the page is.
+    createWorkerPort: ({ listen = false } = {}) => {
+      const { port1, port2 } = new MessageChannel();
+      port1.onmessage = (event) => post("message", event.data);
+      if (listen) listeningPorts.add(port1);
+      return port2;
+    },
+
+    // Registers a callback for messages sent with `Context::send_message`.
+    // The callback receives the message as a JSON string.
+    listen: (callback) => {
+      listeners.push(callback);
+    },
+
+    // Registers a callback for messages sent with `Context::send_message` or
+    // `Context::send_json`. The callback receives the parsed message.
+    listenJson: (callback) => {
+      jsonListeners.push(callback);
+    },
+
+    // Returns the named channel `name`. Messages sent with `send` arrive in
+    // `EditorHandler::on_channel_message`, and `listen` registers a callback
+    // for the parsed messages sent with `Context::channel(name).send`.
+    channel: (name) => ({
+      send: (message) => post("channel", { name, message }),
+      listen: (callback) => {
+        if (!channelListeners.has(name)) channelListeners.set(name, []);
+        channelListeners.get(name).push(callback);
+      },
+    }),
+
+    // Messages between the editors of the instances in the same
+    // `WebviewConfig::instance_group`. `listen` callbacks receive `{ from,
+    // message }`, with the sending instance's id.
+    peers: {
+      broadcast: (message) => post("peerBroadcast", { message: String(message) }),
+      listen: (callback) => window.plugin.channel("peers").listen(callback),
+    },
+
+    // Asks the host to resize the editor window to show the page at the
+    // given size in CSS pixels, see `Context::resize_window`. Resolves to
+    // `true` if the host accepted the new size, and to `false` for sizes
+    // which aren't positive numbers. Sizes the host keeps refusing are
+    // reported on the `resize-denied` channel.
+    resizeWindow: (width, height) => request("resize", { width, height }),
+
+    // Stores UI preferences, e.g. `{ theme: "dark", tab: 2 }`, in the
+    // `WebviewState` so they are saved with the plugin's state.
+    saveUiState: (value) => post("saveUiState", { value }),
+
+    // Resolves to the value last passed to `saveUiState`, or `null`.
+    loadUiState: () => request("loadUiState", {}),
+
+    // Opens a native file dialog and resolves to the selected paths, or `null`
+    // if it was cancelled. Takes the same options as `FileDialogOptions`, e.g.
+    // `{ mode: "open", multiple: true, filters: [{ name: "Audio", extensions:
+    // ["wav"] }] }`. Requires the `file-dialog` feature.
+    pickFile: (options = {}) => request("pickFile", { options }),
+
+    // Sends a request through the editor, which isn't limited by CORS, and
+    // resolves to `{ status, ok, headers, text(), json() }`. Takes `{ method,
+    // headers, body, timeoutMs, maxRedirects, maxSize }` as options and
+    // rejects if the request fails or its URL is not in
+    // `WebviewConfig::fetch_allowed_urls`. Requires the `http` feature.
+    fetch: (url, options = {}) =>
+      request("fetch", { request: { ...options, url: String(url) } }).then((reply) => {
+        if (reply.error) throw new Error(reply.error);
+        const { status, headers, body } = reply;
+        return {
+          status,
+          ok: status >= 200 && status < 300,
+          headers,
+          text: () => body,
+          json: () => JSON.parse(body),
+        };
+      }),
+
+    // The system clipboard, for when `navigator.clipboard` is unavailable.
+    // `readText` resolves to `null` if the clipboard holds no text, and only
+    // works once per click or key press, shortly after it. Require the
+    // `clipboard` feature.
+    clipboard: {
+      writeText: (text) => clipboardRequest("clipboardWrite", { text: String(text) }).then(() => {}),
+      readText: () => {
+        if (performance.now() - lastUserInput > CLIPBOARD_READ_WINDOW) {
+          return Promise.reject(new Error("Reading the clipboard requires a recent click or key press."));
+        }
+        // Only the editor's initialization script knows the key, see
+        // `clipboard::ReadGate`.
+        lastUserInput = -Infinity;
+        return clipboardRequest("clipboardRead", { key: config.clipboardKey }).then((reply) => reply.text);
+      },
+    },
+
+    // Saves the plugin's state to a file picked in a save dialog, or loads it
+    // from one. Resolve to `false` if the dialog was cancelled, and reject if
+    // the file can't be written, read or is not a valid preset. Require the
+    // `file-dialog` feature.
+    presets: {
+      saveToFile: (name) => presetRequest("savePreset", { name: String(name) }),
+      loadFromFile: () => presetRequest("loadPreset", {}),
+    },
+
+    // Fades out the splash screen of `WebviewConfig::splash`.
+    hideSplash: () => {
+      if (!splash) return;
+      const element = splash;
+      splash = null;
+      element.style.opacity = "0";
+      setTimeout(() => element.remove(), 200);
+    },
+
+    // Hides, shows and moves the mouse cursor, e.g. for knobs which can be
+    // dragged indefinitely. Positions are in CSS pixels relative to the
+    // page's top left corner. The cursor is shown again when the page loses
+    // focus. Not supported on Linux.
+    cursor: {
+      hide: () => post("setCursorVisible", { visible: false }),
+      show: () => post("setCursorVisible", { visible: true }),
+      setPosition: (x, y) => post("setCursorPosition", { x, y }),
+    },
+
+    // Shows facts for bug reports over the editor, see
+    // `Context::open_diagnostics`.
+    openDiagnostics: () => post("openDiagnostics"),
+
+    // Keeps the editor from becoming idle, like user input does. Call it
+    // while an animation is running, see `Context::is_idle`.
+    reportActivity: () => reportActivity(),
+
+    // Resolves to the latest frame of the visualization channel added with
+    // `WebviewEditor::with_visualization` under `name`, as an `ArrayBuffer`.
+    visualization: (name) =>
+      fetch(config.visualizationUrl + encodeURIComponent(name)).then((response) => {
+        if (!response.ok) throw new Error(`Unknown visualization: ${name}`);
+        return response.arrayBuffer();
+      }),
+
+    // Parameter access, available when the editor was created with
+    // `WebviewEditor::with_param_sync`.
+    params: {
+      // Returns the last known `{ id, normalized, plain, text, sequence }` of
+      // a parameter, or `undefined` if it hasn't been received yet. See
+      // `Context::sequence` for `sequence`.
+      get: (id) => paramValues[id],
+
+      // Sets a parameter to a normalized value, see `setParam` above.
+      set: setParam,
+      setNormalized: setParam,
+
+      // Starts an automation gesture, e.g. when the user grabs a knob.
+      beginGesture: (id) => {
+        activeGestures.add(id);
+        post("beginGesture", { id });
+      },
+
+      // Ends an automation gesture started with `beginGesture`.
+      endGesture: (id) => {
+        activeGestures.delete(id);
+        post("endGesture", { id });
+      },
+
+      // Checks the hash of the parameter manifest the page was built with,
+      // see `WebviewEditor::write_param_manifest`, against the plugin's
+      // parameters. Resolves to `false` if they differ, which the editor also
+      // logs as an error.
+      verifyManifest: (hash) =>
+        request("verifyParamManifest", { hash }).then((matches) => {
+          if (!matches) console.error("The plugin's parameters differ from the page's manifest.");
+          return matches;
+        }),
+
+      // Registers a callback receiving `{ id, normalized, plain, text }`
+      // whenever a parameter changes, and once for every parameter when the
+      // editor opens. With `subscribe(id, callback)`, only that parameter's
+      // updates are received. Returns a function removing the callback.
+      subscribe: (id, callback) => {
+        const listener =
+          typeof id === "function" ? id : (update) => update.id === id && callback(update);
+
+        paramListeners.push(listener);
+        Object.values(paramValues).forEach((update) => listener(update));
+        return () => paramListeners.splice(paramListeners.indexOf(listener) >>> 0, 1);
+      },
+    },
+
+    // Values added with `WebviewEditor::with_display_value`, like smoothed
+    // parameter values, updated once per frame when they changed.
+    displayValues: {
+      // Returns the last received value of a tap, or `undefined`.
+      get: (id) => displayValues[id],
+
+      // Registers a callback receiving `{ id, value }` whenever a value
+      // changes, and once for every value already received. Returns a
+      // function removing the callback.
+      subscribe: (callback) => {
+        displayListeners.push(callback);
+        Object.entries(displayValues).forEach(([id, value]) => callback({ id, value }));
+        return () => displayListeners.splice(displayListeners.indexOf(callback) >>> 0, 1);
+      },
+    },
+
+    __ipc: {
+      recvMessage: (message) => {
+        listeners.forEach((callback) => callback(message));
+        if (jsonListeners.length > 0 || listeningPorts.size > 0) {
+          const value = JSON.parse(message);
+          jsonListeners.forEach((callback) => callback(value));
+          listeningPorts.forEach((port) => port.postMessage(value));
+        }
+      },
+      recvChannel: (name, message) => {
+        const callbacks = channelListeners.get(name);
+        if (!callbacks) return;
+        const value = JSON.parse(message);
+        callbacks.forEach((callback) => callback(value));
+      },
+      recvParams: (updates, sequence) => {
+        updates.forEach((update) => {
+          update.sequence = sequence;
+          paramValues[update.id] = update;
+          paramListeners.forEach((callback) => callback(update));
+        });
+      },
+      recvDisplayValues: (updates) => {
+        updates.forEach((update) => {
+          displayValues[update.id] = update.value;
+          displayListeners.forEach((callback) => callback(update));
+        });
+      },
+      ready: (version, executionMode) => {
+        if (version !== PROTOCOL_VERSION) {
+          console.warn(
+            `nih_plug_webview: lib.js protocol version ${PROTOCOL_VERSION} does not match ` +
+              `the editor's version ${version}.`,
+          );
+        }
+        editorVersion = version;
+        window.plugin.executionMode = executionMode;
+        readyListeners.splice(0).forEach((callback) => callback(version));
+      },
+      // Hit tests drop zones during a native file drag, at most once per
+      // animation frame.
+      dropHover: (x, y) => {
+        if (dropHoverPoint === null) requestAnimationFrame(updateDropZone);
+        dropHoverPoint = { x, y };
+      },
+      dropEnd: () => {
+        dropHoverPoint = null;
+        setDropZone(null);
+      },
+      // A piece of a call too large to be evaluated at once. `data` are parts
+      // of the arguments' JSON, passed to `__ipc[target]` once complete.
+      recvChunk: (id, index, count, data, target) => {
+        const now = Date.now();
+        incomingChunks.forEach((transfer, transferId) => {
+          if (now - transfer.lastChunk > CHUNK_TIMEOUT) incomingChunks.delete(transferId);
+        });
+
+        if (!incomingChunks.has(id)) {
+          incomingChunks.set(id, { chunks: new Array(count), received: 0, lastChunk: now });
+        }
+        const transfer = incomingChunks.get(id);
+        transfer.chunks[index] = data;
+        transfer.received++;
+        transfer.lastChunk = now;
+        if (transfer.received < count) return;
+
+        incomingChunks.delete(id);
+        const args = JSON.parse(`[${transfer.chunks.join("")}]`);
+        window.plugin.__ipc[target](...args);
+      },
+      recvReply: (id, value) => {
+        const resolve = pendingReplies.get(id);
+        pendingReplies.delete(id);
+        if (resolve) resolve(value);
+      },
+      // Moves the focus into the page when the editor's window is focused
+      // and Tab is pressed, to the last element with Shift+Tab.
+      focusEdge: (reverse) => {
+        const elements = focusableElements();
+        const element = reverse ? elements[elements.length - 1] : elements[0];
+        if (element) element.focus();
+      },
+      // Covers the page after the `EditorHandler` panicked. `detail` is the
+      // panic message in debug builds and `null` otherwise.
+      showCrash: (detail) => {
+        const overlay = document.createElement("div");
+        overlay.style.cssText =
+          "position: fixed; inset: 0; z-index: 2147483647; display: flex;" +
+          "flex-direction: column; align-items: center; justify-content: center;" +
+          "background: rgba(0, 0, 0, 0.85); color: #fff; font-family: sans-serif;";
+        const title = document.createElement("h3");
+        title.textContent = "Plugin UI crashed";
+        overlay.appendChild(title);
+        if (detail !== null) {
+          const pre = document.createElement("pre");
+          pre.style.whiteSpace = "pre-wrap";
+          pre.textContent = detail;
+          overlay.appendChild(pre);
+        }
+        document.body.appendChild(overlay);
+      },
+      // The cursor during a drag on a `data-plugin-capture` element. Only
+      // used outside of the viewport, where the webview may not send pointer
+      // events.
+      capturedPointer: (x, y, down) => {
+        if (!pointerCapture) return;
+        if (!down) {
+          // Inside the viewport, the webview sends the `pointerup` itself.
+          if (outsideViewport(x, y)) endPointerCapture("pointerup", x, y);
+          else endPointerCapture(null);
+        } else if (outsideViewport(x, y)) {
+          const { element, pointerId } = pointerCapture;
+          element.dispatchEvent(capturedPointerEvent("pointermove", pointerId, x, y, 1));
+        }
+      },
+    },
+  };
+
+  // Reports focus changes to `EditorHandler::on_focus_changed`, and to the
+  // page as a `plugin-focus` event. A drag hiding the cursor may never see
+  // its `pointerup` once the page lost focus, so the cursor is shown again.
+  const focusChanged = (focused) => {
+    if (!focused) post("setCursorVisible", { visible: true });
+    post("focus", { focused });
+    window.dispatchEvent(new CustomEvent("plugin-focus", { detail: { focused } }));
+  };
+  window.addEventListener("focus", () => focusChanged(true));
+  window.addEventListener("blur", () => focusChanged(false));
+
+  // Drags started on an element marked `data-plugin-capture`, e.g. a knob,
+  // keep receiving pointer events while the cursor is outside the window. The
+  // element captures the pointer as far as the webview goes, and the editor
+  // reports the cursor beyond that. A lost focus cancels the drag.
+  window.addEventListener(
+    "pointerdown",
+    (event) => {
+      if (!event.isPrimary || event.button !== 0 || !(event.target instanceof Element)) return;
+      const element = event.target.closest("[data-plugin-capture]");
+      if (!element) return;
+      endPointerCapture(null);
+      pointerCapture = { element, pointerId: event.pointerId };
+      element.setPointerCapture(event.pointerId);
+      post("pointerCapture", { captured: true });
+    },
+    { capture: true },
+  );
+  for (const type of ["pointerup", "pointercancel"]) {
+    window.addEventListener(
+      type,
+      (event) => {
+        if (pointerCapture && event.pointerId === pointerCapture.pointerId) endPointerCapture(null);
+      },
+      { capture: true },
+    );
+  }
+  window.addEventListener("blur", () => endPointerCapture("pointercancel", -1, -1));
+
+  // The elements Tab moves between, in order, leaving out the sentinels
+  // below.
+  const focusableElements = () =>
+    Array.from(
+      document.querySelectorAll(
+        "a[href], area[href], button, input, select, textarea, iframe, summary, " +
+          "[contenteditable], [tabindex]",
+      ),
+    )
+      .filter(
+        (element) =>
+          !element.hasAttribute("data-plugin-focus-sentinel") &&
+          element.tabIndex >= 0 &&
+          !element.disabled &&
+          element.getClientRects().length > 0,
+      )
+      .sort((a, b) => (a.tabIndex || Infinity) - (b.tabIndex || Infinity));
+
+  // With `WebviewConfig::keyboard_navigation`, a focusable sentinel at the
+  // start and the end of the page hands the focus back to the host when Tab
+  // leaves the page, and on to the first or last element when it enters it.
+  if (config.keyboardNavigation) {
+    const sentinel = (atStart) => {
+      const element = document.createElement("div");
+      element.tabIndex = 0;
+      element.setAttribute("data-plugin-focus-sentinel", "");
+      element.setAttribute("aria-hidden", "true");
+      element.style.cssText = "position: fixed; width: 1px; height: 1px; overflow: hidden; opacity: 0;";
+      element.addEventListener("focus", (event) => {
+        const leaving = event.relatedTarget !== null && document.contains(event.relatedTarget);
+        element.blur();
+        if (leaving) post("focusHost");
+        else window.plugin.__ipc.focusEdge(!atStart);
+      });
+      return element;
+    };
+    const first = sentinel(true);
+    const last = sentinel(false);
+    const placeSentinels = () => {
+      if (!document.body) return;
+      if (document.body.firstChild !== first) document.body.prepend(first);
+      if (document.body.lastChild !== last) document.body.append(last);
+    };
+    const observer = new MutationObserver(placeSentinels);
+    const observe = () => {
+      placeSentinels();
+      if (document.body) observer.observe(document.body, { childList: true });
+    };
+    if (document.body) observe();
+    else document.addEventListener("DOMContentLoaded", observe);
+  }
+
+  // Tells the editor the page still runs, for `WebviewConfig::watchdog`.
+  if (config.heartbeatInterval) setInterval(() => post("heartbeat"), config.heartbeatInterval);
+
+  // Reports user input for `Context::is_idle`. Only the first input in every
+  // `ACTIVITY_INTERVAL` is posted, so the first one after a pause arrives
+  // right away.
+  let lastActivity = -Infinity;
+  const reportActivity = () => {
+    const now = performance.now();
+    if (now - lastActivity < ACTIVITY_INTERVAL) return;
+    lastActivity = now;
+    post("activity");
+  };
+  for (const type of ["pointerdown", "pointermove", "keydown", "wheel"]) {
+    window.addEventListener(type, reportActivity, { capture: true, passive: true });
+  }
+  for (const type of ["pointerdown", "keydown"]) {
+    window.addEventListener(
+      type,
+      (event) => {
+        if (event.isTrusted) lastUserInput = performance.now();
+      },
+      { capture: true, passive: true },
+    );
+  }
+
+  // Covers the page with the splash screen as soon as the document exists,
+  // which may be before this script runs.
+  if (config.splash) {
+    splash = document.createElement("div");
+    splash.style.cssText =
+      "position: fixed; inset: 0; z-index: 2147483646; display: flex;" +
+      "align-items: center; justify-content: center; transition: opacity 0.2s;" +
+      `background: ${config.splash.background};`;
+    if (config.splash.html !== null) {
+      splash.innerHTML = config.splash.html;
+    } else {
+      const spinner = document.createElement("div");
+      spinner.style.cssText =
+        "width: 32px; height: 32px; border-radius: 50%; border: 3px solid rgba(255, 255, 255, 0.2);" +
+        "border-top-color: rgba(255, 255, 255, 0.8);";
+      spinner.animate([{ transform: "rotate(0deg)" }, { transform: "rotate(360deg)" }], {
+        duration: 800,
+        iterations: Infinity,
+      });
+      splash.appendChild(spinner);
+    }
+
+    const mount = () => {
+      if (!splash) return;
+      if (document.documentElement) document.documentElement.appendChild(splash);
+      else setTimeout(mount);
+    };
+    mount();
+  }
+
+  // Forwards console output and uncaught errors to
+  // `EditorHandler::on_console`, with `WebviewConfig::capture_console`.
+  if (config.captureConsole) {
+    const format = (value) => {
+      if (typeof value === "string") return value;
+      if (value instanceof Error) return value.stack || String(value);
+      try {
+        return JSON.stringify(value) ?? String(value);
+      } catch {
+        return String(value);
+      }
+    };
+
+    ["debug", "log", "info", "warn", "error"].forEach((level) => {
+      const original = console[level];
+      console[level] = (...args) => {
+        original.apply(console, args);
+        post("console", { level, message: args.map(format).join(" ") });
+      };
+    });
+    window.addEventListener("error", (event) => {
+      const message = event.error ? format(event.error) : event.message;
+      post("console", { level: "error", message: `Uncaught ${message}` });
+    });
+    window.addEventListener("unhandledrejection", (event) => {
+      post("console", { level: "error", message: `Unhandled rejection: ${format(event.reason)}` });
+    });
+  }
+
+  // Keeps pinches and Ctrl+wheel from zooming the webview, with
+  // `PinchPolicy::ToPage`. The page's own listeners still get the events.
+  if (config.suppressZoom) {
+    const preventDefault = (event) => event.preventDefault();
+    window.addEventListener(
+      "wheel",
+      (event) => {
+        if (event.ctrlKey) event.preventDefault();
+      },
+      { passive: false },
+    );
+    ["gesturestart", "gesturechange", "gestureend"].forEach((type) =>
+      window.addEventListener(type, preventDefault, { passive: false }),
+    );
+    window.addEventListener(
+      "touchmove",
+      (event) => {
+        if (event.touches.length > 1) event.preventDefault();
+      },
+      { passive: false },
+    );
+  }
+
+  // Opens the devtools on F12 or Cmd+Option+I. This is ignored by the editor
+  // unless `WebviewConfig::devtools` is set.
+  window.addEventListener("keydown", (event) => {
+    if (event.key === "F12" || (event.metaKey && event.altKey && event.code === "KeyI")) {
+      post("openDevtools");
+    }
+  });
+
+  const isEditable = (element) =>
+    !!element &&
+    (element.isContentEditable || ["INPUT", "TEXTAREA", "SELECT"].includes(element.tagName));
+
+  // Forwards key events the page did not handle to the editor, which passes
+  // them on to `EditorHandler::on_key_event` and then to the host.
+  if (config.forwardUnhandledKeys) {
+    const forwardKey = (event) => {
+      if (event.isComposing || isEditable(document.activeElement)) return;
+
+      // Wait for the event to finish propagating so that handlers registered
+      // by the page get a chance to call `preventDefault`.
+      setTimeout(() => {
+        if (event.defaultPrevented) return;
+        post("key", {
+          down: event.type === "keydown",
+          key: event.key,
+          code: event.code,
+          keyCode: event.keyCode,
+          repeat: event.repeat,
+          shift: event.shiftKey,
+          ctrl: event.ctrlKey,
+          alt: event.altKey,
+          meta: event.metaKey,
+        });
+      });
+    };
+    window.addEventListener("keydown", forwardKey);
+    window.addEventListener("keyup", forwardKey);
+  }
+
+  // Describes the element a context menu was requested on for
+  // `EditorHandler::on_context_menu`.
+  const describeElement = (element) => {
+    const tagged = element.closest("[data-context-menu]");
+    if (tagged) return tagged.dataset.contextMenu;
+
+    let description = element.tagName.toLowerCase();
+    if (element.id) description += `#${element.id}`;
+    element.classList.forEach((name) => (description += `.${name}`));
+    return description;
+  };
+
+  // Replaces the webview's own context menu with `EditorHandler::on_context_menu`.
+  // Text inputs and selected text keep the native menu for copy and paste.
+  if (!config.nativeContextMenu) {
+    window.addEventListener("contextmenu", (event) => {
+      if (event.defaultPrevented) return;
+      if (isEditable(event.target) || !window.getSelection().isCollapsed) return;
+
+      event.preventDefault();
+      post("contextMenu", {
+        x: event.clientX,
+        y: event.clientY,
+        element: event.target instanceof Element ? describeElement(event.target) : "",
+      });
+    });
+  }
+
+  const decimal = new Intl.NumberFormat(navigator.languages)
+    .formatToParts(1.5)
+    .find((part) => part.type === "decimal");
+  post("hello", {
+    version: PROTOCOL_VERSION,
+    userAgent: navigator.userAgent,
+    decimalSeparator: decimal ? decimal.value : null,
+  });
+})();
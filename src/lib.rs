@@ -1,24 +1,153 @@
 use std::{
+    any::Any,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
     marker::PhantomData,
-    path::PathBuf,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        Arc, Mutex, MutexGuard, PoisonError,
     },
+    time::{Duration, Instant},
 };
 
-use baseview::{Event, EventStatus, Size, Window, WindowOpenOptions, WindowScalePolicy};
+use atomic_float::AtomicF32;
+use baseview::{
+    Event, EventStatus, MouseEvent, Size, Window, WindowEvent, WindowOpenOptions, WindowScalePolicy,
+};
 use crossbeam::{atomic::AtomicCell, channel::Receiver};
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Modifiers};
 use nih_plug::{
+    nih_error, nih_log, nih_trace, nih_warn,
     params::persist::PersistentField,
-    prelude::{Editor, GuiContext, ParamSetter},
+    prelude::{Editor, GuiContext, ParamPtr, ParamSetter, Params, PluginApi},
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use wry::{
-    http::{self, header::CONTENT_TYPE, Request, Response},
-    WebContext, WebView, WebViewBuilder,
+    http::{
+        self,
+        header::{CONTENT_SECURITY_POLICY, CONTENT_TYPE},
+        Request, Response,
+    },
+    WebView, WebViewBuilder,
+};
+
+/// Script injected into every page, providing the `window.plugin` bridge.
+const LIB_JS: &str = include_str!("lib.js");
+
+/// The version of the message format spoken by `lib.js`, which sends it in
+/// its `hello` message. Must match `PROTOCOL_VERSION` in `lib.js`.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// The verb of messages for [`EditorHandler::on_message`]. Every other verb is
+/// handled by the editor itself, see [`WebviewEvent`].
+const MESSAGE_VERB: &str = "message";
+
+/// Custom protocol serving the frames of the editor's visualization channels.
+const VISUALIZATION_PROTOCOL: &str = "nih-viz";
+
+/// Custom protocol serving the assets published with
+/// [`Context::publish_asset`].
+const ASSET_PROTOCOL: &str = "nih-assets";
+
+/// The channel transport snapshots are sent on, see
+/// [`WebviewEditor::with_transport`].
+const TRANSPORT_CHANNEL: &str = "transport";
+
+/// The channel a size is sent on once the host refused it repeatedly, see
+/// [`EditorHandler::on_resize_denied`].
+const RESIZE_DENIED_CHANNEL: &str = "resize-denied";
+
+/// The channel messages from other instances are sent on, see
+/// [`EditorHandler::on_peer_message`].
+const PEERS_CHANNEL: &str = "peers";
+
+/// How often in a row the host must refuse the same size before
+/// [`EditorHandler::on_resize_denied`] is called.
+const RESIZE_REFUSALS_BEFORE_DENIED: u32 = 3;
+
+/// How long after a page finished loading `lib.js` has to say hello, before
+/// it's evaluated once more and then reported to
+/// [`EditorHandler::on_bridge_unavailable`].
+const BRIDGE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where users can get the WebView2 runtime, see
+/// [`WebviewError::MissingRuntime`].
+const WEBVIEW2_DOWNLOAD_URL: &str = "https://go.microsoft.com/fwlink/p/?LinkId=2124703";
+
+mod assets;
+mod channel;
+mod chunk;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod diagnostics;
+#[cfg(feature = "file-dialog")]
+mod dialog;
+mod display;
+mod eval;
+mod event;
+#[cfg(feature = "http")]
+mod fetch;
+mod gesture;
+mod logging;
+mod number;
+mod overlay;
+mod params;
+mod peers;
+mod platform;
+mod preset;
+mod protocol;
+mod size;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
+mod undo;
+mod visualization;
+mod web_context;
+mod workdir;
+
+use assets::AssetStore;
+use channel::AudioQueue;
+use chunk::Reassembler;
+use diagnostics::ConsoleHistory;
+use display::{DisplayTap, DisplayValues};
+use eval::{EvalState, PendingEvals};
+#[cfg(feature = "http")]
+use fetch::PendingFetches;
+use gesture::GestureTracker;
+use logging::{ipc_summary, Direction, Warnings};
+use params::{ParamManifest, ParamMap};
+use peers::Membership;
+use protocol::{AsyncProtocolHandler, ProtocolPool};
+use size::physical_bounds;
+use undo::UndoHistory;
+use visualization::FrameSource;
+use web_context::{SharedWebContext, WebContextRegistry};
+use workdir::Workdir;
+
+pub use assets::{AssetError, AssetUrl};
+pub use channel::{editor_channel, AudioMessage, EditorReceiver, EditorSender};
+#[cfg(feature = "file-dialog")]
+pub use dialog::{FileDialogMode, FileDialogOptions, FileFilter};
+pub use eval::{EvalError, EvalFuture};
+pub use event::{ConsoleLevel, ParseError, WebviewEvent};
+#[cfg(feature = "http")]
+pub use fetch::{FetchError, FetchRequest, FetchResponse};
+pub use gesture::GestureToken;
+pub use overlay::{OverlayConfig, OverlayHandle, OverlayId};
+pub use params::{ParamIdError, ParamInfo};
+pub use peers::InstanceId;
+pub use preset::StateError;
+pub use protocol::{content_security_policy, custom_protocol_url, ProtocolJob};
+pub use transport::{TransportCell, TransportSnapshot};
+pub use undo::UndoBridge;
+pub use visualization::{
+    visualization_channel, VisualizationProducer, VisualizationSample, VisualizationSource,
 };
+pub use workdir::WorkdirPolicy;
 
 pub use baseview;
 pub use keyboard_types;
@@ -60,360 +189,3793 @@ pub enum WebviewSource {
     CustomProtocol { protocol: String, url_path: String },
 }
 
-pub trait EditorHandler: Sized + Send + Sync + 'static {
-    /// Message type sent from the handler to the editor.
-    type EditorTx: Serialize;
-    /// Message type sent from the editor to the handler.
-    type EditorRx: DeserializeOwned;
-
-    fn init(&mut self, cx: &mut Context<Self>);
-    fn on_frame(&mut self, cx: &mut Context<Self>);
-    fn on_message(&mut self, cx: &mut Context<Self>, message: Self::EditorRx);
-    fn on_window_event(&mut self, cx: &mut Context<Self>, event: Event) -> EventStatus {
-        let _ = (cx, event);
-        EventStatus::Ignored
-    }
+/// A page load lifecycle event, see [`EditorHandler::on_page_load`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageLoadEvent {
+    /// The webview started loading the page at `url`.
+    Started { url: String },
+    /// The page at `url` has finished loading.
+    Finished { url: String },
 }
 
-#[repr(C)]
-pub struct Context<'a, 'b, H: EditorHandler> {
-    handler: &'a WindowHandler,
-    window: &'a mut Window<'b>,
-    _p: PhantomData<H>,
+/// A native file drag-and-drop event, see [`EditorHandler::on_file_drop`].
+///
+/// Positions are in logical pixels relative to the webview, the same
+/// coordinate space as the window size.
+///
+/// `zone` is the `data-plugin-dropzone` attribute of the element under the
+/// cursor, or its closest ancestor having one. While files are dragged over a
+/// drop zone, it has the `plugin-dropzone-active` class. The zone is looked
+/// up by the page once per animation frame, so it may lag a frame behind the
+/// position.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DropEvent {
+    /// Files are being dragged over the webview.
+    Hovered { paths: Vec<PathBuf>, position: (f64, f64), zone: Option<String> },
+    /// Files have been dropped onto the webview.
+    Dropped { paths: Vec<PathBuf>, position: (f64, f64), zone: Option<String> },
+    /// The drag has left the webview or has been cancelled.
+    Cancelled,
 }
 
-impl<'a, 'b, H: EditorHandler> Context<'a, 'b, H> {
-    /// Send a message to the plugin.
-    pub fn send_message(&mut self, message: H::EditorTx) {
-        self.handler.send_json(message);
-    }
-
-    /// Resize the window to the given size (in logical pixels).
-    ///
-    /// Do note that plugin host may refuse to resize the window, in which case
-    /// this method will return `false`.
-    pub fn resize_window(&mut self, width: u32, height: u32) -> bool {
-        self.handler.resize(self.window, width, height)
-    }
-
-    /// Returns `true` if plugin parameters have changed since the last call to this method.
-    pub fn params_changed(&mut self) -> bool {
-        self.handler.params_changed.swap(false, Ordering::SeqCst)
-    }
-
-    /// Returns a `ParamSetter` which can be used to set parameter values.
-    pub fn get_setter(&self) -> ParamSetter {
-        ParamSetter::new(&*self.handler.context)
-    }
+/// What's known about the host running the plugin, see
+/// [`Context::host_info`]. Also available to the page as
+/// `window.plugin.host`, with the fields in camelCase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct HostInfo {
+    /// The plugin API the plugin was loaded through, e.g. `"CLAP"`, `"VST3"`
+    /// or `"Standalone"`.
+    pub plugin_api: String,
+    /// The host's name. nih-plug does not report it yet, so this is always
+    /// `None` for now.
+    pub host_name: Option<String>,
+    /// The operating system, e.g. `"windows"` or `"macos"`.
+    pub os: &'static str,
+}
 
-    /// Returns a reference to the `WebView` used by the editor.
-    pub fn get_webview(&self) -> &WebView {
-        &self.handler.webview
+impl HostInfo {
+    fn new(context: &dyn GuiContext) -> HostInfo {
+        HostInfo {
+            plugin_api: context.plugin_api().to_string(),
+            host_name: None,
+            os: std::env::consts::OS,
+        }
     }
 }
 
-/// `nih_plug_webview`'s state that should be persisted between sessions (like window size).
-///
-/// Add it as a persistent parameter to your plugin's state.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct WebviewState {
-    /// The window's size in logical pixels before applying `scale_factor`.
-    #[serde(with = "nih_plug::params::persist::serialize_atomic_cell")]
-    size: AtomicCell<(u32, u32)>,
+/// Whether the plugin runs as a standalone application or inside a host, see
+/// [`Context::execution_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Running through nih-plug's standalone wrapper, so the editor's window
+    /// belongs to the plugin.
+    Standalone,
+    /// Loaded by a host through this plugin API. The host owns the window.
+    Hosted(PluginApi),
 }
 
-impl WebviewState {
-    /// Initialize the GUI's state. The window size is in logical pixels, so
-    /// before it is multiplied by the DPI scaling factor.
-    pub fn new(width: u32, height: u32) -> Arc<WebviewState> {
-        Arc::new(WebviewState { size: AtomicCell::new((width, height)) })
+impl ExecutionMode {
+    fn new(context: &dyn GuiContext) -> ExecutionMode {
+        match context.plugin_api() {
+            PluginApi::Standalone => ExecutionMode::Standalone,
+            api => ExecutionMode::Hosted(api),
+        }
     }
 
-    /// Returns a `(width, height)` pair for the current size of the GUI in
-    /// logical pixels.
-    pub fn size(&self) -> (u32, u32) {
-        self.size.load()
+    pub fn is_standalone(&self) -> bool {
+        *self == ExecutionMode::Standalone
     }
 }
 
-impl<'a> PersistentField<'a, WebviewState> for Arc<WebviewState> {
-    fn set(&self, new_value: WebviewState) {
-        self.size.store(new_value.size.load());
-    }
+/// Errors which may occur while setting up the editor's webview.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WebviewError {
+    /// `wry` failed to build the webview, for example because the webview
+    /// runtime (WebView2 or WebKitGTK) is missing or broken.
+    Build(wry::Error),
+    /// The webview's data directory could not be created, or is a directory
+    /// like the file system root which the crate refuses to use. The editor
+    /// falls back to a temporary directory.
+    Workdir(PathBuf, std::io::Error),
+    /// The WebView2 runtime is not installed on Windows. See
+    /// [`WebviewConfig::runtime_missing_ui`] for what's shown instead.
+    MissingRuntime { download_url: &'static str },
+    /// An overlay was created with a [`WebviewSource::CustomProtocol`], see
+    /// [`OverlayConfig::source`].
+    UnsupportedOverlaySource,
+    /// The host's window can't be embedded into, e.g. a Wayland surface on
+    /// Linux where only X11 is supported, or an X11 window ID of 0. The
+    /// editor stays empty, and will try again the next time it's opened.
+    UnsupportedWindowing,
+}
 
-    fn map<F, R>(&self, f: F) -> R
-    where
-        F: Fn(&WebviewState) -> R,
-    {
-        f(self)
+impl std::fmt::Display for WebviewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebviewError::Build(err) => write!(f, "failed to construct webview: {err}"),
+            WebviewError::Workdir(path, err) => {
+                write!(f, "can't use {} as the webview's data directory: {err}", path.display())
+            }
+            WebviewError::MissingRuntime { download_url } => write!(
+                f,
+                "the Microsoft Edge WebView2 runtime is not installed, get it from {download_url}"
+            ),
+            WebviewError::UnsupportedOverlaySource => {
+                write!(f, "overlays can't be loaded from a custom protocol")
+            }
+            WebviewError::UnsupportedWindowing => {
+                write!(f, "the host's window can't be embedded into on this platform")
+            }
+        }
     }
 }
 
-struct Config {
-    title: String,
-    state: Arc<WebviewState>,
-    source: WebviewSource,
-    handler: Box<Mutex<dyn EditorHandlerAny>>,
-    context_dir: PathBuf,
-    with_webview_fn: Mutex<Box<dyn Fn(WebViewBuilder) -> WebViewBuilder + Send + Sync + 'static>>,
+impl std::error::Error for WebviewError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WebviewError::Build(err) => Some(err),
+            WebviewError::Workdir(_, err) => Some(err),
+            WebviewError::MissingRuntime { .. }
+            | WebviewError::UnsupportedOverlaySource
+            | WebviewError::UnsupportedWindowing => None,
+        }
+    }
 }
 
-/// A webview-based editor.
-pub struct WebviewEditor {
-    config: Arc<Config>,
-    params_changed: Arc<AtomicBool>,
+/// Returned when a message can't be sent to the page.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SendError {
+    /// The message could not be serialized.
+    Serialize(serde_json::Error),
+    /// There is no webview, because it could not be constructed.
+    NoWebview,
+    /// The webview refused to evaluate the script delivering the message.
+    /// A crashed renderer process is not detected here, the webviews accept
+    /// scripts for it regardless and the message is lost. Use
+    /// [`WatchdogPolicy::Reload`] to recover from that.
+    Script(wry::Error),
 }
 
-impl WebviewEditor {
-    /// Creates a new `WebviewEditor`.
-    pub fn new(
-        title: String,
-        source: WebviewSource,
-        state: Arc<WebviewState>,
-        handler: impl EditorHandler,
-        context_dir: PathBuf,
-    ) -> WebviewEditor {
-        WebviewEditor {
-            config: Arc::new(Config {
-                title,
-                state,
-                source,
-                handler: Box::new(Mutex::new(handler)),
-                context_dir,
-                with_webview_fn: Mutex::new(Box::new(|w| w)),
-            }),
-            params_changed: Arc::new(AtomicBool::new(false)),
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Serialize(err) => write!(f, "can't serialize the message: {err}"),
+            SendError::NoWebview => write!(f, "there is no webview to send the message to"),
+            SendError::Script(err) => write!(f, "the webview failed to receive the message: {err}"),
         }
     }
+}
 
-    /// Creates a new `WebviewEditor` with a callback which allows to configure
-    /// `WebViewBuilder`. Do note that some options will be overridden by the
-    /// `EditorHandler` abstraction in order for it to function properly. To see
-    /// which options are overridden, see the `Editor::spawn` implementation
-    /// for the `WebviewEditor`.
-    pub fn new_with_webview(
-        title: String,
-        source: WebviewSource,
-        state: Arc<WebviewState>,
-        handler: impl EditorHandler,
-        context_dir: PathBuf,
-        f: impl Fn(WebViewBuilder) -> WebViewBuilder + Send + Sync + 'static,
-    ) -> WebviewEditor {
-        WebviewEditor {
-            config: Arc::new(Config {
-                title,
-                state,
-                source,
-                handler: Box::new(Mutex::new(handler)),
-                context_dir,
-                with_webview_fn: Mutex::new(Box::new(f)),
-            }),
-            params_changed: Arc::new(AtomicBool::new(false)),
+impl std::error::Error for SendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SendError::Serialize(err) => Some(err),
+            SendError::NoWebview => None,
+            SendError::Script(err) => Some(err),
         }
     }
 }
 
-impl Editor for WebviewEditor {
-    fn spawn(
-        &self,
-        parent: nih_plug::prelude::ParentWindowHandle,
-        context: Arc<dyn GuiContext>,
-    ) -> Box<dyn std::any::Any + Send> {
-        let (width, height) = self.config.state.size.load();
-
-        let options = WindowOpenOptions {
-            scale: WindowScalePolicy::SystemScaleFactor,
-            size: Size { width: width as f64, height: height as f64 },
-            title: self.config.title.clone(),
-            gl_config: None,
-        };
-
-        let config = self.config.clone();
-        let params_changed = self.params_changed.clone();
+impl From<serde_json::Error> for SendError {
+    fn from(err: serde_json::Error) -> Self {
+        SendError::Serialize(err)
+    }
+}
 
-        let window_handle = baseview::Window::open_parented(&parent, options, move |mut window| {
-            let Config { title: _, state, source, handler, context_dir, with_webview_fn } =
-                &*config;
+/// Handles the editor's messages and events.
+///
+/// All methods are called on the editor's thread, one at a time, and never
+/// from the host's callbacks. Parameter changes made by the host are recorded
+/// and delivered on the next frame, after the messages the page posted until
+/// then. [`Context::sequence`] numbers the messages and parameter updates in
+/// the order they're handled, so the page can tell which one is newer.
+pub trait EditorHandler: Sized + Send + Sync + 'static {
+    /// Message type sent from the handler to the editor.
+    type EditorTx: Serialize;
+    /// Message type sent from the editor to the handler.
+    type EditorRx: DeserializeOwned;
 
-            let (webview_to_editor_tx, webview_rx) = crossbeam::channel::unbounded();
+    fn init(&mut self, cx: &mut Context<Self>);
+    fn on_frame(&mut self, cx: &mut Context<Self>);
+    fn on_message(&mut self, cx: &mut Context<Self>, message: Self::EditorRx);
+    fn on_window_event(&mut self, cx: &mut Context<Self>, event: Event) -> EventStatus {
+        let _ = (cx, event);
+        EventStatus::Ignored
+    }
 
-            let mut webview_builder = WebViewBuilder::new_as_child(window);
+    /// Called each time the editor window is opened, right after the webview
+    /// has been created and [`EditorHandler::init`] has run. This is the place
+    /// to send the page everything that may have changed while the editor was
+    /// closed. [`Context::params_changed`] also returns `true` on the first
+    /// frame after opening.
+    fn on_window_open(&mut self, cx: &mut Context<Self>) {
+        let _ = cx;
+    }
 
-            // Apply user configuration.
-            webview_builder = with_webview_fn.lock().unwrap()(webview_builder);
+    /// Called when this handler replaced another one through
+    /// [`Context::replace_handler`], e.g. to send the page its initial UI.
+    /// The window is already open by then, so [`EditorHandler::init`] and
+    /// [`EditorHandler::on_window_open`] are only called when it's opened
+    /// the next time.
+    fn on_attach(&mut self, cx: &mut Context<Self>) {
+        let _ = cx;
+    }
 
-            //
-            // Configure the webview.
+    /// Called right before this handler is replaced through
+    /// [`Context::replace_handler`], e.g. to save what the page is showing.
+    fn on_detach(&mut self, cx: &mut Context<Self>) {
+        let _ = cx;
+    }
 
-            let (width, height) = state.size.load();
+    /// Called at most once per frame with the IDs and normalized values of all
+    /// parameters changed since the previous frame, if
+    /// [`WebviewConfig::coalesce_param_updates`] is enabled.
+    /// [`Context::params_changed`] keeps working either way.
+    fn on_params_batch_changed(&mut self, cx: &mut Context<Self>, changes: &[(String, f32)]) {
+        let _ = (cx, changes);
+    }
 
-            let mut web_context = WebContext::new(Some(context_dir.clone()));
+    /// Called for key events the page did not handle, if
+    /// [`WebviewConfig::forward_unhandled_keys`] is enabled. Return
+    /// [`EventStatus::Captured`] to keep the event from being passed on to the
+    /// host.
+    fn on_key_event(&mut self, cx: &mut Context<Self>, event: &KeyboardEvent) -> EventStatus {
+        let _ = (cx, event);
+        EventStatus::Ignored
+    }
 
-            let webview_builder = webview_builder
-                .with_bounds(wry::Rect { x: 0, y: 0, width, height })
-                .with_ipc_handler(move |msg: String| {
-                    if let Ok(json_value) = serde_json::from_str(&msg) {
-                        let _ = webview_to_editor_tx.send(json_value);
-                    } else {
-                        panic!("Invalid JSON from webview: {}.", msg);
-                    }
-                })
-                .with_web_context(&mut web_context);
-
-            let webview = match (*source).clone() {
-                WebviewSource::URL(url) => webview_builder.with_url(url.as_str()),
-                WebviewSource::HTML(html) => webview_builder.with_html(html),
-                WebviewSource::DirPath(root) => webview_builder
-                    .with_custom_protocol(
-                        "wry".to_string(), //
-                        move |request| match get_wry_response(&root, request) {
-                            Ok(r) => r.map(Into::into),
-                            Err(e) => http::Response::builder()
-                                .header(CONTENT_TYPE, "text/plain")
-                                .status(500)
-                                .body(e.to_string().as_bytes().to_vec())
-                                .unwrap()
-                                .map(Into::into),
-                        },
-                    )
-                    .with_url("wry://localhost"),
-                WebviewSource::CustomProtocol { url_path: url, protocol } => {
-                    webview_builder.with_url(format!("{protocol}://localhost/{url}").as_str())
-                }
-            }
-            .unwrap()
-            .build()
-            .expect("Failed to construct webview. {}");
+    /// Called on right-clicks, unless [`WebviewConfig::native_context_menu`]
+    /// is enabled or the page called `preventDefault` on the `contextmenu`
+    /// event. `x` and `y` are in logical pixels relative to the webview.
+    /// `element_info` is the `data-context-menu` attribute of the closest
+    /// element having one, or otherwise a `tag#id.class` description of the
+    /// clicked element.
+    fn on_context_menu(&mut self, cx: &mut Context<Self>, x: f64, y: f64, element_info: String) {
+        let _ = (cx, x, y, element_info);
+    }
 
-            let window_handler = WindowHandler {
-                config: config.clone(),
-                context,
-                webview,
-                webview_rx,
-                params_changed,
-            };
+    /// Called with the messages posted by tasks started with
+    /// [`Context::spawn_background`]. Use `message.downcast::<T>()` to get the
+    /// posted value back.
+    fn on_background_message(&mut self, cx: &mut Context<Self>, message: BackgroundMessage) {
+        let _ = (cx, message);
+    }
 
-            let mut handler = handler.lock().unwrap();
-            let mut cx = window_handler.context(&mut window);
-            handler.init(&mut cx);
+    /// Called on every frame with the values sent through the
+    /// [`editor_channel`]s added with [`WebviewEditor::with_editor_channel`].
+    /// Use `message.downcast::<T>()` to get the sent value back.
+    fn on_audio_message(&mut self, cx: &mut Context<Self>, message: AudioMessage) {
+        let _ = (cx, message);
+    }
 
-            window_handler
-        });
+    /// Called for messages from the webview with a verb the crate doesn't
+    /// know, e.g. from a newer `lib.js`. `payload` is the message's payload as
+    /// JSON. By default the verb is logged, at most once every few seconds,
+    /// and [`WebviewConfig::log_ipc`] shows the payloads.
+    fn on_unknown_ipc(&mut self, cx: &mut Context<Self>, verb: &str, payload: String) {
+        let _ = payload;
+        cx.handler.warnings.warn(format!("Ignoring unknown message from webview: {verb}."));
+    }
 
-        return Box::new(EditorHandle { window_handle });
+    /// Called with messages sent with `window.plugin.channel(channel).send`.
+    /// `payload` is the message as JSON.
+    fn on_channel_message(&mut self, cx: &mut Context<Self>, channel: &str, payload: String) {
+        let _ = (cx, channel, payload);
     }
 
-    fn size(&self) -> (u32, u32) {
-        self.config.state.size.load()
+    /// Called when a page starts or finishes loading, including reloads
+    /// triggered from the devtools. [`PageLoadEvent::Finished`] is a good
+    /// place to push the initial state to the frontend.
+    fn on_page_load(&mut self, cx: &mut Context<Self>, event: PageLoadEvent) {
+        let _ = (cx, event);
     }
 
-    fn set_scale_factor(&self, _factor: f32) -> bool {
-        // TODO: implement for Windows and Linux
-        return false;
+    /// Called when `lib.js` did not start in a loaded page, even after being
+    /// evaluated once more, so the page has no `window.plugin` to reach the
+    /// editor with. This is usually caused by a strict
+    /// `Content-Security-Policy`, see [`content_security_policy`]. `reason` is
+    /// also logged as an error.
+    fn on_bridge_unavailable(&mut self, cx: &mut Context<Self>, reason: &str) {
+        let _ = (cx, reason);
     }
 
-    fn param_values_changed(&self) {
-        self.params_changed.store(true, Ordering::SeqCst);
+    /// Called when edits recorded with [`Context::undo_bridge`] become or stop
+    /// being undoable or redoable, and once after the editor opened, e.g. to
+    /// enable the page's undo and redo buttons.
+    fn on_undo_state_changed(&mut self, cx: &mut Context<Self>, can_undo: bool, can_redo: bool) {
+        let _ = (cx, can_undo, can_redo);
     }
 
-    fn param_value_changed(&self, _id: &str, _normalized_value: f32) {
-        self.params_changed.store(true, Ordering::SeqCst);
+    /// Called when files from the file manager are dragged over or dropped
+    /// onto the webview. Unlike the DOM's drop events, this provides the
+    /// files' actual paths.
+    fn on_file_drop(&mut self, cx: &mut Context<Self>, event: DropEvent) {
+        let _ = (cx, event);
     }
 
-    fn param_modulation_changed(&self, _id: &str, _modulation_offset: f32) {
-        self.params_changed.store(true, Ordering::SeqCst);
+    /// Called when a dialog opened with [`Context::pick_file`] is closed, with
+    /// the token returned from that call. `paths` is `None` if the dialog was
+    /// cancelled.
+    #[cfg(feature = "file-dialog")]
+    fn on_file_dialog_result(
+        &mut self,
+        cx: &mut Context<Self>,
+        token: u64,
+        paths: Option<Vec<PathBuf>>,
+    ) {
+        let _ = (cx, token, paths);
     }
-}
 
-/// A handle to the editor window, returned from [`Editor::spawn`]. Host will
-/// call [`drop`] on it when the window is supposed to be closed.
-struct EditorHandle {
-    window_handle: baseview::WindowHandle,
-}
+    /// Called when the window has been resized, either by the host or through
+    /// [`Context::resize_window`]. The size is the window's, in logical
+    /// pixels. The content size it shows has already been stored in the
+    /// [`WebviewState`].
+    fn on_window_resized(&mut self, cx: &mut Context<Self>, width: f64, height: f64) {
+        let _ = (cx, width, height);
+    }
 
-unsafe impl Send for EditorHandle {}
+    /// Called on the first frame and whenever the transport changed, when the
+    /// editor was created with [`WebviewEditor::with_transport`].
+    fn on_transport(&mut self, cx: &mut Context<Self>, transport: &TransportSnapshot) {
+        let _ = (cx, transport);
+    }
 
-impl Drop for EditorHandle {
-    fn drop(&mut self) {
-        self.window_handle.close();
+    /// Called when the window was moved to a monitor with a different scale
+    /// factor, e.g. from 100% to 150%. The logical size stored in the
+    /// [`WebviewState`] is kept, the webview is resized to match it at the new
+    /// scale and the host is asked to resize the window. Only reported on
+    /// Windows.
+    fn on_scale_factor_changed(&mut self, cx: &mut Context<Self>, factor: f64) {
+        let _ = (cx, factor);
     }
-}
 
-/// This structure manages the editor window's event loop.
-struct WindowHandler {
-    config: Arc<Config>,
-    webview: WebView,
-    context: Arc<dyn GuiContext>,
-    params_changed: Arc<AtomicBool>,
-    webview_rx: Receiver<Value>,
-}
+    /// Called for the page's console output, uncaught errors and unhandled
+    /// promise rejections if [`WebviewConfig::capture_console`] is enabled,
+    /// e.g. to attach them to crash reports. They are also logged through
+    /// nih-plug. At most 50 messages per second are passed on, the rest are
+    /// counted and dropped.
+    fn on_console(&mut self, cx: &mut Context<Self>, level: ConsoleLevel, message: String) {
+        let _ = (cx, level, message);
+    }
 
-impl WindowHandler {
-    fn context<'a, 'b>(&'a self, window: &'a mut Window<'b>) -> Context<'a, 'b, ()> {
-        Context { handler: self, window, _p: PhantomData }
+    /// Called when the host refused to resize the window to the same size
+    /// several times in a row, e.g. because it locks the size of plugin
+    /// windows. The page receives the size on the reserved `resize-denied`
+    /// channel, as `{ width, height }`, so it can switch to a layout which
+    /// fits the current size. See also [`Context::can_resize`].
+    fn on_resize_denied(&mut self, cx: &mut Context<Self>, requested: (f64, f64)) {
+        let _ = (cx, requested);
     }
 
-    pub fn resize(&self, window: &mut baseview::Window, width: u32, height: u32) -> bool {
-        let old = self.config.state.size.swap((width, height));
+    /// Called with a message another instance of the
+    /// [`WebviewConfig::instance_group`] sent with
+    /// [`Context::broadcast_to_peers`], while both editors are open. The page
+    /// receives it on the reserved `peers` channel, as `{ from, message }`.
+    fn on_peer_message(&mut self, cx: &mut Context<Self>, from: InstanceId, message: String) {
+        let _ = (cx, from, message);
+    }
 
-        if !self.context.request_resize() {
-            // Resize failed.
-            self.config.state.size.store(old);
-            return false;
-        }
+    /// Called when the editor window became hidden or visible again, e.g.
+    /// because the host window was minimized. Frames keep being processed
+    /// while the window is hidden, so this is the signal to skip expensive
+    /// updates nobody can see. Not reported on Linux.
+    fn on_visibility_changed(&mut self, cx: &mut Context<Self>, visible: bool) {
+        let _ = (cx, visible);
+    }
 
-        window.resize(Size { width: width as f64, height: height as f64 });
+    /// Called once the page sent nothing for the timeout of the
+    /// [`WebviewConfig::watchdog`], with how long it has been silent while
+    /// the window was visible. Called again only after the page responded or
+    /// was reloaded.
+    fn on_unresponsive(&mut self, cx: &mut Context<Self>, elapsed: Duration) {
+        let _ = (cx, elapsed);
+    }
 
-        self.webview.set_bounds(wry::Rect { x: 0, y: 0, width, height });
+    /// Called for messages an overlay created with [`Context::create_overlay`]
+    /// posted with `window.ipc.postMessage(message)`. Overlays don't load
+    /// `lib.js`, so the message arrives as it was posted.
+    fn on_message_from(&mut self, cx: &mut Context<Self>, overlay: OverlayId, message: String) {
+        let _ = (cx, overlay, message);
+    }
 
-        true
+    /// Called when the page gains or loses the keyboard focus, e.g. because
+    /// the user clicked into the host. The page also receives a
+    /// `plugin-focus` event on `window` with `detail.focused`.
+    fn on_focus_changed(&mut self, cx: &mut Context<Self>, focused: bool) {
+        let _ = (cx, focused);
     }
 
-    pub fn send_json<T: serde::Serialize>(&self, json: T) {
-        if let Ok(json_str) = serde_json::to_string(&json) {
-            self.webview
-                .evaluate_script(&format!("window.plugin.__ipc.recvMessage(`{}`);", json_str))
-                .unwrap();
-        } else {
-            panic!("Can't convert JSON to string.");
-        }
+    /// Called when the editor becomes idle or the user interacts with it
+    /// again, see [`Context::is_idle`].
+    fn on_idle_changed(&mut self, cx: &mut Context<Self>, idle: bool) {
+        let _ = (cx, idle);
     }
 
-    pub fn next_message(&self) -> Result<Value, crossbeam::channel::TryRecvError> {
-        self.webview_rx.try_recv()
+    /// Called when setting up the webview failed, before
+    /// [`EditorHandler::init`]. If the webview could not be constructed, the
+    /// editor falls back to a minimal error page (or an empty window) instead
+    /// of taking down the host.
+    fn on_error(&mut self, err: &WebviewError) {
+        let _ = err;
     }
+
+    /// Called when the host closes the editor window. No [`Context`] is passed,
+    /// as the webview may already be gone at this point.
+    fn on_window_close(&mut self) {}
 }
 
-impl baseview::WindowHandler for WindowHandler {
-    fn on_frame(&mut self, window: &mut baseview::Window) {
-        let mut handler = self.config.handler.lock().unwrap();
-        let mut cx = self.context(window);
+/// The parts of [`Context`] which don't need a real webview or host.
+/// Implemented by [`Context`] and, with the `testing` feature, by
+/// [`testing::MockContext`], so message handling written against this trait
+/// can be unit tested.
+pub trait EditorContext<H: EditorHandler> {
+    /// See [`Context::send_message`].
+    fn send_message(&mut self, message: H::EditorTx) -> Result<(), SendError>;
+    /// See [`Context::resize_window`].
+    fn resize_window(&mut self, width: u32, height: u32) -> bool;
+    /// See [`Context::begin_gesture_by_id`].
+    fn begin_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError>;
+    /// See [`Context::set_param_normalized_by_id`].
+    fn set_param_normalized_by_id(&self, id: &str, normalized: f32) -> Result<(), ParamIdError>;
+    /// See [`Context::end_gesture_by_id`].
+    fn end_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError>;
+}
 
-        // Call on_message for each message received from the webview.
-        while let Ok(event) = self.next_message() {
-            handler.on_message(&mut cx, event);
-        }
+impl<'a, 'b, H: EditorHandler> EditorContext<H> for Context<'a, 'b, H> {
+    fn send_message(&mut self, message: H::EditorTx) -> Result<(), SendError> {
+        Context::send_message(self, message)
+    }
 
-        handler.on_frame(&mut cx);
+    fn resize_window(&mut self, width: u32, height: u32) -> bool {
+        Context::resize_window(self, width, height)
     }
 
-    fn on_event(&mut self, window: &mut baseview::Window, event: Event) -> EventStatus {
-        // Focus the webview so that it can receive keyboard events.
-        self.webview.focus();
+    fn begin_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError> {
+        Context::begin_gesture_by_id(self, id)
+    }
 
-        let mut handler = self.config.handler.lock().unwrap();
-        let mut cx = self.context(window);
+    fn set_param_normalized_by_id(&self, id: &str, normalized: f32) -> Result<(), ParamIdError> {
+        Context::set_param_normalized_by_id(self, id, normalized)
+    }
 
-        handler.on_window_event(&mut cx, event)
+    fn end_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError> {
+        Context::end_gesture_by_id(self, id)
     }
 }
 
-//
-//
-//
+#[repr(C)]
+pub struct Context<'a, 'b, H: EditorHandler> {
+    handler: &'a WindowHandler,
+    window: &'a mut Window<'b>,
+    _p: PhantomData<H>,
+}
 
-impl EditorHandler for () {
-    type EditorRx = ();
+impl<'a, 'b, H: EditorHandler> Context<'a, 'b, H> {
+    /// Send a message to the plugin. Fails if there is no webview or the
+    /// webview didn't accept the message, in which case it's lost.
+    pub fn send_message(&mut self, message: H::EditorTx) -> Result<(), SendError> {
+        self.handler.send_json(message)
+    }
+
+    /// Send any serializable value to the plugin. Listeners registered with
+    /// `window.plugin.listenJson` receive it already parsed, the ones
+    /// registered with `window.plugin.listen` receive the JSON string.
+    pub fn send_json<T: Serialize>(&mut self, value: &T) -> Result<(), SendError> {
+        self.handler.send_json(value)
+    }
+
+    /// Returns the named channel `name`, for sending messages to the
+    /// listeners registered with `window.plugin.channel(name).listen`.
+    /// Messages sent with [`Context::send_message`] are not affected by
+    /// channels.
+    pub fn channel(&self, name: &str) -> Channel<'_> {
+        Channel { handler: self.handler, name: name.to_string() }
+    }
+
+    /// Resize the window to show the page at the given size, in CSS pixels.
+    /// The window's logical size is this times the zoom factor, see
+    /// [`WebviewState::content_size`].
+    ///
+    /// The size is first snapped according to the configured [`ResizePolicy`].
+    /// Do note that plugin host may refuse to resize the window, in which case
+    /// this method will return `false`. Under a [`ResizeThrottle`], the
+    /// request is only passed to the host later and `true` is returned right
+    /// away.
+    pub fn resize_window(&mut self, width: u32, height: u32) -> bool {
+        self.handler.resize(self.window, width, height)
+    }
+
+    /// The [`GpuPolicy`] the webview was created with, e.g. for bug reports.
+    /// Always [`GpuPolicy::Auto`] where [`WebviewConfig::gpu_acceleration`]
+    /// can't be applied. This is only the configured policy: whether the
+    /// webview actually renders on the GPU, or fell back to software under
+    /// `Auto`, is not reported by any of the webviews.
+    pub fn configured_gpu_policy(&self) -> GpuPolicy {
+        if cfg!(target_os = "windows") {
+            self.handler.config.options.gpu_acceleration
+        } else {
+            GpuPolicy::Auto
+        }
+    }
+
+    /// The user agent of the webview, e.g. for bug reports. Reported by
+    /// `lib.js` once it loaded, before that this is the configured
+    /// [`WebviewConfig::user_agent`], if any.
+    pub fn user_agent(&self) -> Option<String> {
+        let reported = self.handler.user_agent.borrow().clone();
+        reported.or_else(|| self.handler.config.options.user_agent.clone())
+    }
+
+    /// The value of the tap `id` added with
+    /// [`WebviewEditor::with_display_value`], as last sent to the page.
+    /// `None` for unknown IDs, and before the value was first read.
+    pub fn display_value(&self, id: &str) -> Option<f32> {
+        self.handler.display_values.get(&self.handler.config.display_taps, id)
+    }
+
+    /// Whether the host resizes the window when asked to, as learned from the
+    /// last resize requests. `None` until the host accepted or repeatedly
+    /// refused a request. Kept while the editor is closed and reopened.
+    pub fn can_resize(&self) -> Option<bool> {
+        self.handler.config.can_resize.load()
+    }
+
+    /// Identifies this plugin instance in the messages to the other
+    /// instances of its [`WebviewConfig::instance_group`].
+    pub fn instance_id(&self) -> InstanceId {
+        self.handler.config.instance_id
+    }
+
+    /// Sends `message` to the open editors of the other instances in the
+    /// [`WebviewConfig::instance_group`], which receive it in
+    /// [`EditorHandler::on_peer_message`]. Also available as
+    /// `window.plugin.peers.broadcast(message)`. Returns how many editors
+    /// it reached, `0` without a group. Messages longer than 64 KiB are
+    /// dropped.
+    pub fn broadcast_to_peers(&self, message: &str) -> usize {
+        self.handler.broadcast_to_peers(message)
+    }
+
+    /// Sets the title of the standalone application's window, e.g. to show
+    /// the loaded preset. Does nothing when running inside a host, as hosts
+    /// own the titles of their plugin windows.
+    pub fn set_window_title(&mut self, title: &str) {
+        if self.execution_mode().is_standalone() {
+            platform::set_window_title(self.window, title);
+        }
+    }
+
+    /// Whether the editor window can currently be seen. `false` while e.g.
+    /// the host window is minimized, see
+    /// [`EditorHandler::on_visibility_changed`]. Always `true` on Linux.
+    pub fn is_window_visible(&self) -> bool {
+        self.handler.visible.get()
+    }
+
+    /// Whether the user hasn't interacted with the page for
+    /// [`WebviewConfig::idle_timeout`], e.g. to update meters less often.
+    /// Pages can keep the editor from becoming idle during an animation with
+    /// `window.plugin.reportActivity()`. See also
+    /// [`EditorHandler::on_idle_changed`].
+    pub fn is_idle(&self) -> bool {
+        self.handler.idle.get()
+    }
+
+    /// Opens a secondary webview on top of the editor's own, e.g. a floating
+    /// tuner strip. It's closed together with the editor window.
+    pub fn create_overlay(&mut self, config: OverlayConfig) -> Result<OverlayHandle, WebviewError> {
+        let handler = self.handler;
+        let id = OverlayId(handler.next_overlay.get());
+        handler.next_overlay.set(id.0 + 1);
+
+        let messages = handler.overlay_messages.0.clone();
+        let webview =
+            overlay::build(self.window, id, config, handler.scale_factor.get(), messages)?;
+        handler.overlays.borrow_mut().insert(id, webview);
+        Ok(OverlayHandle { id })
+    }
+
+    /// The directory the webview keeps its data in, e.g. to tell the page
+    /// where downloads end up. `None` if no directory could be created.
+    pub fn workdir(&self) -> Option<&Path> {
+        self.handler.workdir.as_ref().map(Workdir::path)
+    }
+
+    /// Whether the plugin runs as a standalone application or inside a host.
+    /// Only the standalone application's window title and position are set
+    /// by the editor, and on macOS it gets a menu bar with a "Quit" item.
+    /// Also available to the page as `window.plugin.executionMode`, either
+    /// `"standalone"` or `"hosted"`, once `onReady` callbacks are called.
+    pub fn execution_mode(&self) -> ExecutionMode {
+        ExecutionMode::new(&*self.handler.context)
+    }
+
+    /// What's known about the host running the plugin.
+    pub fn host_info(&self) -> HostInfo {
+        HostInfo::new(&*self.handler.context)
+    }
+
+    /// Returns facts for bug reports as JSON: the crate's and wry's versions,
+    /// the webview's user agent, the data directory and whether it's
+    /// writable, the last error creating the editor, and the last 200 console
+    /// messages if [`WebviewConfig::capture_console`] is enabled.
+    pub fn diagnostics_report(&self) -> String {
+        self.handler.diagnostics_report()
+    }
+
+    /// Shows the [`Context::diagnostics_report`] in an overlay covering the
+    /// editor, with a button to copy it. It also reports the GPU the webview
+    /// renders with. Works even if the editor's page is broken, e.g. from a
+    /// hidden shortcut. Also available as `window.plugin.openDiagnostics()`.
+    pub fn open_diagnostics(&mut self) -> Result<(), WebviewError> {
+        if let Some(id) = self.handler.diagnostics.take() {
+            self.handler.overlays.borrow_mut().remove(&id);
+        }
+
+        let page = diagnostics::page(&self.diagnostics_report());
+        let overlay = self.create_overlay(OverlayConfig {
+            source: WebviewSource::HTML(page),
+            position: (0, 0),
+            size: self.handler.config.state.size(),
+            transparent: false,
+        })?;
+        self.handler.diagnostics.set(Some(overlay.id()));
+        Ok(())
+    }
+
+    /// Gives the webview the keyboard focus, e.g. after opening a text input
+    /// in the page.
+    pub fn focus(&mut self) {
+        if let Some(webview) = &self.handler.webview {
+            webview.focus();
+        }
+    }
+
+    /// Takes the keyboard focus away from the page, so that key presses go to
+    /// the host again.
+    pub fn blur(&mut self) {
+        self.handler.evaluate_script("document.activeElement && document.activeElement.blur();");
+        platform::focus_window(self.window);
+    }
+
+    /// Hides or shows the mouse cursor, e.g. while dragging an infinite knob
+    /// together with [`Context::set_cursor_position`]. The cursor is shown
+    /// again when the page loses focus or the editor closes. Also available
+    /// as `window.plugin.cursor.hide()` and `show()`. Not supported on Linux.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.handler.set_cursor_visible(visible);
+    }
+
+    /// Moves the mouse cursor to a position in logical pixels relative to the
+    /// webview's top left corner. Also available as
+    /// `window.plugin.cursor.setPosition(x, y)`. Returns `false` if this is
+    /// not supported on the current platform, which includes Linux.
+    pub fn set_cursor_position(&mut self, x: f64, y: f64) -> bool {
+        platform::set_cursor_position(self.window, (x, y), self.handler.scale_factor.get())
+    }
+
+    /// Reloads the page. `lib.js` repeats its handshake on every page load,
+    /// so parameter values are sent again and `window.plugin.onReady`
+    /// callbacks run as on the first load. A [`WebviewSource::HTML`] page is
+    /// loaded again from its HTML, as the webviews can't reload inline HTML.
+    pub fn reload(&mut self) {
+        let handler = self.handler;
+        match &handler.config.source {
+            WebviewSource::HTML(html) => {
+                self.load_html(html);
+            }
+            _ => handler.evaluate_script("location.reload();"),
+        }
+    }
+
+    /// Navigates to another page, e.g. a license activation view. Relative
+    /// URLs are resolved against the current page. Under
+    /// [`NavigationPolicy::SameOrigin`], URLs outside the origin of the
+    /// [`WebviewSource`] are refused and `false` is returned.
+    pub fn load_url(&mut self, url: &str) -> bool {
+        let Some(webview) = &self.handler.webview else { return false };
+
+        if is_relative_url(url) {
+            let url = serde_json::to_string(url).expect("Can't convert JSON to string.");
+            self.handler.evaluate_script(&format!("location.assign({url});"));
+            return true;
+        }
+        if self.handler.config.options.navigation_policy == NavigationPolicy::SameOrigin
+            && !is_allowed_origin(&allowed_origins(&self.handler.config.source), url)
+        {
+            nih_warn!("Refusing to load {url}, which is outside of the page's origin.");
+            return false;
+        }
+        webview.load_url(url);
+        true
+    }
+
+    /// Replaces the page with `html`, loaded as a `data:` URL. This is always
+    /// allowed by the [`NavigationPolicy`]. Most webviews limit such URLs to
+    /// a few megabytes, so larger pages should be served through a
+    /// [`WebviewSource`] instead.
+    pub fn load_html(&mut self, html: &str) -> bool {
+        let Some(webview) = &self.handler.webview else { return false };
+
+        self.handler.trusted_navigation.store(true, Ordering::SeqCst);
+        webview.load_url(&data_url(html));
+        true
+    }
+
+    /// Zooms the page, e.g. for a "UI size" option. The factor is clamped to
+    /// 0.25–5.0, saved in the [`WebviewState`] and applied again when the
+    /// editor is reopened. The window is resized to keep showing the same
+    /// [`WebviewState::content_size`], which is not changed by zooming.
+    /// Returns `false` if the host refused the new window size, in which case
+    /// the zoomed page is shown in the old one.
+    pub fn set_zoom(&mut self, factor: f64) -> bool {
+        let factor = if factor.is_finite() {
+            factor.clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end())
+        } else {
+            1.0
+        };
+        let state = &self.handler.config.state;
+        state.zoom.store(factor);
+        self.handler.apply_zoom();
+        let (width, height) = state.content_size();
+        self.handler.resize(self.window, width, height)
+    }
+
+    /// Opens a native file dialog parented to the editor window, without
+    /// blocking. The selection is delivered to
+    /// [`EditorHandler::on_file_dialog_result`] together with the returned
+    /// token. Requires the `file-dialog` feature.
+    #[cfg(feature = "file-dialog")]
+    pub fn pick_file(&mut self, options: FileDialogOptions) -> u64 {
+        let token = self.handler.next_dialog_token.get();
+        self.handler.next_dialog_token.set(token + 1);
+        self.handler.open_file_dialog(self.window, options, move |paths| {
+            Box::new(move |handler, cx| handler.on_file_dialog_result(cx, token, paths))
+        });
+        token
+    }
+
+    /// Exports the plugin's current state, e.g. for saving a preset file. The
+    /// data is the same JSON nih-plug stores in the host's project.
+    pub fn export_state(&self) -> Vec<u8> {
+        preset::export(&*self.handler.context)
+    }
+
+    /// Restores a state exported with [`Context::export_state`], as the host
+    /// would when loading a project. States of older plugin versions are
+    /// migrated through `Plugin::filter_state`.
+    pub fn import_state(&self, bytes: &[u8]) -> Result<(), StateError> {
+        preset::import(&*self.handler.context, bytes)
+    }
+
+    /// Runs `f` on a new background thread, e.g. for blocking network
+    /// requests which would otherwise freeze the editor and the host. `f` can
+    /// post messages back through the [`BackgroundSender`], which arrive in
+    /// [`EditorHandler::on_background_message`] on the editor thread.
+    pub fn spawn_background<F>(&self, f: F)
+    where
+        F: FnOnce(BackgroundSender) + Send + 'static,
+    {
+        let sender = BackgroundSender(self.handler.background_messages.0.clone());
+        std::thread::spawn(move || f(sender));
+    }
+
+    /// Sends `request` from a background thread and calls `callback` with the
+    /// response on the editor thread, from a later frame, e.g. to check for
+    /// updates. Requires the `http` feature.
+    #[cfg(feature = "http")]
+    pub fn fetch(
+        &mut self,
+        request: FetchRequest,
+        callback: impl FnOnce(Result<FetchResponse, FetchError>) + 'static,
+    ) {
+        self.handler.fetches.fetch(request, Box::new(callback));
+    }
+
+    /// Returns the undo history for edits which are not parameter changes,
+    /// see [`UndoBridge`]. It's kept in the [`WebviewState`], so it outlives
+    /// the editor window.
+    pub fn undo_bridge(&self) -> UndoBridge<'_> {
+        UndoBridge {
+            history: &self.handler.config.state.undo_history,
+            limit: self.handler.config.options.undo_limit,
+        }
+    }
+
+    /// Evaluates `script` in the page and calls `callback` with the JSON of
+    /// its completion value, e.g. `"42"` for `"document.body.scrollTop"`. The
+    /// callback is always called on the editor thread, from a later frame. The
+    /// script is run with `eval`, so a Content Security Policy must allow
+    /// `'unsafe-eval'`.
+    pub fn eval_with_result(
+        &mut self,
+        script: &str,
+        callback: impl FnOnce(Result<String, EvalError>) + 'static,
+    ) {
+        self.handler.evals.eval(self.handler.webview.as_ref(), script, Box::new(callback));
+    }
+
+    /// Like [`Context::eval_with_result`], but returns a future which can be
+    /// awaited from another thread, e.g. from a task started with
+    /// [`Context::spawn_background`].
+    pub fn eval_future(&mut self, script: &str) -> EvalFuture {
+        let state = Arc::new(Mutex::new(EvalState::default()));
+        let future = EvalFuture { state: state.clone() };
+        self.eval_with_result(script, move |result| EvalState::resolve(&state, result));
+        future
+    }
+
+    /// Returns `true` if plugin parameters have changed since the last call to this method.
+    /// Also returns `true` once after the editor window opened, as the parameters may have
+    /// changed while it was closed.
+    pub fn params_changed(&mut self) -> bool {
+        self.handler.params_changed.swap(false, Ordering::SeqCst)
+    }
+
+    /// Replaces the editor's handler with `handler`, e.g. to switch between
+    /// UI modes without reopening the editor. The swap happens once the
+    /// current callback returned, before the next message from the page is
+    /// handled, and at the latest at the end of the current frame. The old
+    /// handler's [`EditorHandler::on_detach`] is called first, then the new
+    /// one's [`EditorHandler::on_attach`]. Messages from the page are never
+    /// dropped by the swap, they're handled by whichever handler is current.
+    /// The new handler stays in place when the editor is reopened.
+    pub fn replace_handler<N: EditorHandler>(&mut self, handler: N) {
+        *self.handler.next_handler.borrow_mut() = Some(Box::new(handler));
+    }
+
+    /// The position of the current message from the page or batch of
+    /// parameter changes in the order they're handled in. It increases with
+    /// every one of them, and parameter updates sent to the page carry theirs
+    /// as `sequence`. Include it in messages to the page so that it can
+    /// discard a value which is older than one it already has.
+    pub fn sequence(&self) -> u64 {
+        self.handler.sequence.get()
+    }
+
+    /// Returns a `ParamSetter` which can be used to set parameter values.
+    pub fn get_setter(&self) -> ParamSetter {
+        ParamSetter::new(&*self.handler.context)
+    }
+
+    /// Starts an automation gesture for the parameter with the given ID, and
+    /// returns the token needed to change the parameter and end the gesture.
+    /// If a gesture is already open for the parameter, it's joined instead.
+    /// Gestures still open when the editor closes are ended automatically.
+    pub fn begin_gesture(&self, id: &str) -> Result<GestureToken, ParamIdError> {
+        self.begin_gesture_by_id(id)?;
+        Ok(GestureToken { id: id.to_string() })
+    }
+
+    /// Sets the parameter of a gesture started with
+    /// [`Context::begin_gesture`] to a normalized value.
+    pub fn set_param(&self, gesture: &GestureToken, normalized: f32) {
+        // The ID was checked when the gesture was started.
+        let _ = self.set_param_normalized_by_id(&gesture.id, normalized);
+    }
+
+    /// Ends a gesture started with [`Context::begin_gesture`].
+    pub fn end_gesture(&self, gesture: GestureToken) {
+        self.handler.gestures.end(&gesture.id);
+    }
+
+    /// Returns the IDs of the parameters with an open gesture, sorted. Useful
+    /// for debugging unbalanced gestures.
+    pub fn open_gestures(&self) -> Vec<String> {
+        self.handler.gestures.open_ids()
+    }
+
+    /// Starts an automation gesture for the parameter with the given ID. Must
+    /// be paired with [`Context::end_gesture_by_id`]. Starting a gesture
+    /// which is already open has no effect.
+    pub fn begin_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError> {
+        let ptr = self.handler.param_by_id(id)?;
+        self.handler.gestures.begin(id, ptr);
+        Ok(())
+    }
+
+    /// Sets the parameter with the given ID to a normalized value. Should be
+    /// called within a gesture.
+    pub fn set_param_normalized_by_id(
+        &self,
+        id: &str,
+        normalized: f32,
+    ) -> Result<(), ParamIdError> {
+        let ptr = self.handler.param_by_id(id)?;
+        // SAFETY: The pointer is kept valid by the `ParamMap`.
+        unsafe {
+            self.handler.context.raw_set_parameter_normalized(ptr, normalized.clamp(0.0, 1.0))
+        };
+        Ok(())
+    }
+
+    /// Ends an automation gesture started with [`Context::begin_gesture_by_id`].
+    /// Ending a gesture which is not open logs a warning and has no effect.
+    pub fn end_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError> {
+        self.handler.param_by_id(id)?;
+        self.handler.gestures.end(id);
+        Ok(())
+    }
+
+    /// Returns the metadata of the parameter with the given ID, or `None` if
+    /// there is no such parameter or the editor was not given the plugin's
+    /// parameters with [`WebviewEditor::with_params`].
+    pub fn param_info(&self, id: &str) -> Option<ParamInfo> {
+        self.handler.config.params.as_ref()?.info(id)
+    }
+
+    /// Returns the metadata of all of the plugin's parameters. Empty if the
+    /// editor was not given the parameters with [`WebviewEditor::with_params`].
+    pub fn param_infos(&self) -> Vec<ParamInfo> {
+        self.handler.config.params.as_ref().map(ParamMap::infos).unwrap_or_default()
+    }
+
+    /// Returns the manifest written by [`WebviewEditor::write_param_manifest`],
+    /// for the current parameters. `None` if the editor was not given the
+    /// parameters with [`WebviewEditor::with_params`].
+    pub fn param_manifest_json(&self) -> Option<String> {
+        self.handler.config.params.as_ref().map(|params| params.manifest().to_json())
+    }
+
+    /// Returns a parameter's current normalized value, without the host's
+    /// modulation. `None` if there is no such parameter or the editor was not
+    /// given the parameters with [`WebviewEditor::with_params`].
+    pub fn param_normalized(&self, id: &str) -> Option<f32> {
+        self.handler.config.params.as_ref()?.normalized(id)
+    }
+
+    /// Returns a parameter's current plain value, e.g. in decibels, without
+    /// the host's modulation. Format it for display with
+    /// [`Context::normalized_to_string`].
+    pub fn param_plain(&self, id: &str) -> Option<f32> {
+        self.handler.config.params.as_ref()?.plain(id)
+    }
+
+    /// Formats a parameter's normalized value exactly like the host's generic
+    /// editor would, including the unit.
+    pub fn normalized_to_string(&self, id: &str, normalized: f32) -> Option<String> {
+        self.handler.config.params.as_ref()?.normalized_to_string(id, normalized)
+    }
+
+    /// Parses a string, for example one typed into a value box, into a
+    /// parameter's normalized value.
+    pub fn string_to_normalized(&self, id: &str, text: &str) -> Option<f32> {
+        self.handler.config.params.as_ref()?.string_to_normalized(id, text)
+    }
+
+    /// Parses a number typed by the user, e.g. `0,5` in a locale with a comma
+    /// decimal separator. Digit grouping like `1.000,5` is ignored, and
+    /// fullwidth digits typed with CJK input methods are accepted. Uses
+    /// [`WebviewConfig::decimal_separator`], or the separator of the
+    /// webview's locale, and a point until the page reported it.
+    pub fn parse_user_number(&self, text: &str) -> Option<f64> {
+        let separator = self.handler.config.options.decimal_separator;
+        number::parse(text, separator.or(self.handler.decimal_separator.get()).unwrap_or('.'))
+    }
+
+    /// Opens `url` with the system's default handler, e.g. a web page in the
+    /// user's browser.
+    pub fn open_external(&self, url: &str) -> std::io::Result<()> {
+        open_external(url)
+    }
+
+    /// Makes `bytes` available to the page at the returned URL, e.g. for an
+    /// image rendered by the editor, without encoding them into a message.
+    /// Publishing under the same `name` again replaces the asset, under a new
+    /// URL. Assets are kept until they're revoked or the [`WebviewEditor`] is
+    /// dropped, and may take up [`WebviewConfig::max_asset_bytes`] together.
+    pub fn publish_asset(
+        &self,
+        name: &str,
+        mime: &str,
+        bytes: Vec<u8>,
+    ) -> Result<AssetUrl, AssetError> {
+        let max_bytes = self.handler.config.options.max_asset_bytes;
+        self.handler.config.assets.publish(name, mime, bytes, max_bytes)
+    }
+
+    /// Removes an asset published with [`Context::publish_asset`], returning
+    /// whether there was one. Its URLs answer with 404 afterwards.
+    pub fn revoke_asset(&self, name: &str) -> bool {
+        self.handler.config.assets.revoke(name)
+    }
+
+    /// Puts `text` on the system clipboard, which also works where the
+    /// webview keeps `navigator.clipboard` from the page. Returns `false` if
+    /// the clipboard can't be written, which is logged. Requires the
+    /// `clipboard` feature.
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_write_text(&self, text: &str) -> bool {
+        match self.handler.clipboard.write_text(text) {
+            Ok(()) => true,
+            Err(err) => {
+                nih_warn!("Could not write to the clipboard: {err}.");
+                false
+            }
+        }
+    }
+
+    /// Returns the text on the system clipboard, or `None` if it's empty,
+    /// holds something other than text, or can't be read, which is logged.
+    /// Requires the `clipboard` feature.
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_read_text(&self) -> Option<String> {
+        self.handler.clipboard.read_text().unwrap_or_else(|err| {
+            nih_warn!("Could not read the clipboard: {err}.");
+            None
+        })
+    }
+
+    /// Opens the webview's devtools, if they are enabled with
+    /// [`WebviewConfig::devtools`].
+    #[cfg(any(debug_assertions, feature = "devtools"))]
+    pub fn open_devtools(&self) {
+        self.handler.open_devtools();
+    }
+
+    /// Closes the webview's devtools.
+    #[cfg(any(debug_assertions, feature = "devtools"))]
+    pub fn close_devtools(&self) {
+        if let Some(webview) = &self.handler.webview {
+            webview.close_devtools();
+        }
+    }
+
+    /// Returns `true` if the webview's devtools are open.
+    #[cfg(any(debug_assertions, feature = "devtools"))]
+    pub fn is_devtools_open(&self) -> bool {
+        self.handler.webview.as_ref().is_some_and(|webview| webview.is_devtools_open())
+    }
+
+    /// Returns a reference to the `WebView` used by the editor, or `None` if
+    /// the webview could not be constructed.
+    pub fn get_webview(&self) -> Option<&WebView> {
+        self.handler.webview.as_ref()
+    }
+}
+
+/// A named message channel to the frontend, see [`Context::channel`].
+pub struct Channel<'a> {
+    handler: &'a WindowHandler,
+    name: String,
+}
+
+impl Channel<'_> {
+    /// Sends a value to the channel's listeners, which receive it parsed.
+    pub fn send<T: Serialize>(&self, value: &T) -> Result<(), SendError> {
+        self.handler.send_channel(&self.name, value)
+    }
+}
+
+/// A message posted by a background task, see
+/// [`EditorHandler::on_background_message`].
+pub type BackgroundMessage = Box<dyn Any + Send>;
+
+/// Posts messages from a task started with [`Context::spawn_background`] to
+/// [`EditorHandler::on_background_message`].
+#[derive(Clone)]
+pub struct BackgroundSender(crossbeam::channel::Sender<BackgroundMessage>);
+
+impl BackgroundSender {
+    /// Posts a message to the editor. Returns `false` if the editor has been
+    /// closed in the meantime, in which case the message is dropped.
+    pub fn send<T: Any + Send>(&self, message: T) -> bool {
+        self.0.send(Box::new(message)).is_ok()
+    }
+}
+
+/// `nih_plug_webview`'s state that should be persisted between sessions (like window size).
+///
+/// Add it as a persistent parameter to your plugin's state.
+///
+/// When adding a field, also restore it in [`WebviewState::restore`], and mark
+/// it `#[serde(default)]` so that states saved by older versions still load.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebviewState {
+    /// The size the page is laid out in, see [`WebviewState::content_size`].
+    /// `(0, 0)` if a restored state held an invalid size.
+    #[serde(
+        rename = "size",
+        serialize_with = "nih_plug::params::persist::serialize_atomic_cell::serialize",
+        deserialize_with = "deserialize_size"
+    )]
+    content_size: AtomicCell<(u32, u32)>,
+    /// Arbitrary UI preferences, see [`WebviewState::set_ui_state`].
+    #[serde(default)]
+    ui_state: Mutex<Value>,
+    /// The page's zoom factor, see [`Context::set_zoom`].
+    #[serde(
+        serialize_with = "nih_plug::params::persist::serialize_atomic_cell::serialize",
+        deserialize_with = "deserialize_zoom",
+        default = "default_zoom"
+    )]
+    zoom: AtomicCell<f64>,
+    /// Names the data directory under [`WorkdirPolicy::PerInstance`]. `0`
+    /// until the first editor of the instance opens.
+    #[serde(with = "nih_plug::params::persist::serialize_atomic_cell", default)]
+    instance_id: AtomicCell<u64>,
+    /// Where the standalone application's window was last, see
+    /// [`WebviewState::window_position`].
+    #[serde(with = "nih_plug::params::persist::serialize_atomic_cell", default)]
+    window_position: AtomicCell<Option<(f64, f64)>>,
+    /// Edits recorded through [`Context::undo_bridge`]. Only saved under
+    /// [`WebviewConfig::persist_undo_history`].
+    #[serde(default, skip_serializing_if = "undo::skip_saving")]
+    undo_history: Mutex<UndoHistory>,
+    /// The size passed to [`WebviewState::new`], used in place of invalid
+    /// restored sizes.
+    #[serde(skip)]
+    default_size: (u32, u32),
+}
+
+/// The largest width or height accepted from a restored state or a `lib.js`
+/// resize request.
+const MAX_EDITOR_SIZE: f64 = 16384.0;
+
+/// The range of zoom factors accepted by [`Context::set_zoom`].
+const ZOOM_RANGE: std::ops::RangeInclusive<f64> = 0.25..=5.0;
+
+fn default_zoom() -> AtomicCell<f64> {
+    AtomicCell::new(1.0)
+}
+
+/// Reads a zoom factor, falling back to 1.0 for invalid values.
+fn deserialize_zoom<'de, D>(deserializer: D) -> Result<AtomicCell<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let zoom = Value::deserialize(deserializer)?.as_f64().filter(|zoom| ZOOM_RANGE.contains(zoom));
+    Ok(AtomicCell::new(zoom.unwrap_or(1.0)))
+}
+
+/// Reads a window size, mapping sizes that can't be valid, e.g. from a
+/// corrupted project file, to `(0, 0)` rather than failing to load the whole
+/// state.
+fn deserialize_size<'de, D>(deserializer: D) -> Result<AtomicCell<(u32, u32)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    let dimension = |i: usize| {
+        value
+            .get(i)
+            .and_then(Value::as_f64)
+            .filter(|&v| v.is_finite() && (1.0..=MAX_EDITOR_SIZE).contains(&v))
+            .map(|v| v.round() as u32)
+    };
+
+    Ok(AtomicCell::new(match (dimension(0), dimension(1)) {
+        (Some(width), Some(height)) => (width, height),
+        _ => (0, 0),
+    }))
+}
+
+impl WebviewState {
+    /// Initialize the GUI's state. The size is the
+    /// [`WebviewState::content_size`], which is also the window size in
+    /// logical pixels until the page is zoomed.
+    pub fn new(width: u32, height: u32) -> Arc<WebviewState> {
+        Arc::new(WebviewState {
+            content_size: AtomicCell::new((width, height)),
+            ui_state: Mutex::new(Value::Null),
+            zoom: default_zoom(),
+            instance_id: AtomicCell::new(0),
+            window_position: AtomicCell::new(None),
+            undo_history: Mutex::default(),
+            default_size: (width, height),
+        })
+    }
+
+    /// Copies every persisted field of a restored state into this one.
+    fn restore(&self, restored: WebviewState) {
+        // Destructured so that new fields can't be forgotten here.
+        let WebviewState {
+            content_size,
+            ui_state,
+            zoom,
+            instance_id,
+            window_position,
+            undo_history,
+            default_size: _,
+        } = restored;
+
+        let size = match content_size.load() {
+            (0, _) | (_, 0) => {
+                nih_warn!("Ignoring an invalid window size in the restored state.");
+                self.default_size
+            }
+            size => size,
+        };
+        self.content_size.store(size);
+        self.set_ui_state(ui_state.into_inner().unwrap_or_else(PoisonError::into_inner));
+        self.zoom.store(zoom.load());
+        self.instance_id.store(instance_id.load());
+        self.window_position.store(window_position.load());
+        let undo_history = undo_history.into_inner().unwrap_or_else(PoisonError::into_inner);
+        self.undo_history.lock().unwrap_or_else(PoisonError::into_inner).restore(undo_history);
+    }
+
+    /// Returns the instance's identifier, assigning one if it has none yet.
+    fn instance_id(&self) -> u64 {
+        let id = workdir::random_id().max(1);
+        match self.instance_id.compare_exchange(0, id) {
+            Ok(_) => id,
+            Err(existing) => existing,
+        }
+    }
+
+    /// Returns the page's zoom factor, see [`Context::set_zoom`].
+    pub fn zoom(&self) -> f64 {
+        self.zoom.load()
+    }
+
+    /// Returns a `(width, height)` pair for the current size of the GUI in
+    /// logical pixels, which is the [`WebviewState::content_size`] scaled by
+    /// the zoom factor.
+    pub fn size(&self) -> (u32, u32) {
+        size::window_size(self.content_size.load(), self.zoom())
+    }
+
+    /// Returns the size the page is laid out in, in CSS pixels. Unlike the
+    /// window size, this doesn't change when the page is zoomed with
+    /// [`Context::set_zoom`].
+    pub fn content_size(&self) -> (u32, u32) {
+        self.content_size.load()
+    }
+
+    /// Where the standalone application's window was when it was last moved,
+    /// in the platform's screen coordinates. The window is opened there
+    /// again. `None` if it was never moved, or when running inside a host,
+    /// which places the plugin window itself. Not supported on Linux.
+    pub fn window_position(&self) -> Option<(f64, f64)> {
+        self.window_position.load()
+    }
+
+    /// Stores UI preferences, like the selected tab or theme, to be saved with
+    /// the plugin's state. The frontend can also set these with
+    /// `window.plugin.saveUiState(value)`.
+    pub fn set_ui_state(&self, value: Value) {
+        *self.ui_state.lock().unwrap() = value;
+    }
+
+    /// Returns the UI preferences stored with [`WebviewState::set_ui_state`],
+    /// or `null` if there are none. The frontend can read these with
+    /// `window.plugin.loadUiState()`.
+    pub fn ui_state(&self) -> Value {
+        self.ui_state.lock().unwrap().clone()
+    }
+}
+
+impl<'a> PersistentField<'a, WebviewState> for Arc<WebviewState> {
+    fn set(&self, new_value: WebviewState) {
+        self.restore(new_value);
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&WebviewState) -> R,
+    {
+        f(self)
+    }
+}
+
+/// Options for a [`WebviewEditor`], applied with [`WebviewEditor::with_config`].
+#[derive(Debug, Clone)]
+pub struct WebviewConfig {
+    /// How window sizes are adjusted before being applied. Defaults to
+    /// [`ResizePolicy::Free`].
+    pub resize_policy: ResizePolicy,
+    /// Where the webview is allowed to navigate. Defaults to
+    /// [`NavigationPolicy::SameOrigin`].
+    pub navigation_policy: NavigationPolicy,
+    /// Enables the webview's devtools, which can then be opened with F12 (or
+    /// Cmd+Option+I on macOS) or [`Context::open_devtools`]. Defaults to
+    /// `true` in debug builds. Release builds also need the `devtools` feature.
+    pub devtools: bool,
+    /// Collects parameter changes reported by the host and delivers them once
+    /// per frame through [`EditorHandler::on_params_batch_changed`], so that
+    /// loading a preset results in a single callback rather than one per
+    /// parameter. Requires [`WebviewEditor::with_params`]. Defaults to `false`.
+    pub coalesce_param_updates: bool,
+    /// Passes key presses the page did not handle (no text input focused and
+    /// `preventDefault` not called) to [`EditorHandler::on_key_event`], and
+    /// then on to the host so that e.g. the space bar still toggles playback.
+    /// Passing keys on to the host is currently only supported on Windows.
+    /// Defaults to `false`.
+    pub forward_unhandled_keys: bool,
+    /// The RGBA color shown before the page paints its own background, e.g. to
+    /// avoid a white flash when opening a dark themed editor. A color with an
+    /// alpha below 255 also makes the webview transparent. WebView2 only
+    /// supports fully opaque or fully transparent backgrounds. Defaults to
+    /// `None`, which uses the webview's default (usually white).
+    pub background_color: Option<(u8, u8, u8, u8)>,
+    /// How often [`EditorHandler::on_frame`] is called. Messages from the
+    /// webview are still handled on every frame. Defaults to
+    /// [`FrameRate::Full`].
+    pub frame_rate: FrameRate,
+    /// What happens when the [`EditorHandler`] panics. Defaults to
+    /// [`PanicPolicy::Recover`].
+    pub panic_policy: PanicPolicy,
+    /// Limits how often resize requests are passed to the host, for pages
+    /// resizing the window while the user drags a handle. Some hosts flicker
+    /// or stall when asked to resize many times per second. Defaults to
+    /// [`ResizeThrottle::None`].
+    pub resize_throttle: ResizeThrottle,
+    /// Shows the webview's own context menu ("Reload", "Inspect Element",
+    /// ...) on right-click. When disabled, right-clicks go to
+    /// [`EditorHandler::on_context_menu`] instead, except on text inputs and
+    /// selected text which keep their copy and paste menu. Defaults to
+    /// `false`.
+    pub native_context_menu: bool,
+    /// Scripts run before the page's own scripts on every page load, in
+    /// order. They run after the crate's own script, so `window.plugin` is
+    /// available within them. Initialization scripts added in the
+    /// [`WebviewEditor::new_with_webview`] callback run before the crate's
+    /// script instead.
+    pub init_scripts: Vec<String>,
+    /// Shares one `WebContext` between all open editors using the same
+    /// `context_dir`, instead of creating one per editor. This avoids
+    /// WebView2 failing with `0x8007139F` on Windows when several instances
+    /// of the plugin open their editors at once, at the cost of the
+    /// instances sharing their `localStorage`, cookies and caches. The
+    /// context is destroyed with the last editor using it. Defaults to
+    /// `false`.
+    pub share_web_context: bool,
+    /// Where under the `context_dir` the webview keeps its data. Missing
+    /// directories are created when the editor opens. Defaults to
+    /// [`WorkdirPolicy::Shared`].
+    pub workdir_policy: WorkdirPolicy,
+    /// What's shown in the window when the WebView2 runtime is missing on
+    /// Windows. Defaults to [`RuntimeMissingUi::BuiltIn`].
+    pub runtime_missing_ui: RuntimeMissingUi,
+    /// The most memory, in bytes, used for reassembling large messages from
+    /// the webview. Messages larger than 256 KiB are sent in chunks by
+    /// `lib.js`, and a message which would exceed this limit is discarded.
+    /// Defaults to 64 MiB.
+    pub max_message_size: usize,
+    /// Forwards the page's `console` output, uncaught errors and unhandled
+    /// promise rejections to nih-plug's log and to
+    /// [`EditorHandler::on_console`], so problems in a shipped editor show up
+    /// without opening the devtools. Defaults to `false`.
+    pub capture_console: bool,
+    /// Covers every page with a splash screen until it finished loading, for
+    /// pages which take a while to show anything. Defaults to `None`.
+    pub splash: Option<SplashConfig>,
+    /// Replaces the webview's user agent string, e.g. to tell the plugin apart
+    /// from a browser. Applied before the callback of
+    /// [`WebviewEditor::new_with_webview`], which can still override it. See
+    /// also [`Context::user_agent`]. Defaults to `None`.
+    pub user_agent: Option<String>,
+    /// The preferred languages, as an `Accept-Language` value like
+    /// `"de-DE,de;q=0.9,en;q=0.8"`. The webviews don't allow changing the
+    /// header they send, so this only sets `navigator.language` and
+    /// `navigator.languages`, which is what most frontends read. Defaults to
+    /// `None`, which keeps the system's languages.
+    pub accept_language: Option<String>,
+    /// Whether the webview renders on the GPU. Forcing software rendering
+    /// works around broken graphics drivers which leave the editor black.
+    /// Only applied on Windows, where WebView2 requires every webview sharing
+    /// a data directory to use the same policy. Defaults to
+    /// [`GpuPolicy::Auto`].
+    pub gpu_acceleration: GpuPolicy,
+    /// The decimal separator expected by [`Context::parse_user_number`].
+    /// Defaults to `None`, which uses the one of the webview's locale.
+    pub decimal_separator: Option<char>,
+    /// How long the editor goes without user input before it's idle, see
+    /// [`Context::is_idle`]. Defaults to 10 seconds.
+    pub idle_timeout: Duration,
+    /// Limits [`EditorHandler::on_frame`] to this many calls per second while
+    /// the editor is idle, if it would be called more often under
+    /// [`WebviewConfig::frame_rate`]. The full rate is restored on the frame
+    /// the next input arrives. Defaults to `None`.
+    pub throttle_when_idle: Option<u32>,
+    /// The most requests to the protocols added with
+    /// [`WebviewEditor::with_async_protocol`] which may be queued or answered
+    /// at the same time. Further requests are answered with `503 Service
+    /// Unavailable` right away. Defaults to 32.
+    pub max_async_requests: usize,
+    /// The most bytes the assets published with [`Context::publish_asset`]
+    /// may take up together. Defaults to 64 MiB.
+    pub max_asset_bytes: usize,
+    /// Logs a one-line summary of every message between the page and the
+    /// editor, with its direction, verb, length and the start of its payload.
+    /// Meant for debugging, as it logs for every parameter change. Defaults
+    /// to `false`.
+    pub log_ipc: bool,
+    /// Sent as the `Content-Security-Policy` header with the files served for
+    /// a [`WebviewSource::DirPath`], e.g. the one returned by
+    /// [`content_security_policy`]. Defaults to `None`.
+    pub content_security_policy: Option<String>,
+    /// How mouse wheel events reach the webview. Defaults to
+    /// [`WheelPolicy::Default`].
+    pub wheel_policy: WheelPolicy,
+    /// What pinch gestures do. Defaults to [`PinchPolicy::BrowserZoom`].
+    pub pinch_policy: PinchPolicy,
+    /// Lets Tab move the keyboard focus into and out of the page, so hosts'
+    /// keyboard navigation and accessibility tools can reach it. Tab pressed
+    /// while the editor's window has the focus focuses the page's first
+    /// focusable element, or the last one with Shift. Tab past the last
+    /// element, or Shift+Tab before the first, gives the focus back to the
+    /// host's window. This adds an invisible focusable element to the start
+    /// and the end of the page's `<body>`. Defaults to `false`.
+    pub keyboard_navigation: bool,
+    /// The URL prefixes `window.plugin.fetch` may request, e.g.
+    /// `"https://example.com/api/"`. Other URLs are rejected, and redirects
+    /// to them too, so the page can't reach arbitrary servers.
+    /// [`Context::fetch`] is not limited.
+    /// Requires the `http` feature. Defaults to none.
+    pub fetch_allowed_urls: Vec<String>,
+    /// The most edits [`Context::undo_bridge`] can undo. Defaults to 100.
+    pub undo_limit: usize,
+    /// Saves the [`Context::undo_bridge`] history with the plugin's state, so
+    /// edits can still be undone after the project is reloaded. This makes
+    /// the state larger by every state in the history. Defaults to `false`.
+    pub persist_undo_history: bool,
+    /// Watches for a page which stopped responding, e.g. because it's stuck
+    /// in an endless loop. Defaults to [`WatchdogPolicy::Disabled`].
+    pub watchdog: WatchdogPolicy,
+    /// Lets the open editors of the plugin instances registered under the
+    /// same name exchange messages, see [`Context::broadcast_to_peers`], e.g.
+    /// to have several instances follow a controller. Only instances loaded in
+    /// the same process, by the same copy of the plugin, reach each other.
+    /// Defaults to `None`.
+    pub instance_group: Option<String>,
+}
+
+/// A splash screen shown while the page loads, see [`WebviewConfig::splash`].
+/// It's shown again whenever the page is reloaded.
+#[derive(Debug, Clone)]
+pub struct SplashConfig {
+    /// The RGBA color behind the content.
+    pub background_color: (u8, u8, u8, u8),
+    pub content: SplashContent,
+    /// Hides the splash once the page finished loading. Single page apps
+    /// which only render after that can disable this and call
+    /// `window.plugin.hideSplash()` themselves.
+    pub hide_on_load: bool,
+}
+
+impl Default for SplashConfig {
+    fn default() -> Self {
+        SplashConfig {
+            background_color: (30, 30, 30, 255),
+            content: SplashContent::Spinner,
+            hide_on_load: true,
+        }
+    }
+}
+
+/// What's shown in the middle of the splash screen, see [`SplashConfig`].
+#[derive(Debug, Clone)]
+pub enum SplashContent {
+    /// A simple spinner.
+    Spinner,
+    /// HTML, e.g. an inline SVG logo.
+    Html(String),
+}
+
+/// See [`WebviewConfig::gpu_acceleration`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GpuPolicy {
+    /// Leaves the decision to the webview, which falls back to software
+    /// rendering for GPUs and drivers it knows to be broken.
+    #[default]
+    Auto,
+    /// Always renders in software.
+    ForceSoftware,
+    /// Renders on the GPU even if its driver is known to be broken.
+    ForceHardware,
+}
+
+impl GpuPolicy {
+    /// The WebView2 browser arguments for the policy. Replaces wry's own
+    /// arguments, so these are repeated.
+    #[cfg(target_os = "windows")]
+    fn browser_args(self) -> Option<&'static str> {
+        match self {
+            GpuPolicy::Auto => None,
+            GpuPolicy::ForceSoftware => {
+                Some("--disable-features=msWebOOUI,msPdfOOUI,msSmartScreenProtection --disable-gpu")
+            }
+            GpuPolicy::ForceHardware => Some(
+                "--disable-features=msWebOOUI,msPdfOOUI,msSmartScreenProtection \
+                 --ignore-gpu-blocklist",
+            ),
+        }
+    }
+}
+
+/// See [`WebviewConfig::runtime_missing_ui`]. Clicking the message opens the
+/// runtime's download page.
+#[derive(Debug, Clone, Copy)]
+pub enum RuntimeMissingUi {
+    /// Leaves the window empty.
+    None,
+    /// A short native message asking to install the runtime.
+    BuiltIn,
+    /// A native message with the returned text.
+    Custom(fn(&WebviewError) -> String),
+}
+
+impl Default for WebviewConfig {
+    fn default() -> Self {
+        WebviewConfig {
+            resize_policy: ResizePolicy::Free,
+            navigation_policy: NavigationPolicy::SameOrigin,
+            devtools: cfg!(debug_assertions),
+            coalesce_param_updates: false,
+            forward_unhandled_keys: false,
+            background_color: None,
+            frame_rate: FrameRate::Full,
+            panic_policy: PanicPolicy::Recover,
+            resize_throttle: ResizeThrottle::None,
+            native_context_menu: false,
+            init_scripts: Vec::new(),
+            share_web_context: false,
+            workdir_policy: WorkdirPolicy::Shared,
+            runtime_missing_ui: RuntimeMissingUi::BuiltIn,
+            max_message_size: 64 * 1024 * 1024,
+            capture_console: false,
+            splash: None,
+            user_agent: None,
+            accept_language: None,
+            gpu_acceleration: GpuPolicy::Auto,
+            decimal_separator: None,
+            idle_timeout: Duration::from_secs(10),
+            throttle_when_idle: None,
+            max_async_requests: 32,
+            max_asset_bytes: 64 * 1024 * 1024,
+            log_ipc: false,
+            content_security_policy: None,
+            wheel_policy: WheelPolicy::Default,
+            pinch_policy: PinchPolicy::BrowserZoom,
+            keyboard_navigation: false,
+            fetch_allowed_urls: Vec::new(),
+            undo_limit: 100,
+            persist_undo_history: false,
+            watchdog: WatchdogPolicy::Disabled,
+            instance_group: None,
+        }
+    }
+}
+
+/// How often [`EditorHandler::on_frame`] is called, see
+/// [`WebviewConfig::frame_rate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameRate {
+    /// On every frame of the editor window, usually 60 times per second.
+    Full,
+    /// At most this many times per second.
+    Limited(u32),
+    /// Never, for editors which only react to messages and events.
+    Disabled,
+}
+
+/// The most console messages from the page logged per second, see
+/// [`EditorHandler::on_console`].
+const CONSOLE_MESSAGES_PER_SECOND: u32 = 50;
+
+/// Counts the console messages logged in the current second.
+#[derive(Default)]
+struct ConsoleBudget {
+    window_start: Cell<Option<Instant>>,
+    logged: Cell<u32>,
+    dropped: Cell<u32>,
+}
+
+impl ConsoleBudget {
+    /// Returns `true` if another message may be logged in this second.
+    fn take(&self) -> bool {
+        let now = Instant::now();
+        let elapsed = self.window_start.get().map(|start| now - start);
+        if elapsed.unwrap_or(Duration::MAX) >= Duration::from_secs(1) {
+            let dropped = self.dropped.replace(0);
+            if dropped > 0 {
+                nih_warn!("[webview] Dropped {dropped} console messages.");
+            }
+            self.window_start.set(Some(now));
+            self.logged.set(0);
+        }
+
+        if self.logged.get() < CONSOLE_MESSAGES_PER_SECOND {
+            self.logged.set(self.logged.get() + 1);
+            true
+        } else {
+            self.dropped.set(self.dropped.get() + 1);
+            false
+        }
+    }
+}
+
+/// See [`WebviewConfig::wheel_policy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WheelPolicy {
+    /// Leaves wheel events to the platform, which sends them to the focused
+    /// window on Windows. Hosts which keep the focus in their own windows may
+    /// then handle the events themselves instead of passing them on to the
+    /// editor, as has been reported for Ableton Live on Windows.
+    #[default]
+    Default,
+    /// Passes wheel messages the host's windows receive while the cursor is
+    /// over the editor on to the webview, by subclassing the editor's parent
+    /// and top-level windows. This only sees messages which reach those
+    /// windows, not ones a host handles in another of its windows or in a
+    /// message hook first. Only needed on Windows: on macOS scroll events
+    /// always go to the view under the cursor.
+    ///
+    /// Requires the `wheel-forwarding` feature, as this changes how windows
+    /// owned by the host handle their messages. Without it, this acts like
+    /// [`WheelPolicy::Default`].
+    ForceToWebview,
+}
+
+/// See [`WebviewConfig::pinch_policy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PinchPolicy {
+    /// Pinching zooms the page, as does scrolling with Ctrl held on Windows
+    /// and Linux.
+    #[default]
+    BrowserZoom,
+    /// Keeps the webview from zooming, so the page can use pinches itself,
+    /// e.g. to zoom a canvas. Touchpad pinches arrive as `wheel` events with
+    /// `ctrlKey` set, and as `gesturechange` events in WebKit on macOS. Also
+    /// stops zooming with Ctrl and the scroll wheel.
+    ToPage,
+}
+
+/// What happens when the page stops responding, see
+/// [`WebviewConfig::watchdog`]. `lib.js` sends a heartbeat a few times per
+/// `timeout`, and the page counts as unresponsive once nothing arrived from
+/// it for `timeout`. Only time in which the window is visible counts, as the
+/// webviews throttle the timers of hidden pages. A crashed renderer process
+/// stops the heartbeats too, so this also catches those.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WatchdogPolicy {
+    /// The page is not watched and sends no heartbeats.
+    #[default]
+    Disabled,
+    /// Calls [`EditorHandler::on_unresponsive`].
+    Notify { timeout: Duration },
+    /// Calls [`EditorHandler::on_unresponsive`], then loads the page again
+    /// from its [`WebviewSource`].
+    Reload { timeout: Duration },
+}
+
+impl WatchdogPolicy {
+    fn timeout(self) -> Option<Duration> {
+        match self {
+            WatchdogPolicy::Disabled => None,
+            WatchdogPolicy::Notify { timeout } | WatchdogPolicy::Reload { timeout } => {
+                Some(timeout)
+            }
+        }
+    }
+}
+
+/// How often resize requests are passed to the host, see
+/// [`WebviewConfig::resize_throttle`]. Requests arriving in between are
+/// coalesced into the last one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeThrottle {
+    /// Every request is passed on right away.
+    None,
+    /// At most once per frame of the editor window.
+    PerFrame,
+    /// At most once every this many milliseconds.
+    Millis(u64),
+}
+
+impl ResizeThrottle {
+    /// Whether a coalesced request can be passed on at `now`, if the last one
+    /// was passed on at `last`.
+    fn is_due(self, last: Option<Instant>, now: Instant) -> bool {
+        match (self, last) {
+            (ResizeThrottle::Millis(interval), Some(last)) => {
+                now.saturating_duration_since(last) >= Duration::from_millis(interval)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// What happens when the [`EditorHandler`] panics, see
+/// [`WebviewConfig::panic_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Logs the panic, stops calling the handler for as long as the editor is
+    /// open, and shows a "Plugin UI crashed" message in the webview. The
+    /// message includes the panic message in debug builds.
+    Recover,
+    /// Logs the panic and aborts the process, taking the host down with it.
+    Abort,
+}
+
+/// Controls navigation away from the page loaded from the [`WebviewSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationPolicy {
+    /// Navigation is only allowed within the origin of the [`WebviewSource`].
+    /// Other `http(s)` and `mailto` links, as well as links opening a new
+    /// window, are opened with the system's default handler instead.
+    SameOrigin,
+    /// The webview may navigate anywhere, for plugins intentionally embedding
+    /// external sites.
+    AllowAll,
+}
+
+/// Constrains the sizes the editor window can take.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizePolicy {
+    /// Any size is accepted as is.
+    Free,
+    /// The width is kept and the height is adjusted so that `width / height`
+    /// equals the given ratio.
+    AspectRatio(f64),
+    /// Sizes are snapped to the nearest integer multiple of `base`, using the
+    /// same multiple for both dimensions.
+    Quantized { base: (f64, f64) },
+}
+
+impl ResizePolicy {
+    /// Snaps a size in logical pixels to the nearest size allowed by the policy.
+    pub fn snap(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let (w, h) = (width as f64, height as f64);
+
+        match *self {
+            ResizePolicy::Free => (width, height),
+            ResizePolicy::AspectRatio(ratio) if ratio.is_finite() && ratio > 0.0 => {
+                (width, (w / ratio).round() as u32)
+            }
+            ResizePolicy::Quantized { base: (bw, bh) }
+                if bw.is_finite() && bh.is_finite() && bw > 0.0 && bh > 0.0 =>
+            {
+                let n = (w / bw).min(h / bh).round().max(1.0);
+                ((bw * n).round() as u32, (bh * n).round() as u32)
+            }
+            // Invalid parameters, leave the size untouched.
+            _ => (width, height),
+        }
+    }
+}
+
+struct Config {
+    title: String,
+    state: Arc<WebviewState>,
+    source: WebviewSource,
+    /// Replaced through [`Context::replace_handler`].
+    handler: Mutex<Box<dyn EditorHandlerAny>>,
+    /// `None` to use a directory in the user's cache directory.
+    context_dir: Option<PathBuf>,
+    with_webview_fn: Mutex<Box<dyn Fn(WebViewBuilder) -> WebViewBuilder + Send + Sync + 'static>>,
+    options: WebviewConfig,
+    /// The plugin's parameters, if provided with [`WebviewEditor::with_params`].
+    params: Option<ParamMap>,
+    /// Set by [`WebviewEditor::with_param_sync`].
+    param_sync: bool,
+    /// Added with [`WebviewEditor::with_visualization`], by name.
+    visualizations: HashMap<String, Arc<dyn FrameSource>>,
+    /// Added with [`WebviewEditor::with_editor_channel`].
+    audio_queues: Vec<Box<dyn AudioQueue>>,
+    /// Added with [`WebviewEditor::with_transport`].
+    transport: Option<TransportCell>,
+    /// Added with [`WebviewEditor::with_display_value`].
+    display_taps: Vec<DisplayTap>,
+    /// Added with [`WebviewEditor::with_async_protocol`], by name.
+    async_protocols: HashMap<String, AsyncProtocolHandler>,
+    /// See [`Context::can_resize`].
+    can_resize: AtomicCell<Option<bool>>,
+    /// Published with [`Context::publish_asset`].
+    assets: Arc<AssetStore>,
+    /// See [`Context::instance_id`].
+    instance_id: InstanceId,
+}
+
+impl Config {
+    /// Locks the handler. A panic caught under [`PanicPolicy::Recover`]
+    /// poisons the lock, but the handler gets another chance once the editor
+    /// is opened again.
+    fn handler(&self) -> MutexGuard<'_, Box<dyn EditorHandlerAny>> {
+        self.handler.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A webview-based editor.
+///
+/// The webview and its `WebContext` are created each time the host opens the
+/// editor and are destroyed together with the editor window, so no browser
+/// resources are held while the editor is closed, or by a host scanning
+/// plugins without opening their editors. Editors don't share any state,
+/// unless [`WebviewConfig::share_web_context`] lets the open ones share their
+/// `WebContext`.
+pub struct WebviewEditor {
+    config: Arc<Config>,
+    params_changed: Arc<AtomicBool>,
+    /// The scale factor set by the host through [`Editor::set_scale_factor`],
+    /// or `None` to use the system's.
+    scale_factor: Arc<AtomicCell<Option<f32>>>,
+    /// Whether the editor window is currently open.
+    open: Arc<AtomicBool>,
+}
+
+impl WebviewEditor {
+    /// Creates a new `WebviewEditor`.
+    ///
+    /// `context_dir` is where the webview keeps its data, see
+    /// [`WebviewConfig::workdir_policy`]. With `None`, a directory named after
+    /// the `title` in the user's cache directory is used, which can be read
+    /// back with [`Context::workdir`].
+    pub fn new(
+        title: String,
+        source: WebviewSource,
+        state: Arc<WebviewState>,
+        handler: impl EditorHandler,
+        context_dir: impl Into<Option<PathBuf>>,
+    ) -> WebviewEditor {
+        WebviewEditor::new_with_webview(title, source, state, handler, context_dir, |w| w)
+    }
+
+    /// Creates a new `WebviewEditor` with a callback which allows to configure
+    /// `WebViewBuilder`. Do note that some options will be overridden by the
+    /// `EditorHandler` abstraction in order for it to function properly. To see
+    /// which options are overridden, see the `Editor::spawn` implementation
+    /// for the `WebviewEditor`. The callback runs first, so initialization
+    /// scripts added here run before `window.plugin` is set up. Use
+    /// [`WebviewConfig::init_scripts`] for scripts which need it.
+    pub fn new_with_webview(
+        title: String,
+        source: WebviewSource,
+        state: Arc<WebviewState>,
+        handler: impl EditorHandler,
+        context_dir: impl Into<Option<PathBuf>>,
+        f: impl Fn(WebViewBuilder) -> WebViewBuilder + Send + Sync + 'static,
+    ) -> WebviewEditor {
+        WebviewEditor {
+            config: Arc::new(Config {
+                title,
+                state,
+                source,
+                handler: Mutex::new(Box::new(handler)),
+                context_dir: context_dir.into(),
+                with_webview_fn: Mutex::new(Box::new(f)),
+                options: WebviewConfig::default(),
+                params: None,
+                param_sync: false,
+                visualizations: HashMap::new(),
+                audio_queues: Vec::new(),
+                transport: None,
+                display_taps: Vec::new(),
+                async_protocols: HashMap::new(),
+                can_resize: AtomicCell::new(None),
+                assets: Arc::default(),
+                instance_id: InstanceId::next(),
+            }),
+            params_changed: Arc::new(AtomicBool::new(false)),
+            scale_factor: Arc::new(AtomicCell::new(None)),
+            open: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Sets the editor's options. See [`WebviewConfig`].
+    pub fn with_config(mut self, options: WebviewConfig) -> WebviewEditor {
+        self.config_mut().options = options;
+        self
+    }
+
+    /// Keeps the frontend in sync with the plugin's parameters.
+    ///
+    /// Each parameter's `{ id, normalized, plain, text }` is pushed to
+    /// `window.plugin.params` when the editor opens and whenever it changes,
+    /// and the frontend can set parameters by ID through
+    /// `window.plugin.params.set`, `beginGesture` and `endGesture`. Other
+    /// messages still reach [`EditorHandler::on_message`] as usual.
+    pub fn with_param_sync(self, params: Arc<dyn Params>) -> WebviewEditor {
+        let mut editor = self.with_params(params);
+        editor.config_mut().param_sync = true;
+        editor
+    }
+
+    /// Gives the editor access to the plugin's parameters, so they can be
+    /// looked up by their IDs through the [`Context`].
+    pub fn with_params(mut self, params: Arc<dyn Params>) -> WebviewEditor {
+        self.config_mut().params = Some(ParamMap::new(params));
+        self
+    }
+
+    /// Makes the frames of a [`visualization_channel`] available to the
+    /// frontend under `name`. `window.plugin.visualization(name)` resolves to
+    /// the most recently written frame as an `ArrayBuffer`, and is cheap enough
+    /// to be called on every animation frame.
+    pub fn with_visualization<T: VisualizationSample>(
+        mut self,
+        name: impl Into<String>,
+        source: VisualizationSource<T>,
+    ) -> WebviewEditor {
+        self.config_mut().visualizations.insert(name.into(), Arc::new(source));
+        self
+    }
+
+    /// Delivers the values sent through an [`editor_channel`] to
+    /// [`EditorHandler::on_audio_message`].
+    pub fn with_editor_channel<T: Send + 'static>(
+        mut self,
+        receiver: EditorReceiver<T>,
+    ) -> WebviewEditor {
+        self.config_mut().audio_queues.push(Box::new(receiver));
+        self
+    }
+
+    /// Reads the transport from `cell` on every frame and, when it changed,
+    /// passes it to [`EditorHandler::on_transport`] and sends it to the
+    /// page on the reserved `transport` channel, for
+    /// `window.plugin.channel("transport").listen(callback)`. The audio
+    /// thread updates the cell, see [`TransportSnapshot`].
+    pub fn with_transport(mut self, cell: TransportCell) -> WebviewEditor {
+        self.config_mut().transport = Some(cell);
+        self
+    }
+
+    /// Reads `value` on every frame and, when it changed by more than
+    /// `epsilon`, sends it to the page for
+    /// `window.plugin.displayValues.get(id)` and `subscribe`. Also available
+    /// through [`Context::display_value`]. Meant for values only the audio
+    /// thread knows, like a parameter's smoothed value, which it stores from
+    /// `Plugin::process`. Reading them never blocks or allocates.
+    pub fn with_display_value(
+        mut self,
+        id: impl Into<String>,
+        value: Arc<AtomicF32>,
+        epsilon: f32,
+    ) -> WebviewEditor {
+        self.config_mut().display_taps.push(DisplayTap { id: id.into(), value, epsilon });
+        self
+    }
+
+    /// Serves the custom protocol `name` from a pool of worker threads, for
+    /// responses which take long to produce, like reading a large file from
+    /// disk. `handler` is called on the editor's thread and returns the job
+    /// answering the request, which runs on a worker. Queued jobs are
+    /// cancelled when the page navigates away or the editor closes. See
+    /// [`WebviewConfig::max_async_requests`] and [`custom_protocol_url`].
+    ///
+    /// Protocols added through [`WebviewEditor::new_with_webview`] are still
+    /// answered on the editor's thread.
+    pub fn with_async_protocol<F, J>(mut self, name: impl Into<String>, handler: F) -> WebviewEditor
+    where
+        F: Fn(Request<Vec<u8>>) -> J + Send + Sync + 'static,
+        J: FnOnce() -> Response<Cow<'static, [u8]>> + Send + 'static,
+    {
+        let handler: AsyncProtocolHandler =
+            Arc::new(move |request| Box::new(handler(request)) as ProtocolJob);
+        self.config_mut().async_protocols.insert(name.into(), handler);
+        self
+    }
+
+    /// Writes a JSON manifest of `params` to `path`, for generating typed
+    /// bindings in the frontend's build, e.g. from a `build.rs` or a test. It
+    /// lists every parameter's ID, name, type, plain range and default, unit,
+    /// step count, group path and, for enums, variant names, along with a
+    /// `hash` of all of that. See [`WebviewEditor::write_param_types`] and
+    /// [`Context::param_manifest_json`].
+    pub fn write_param_manifest(params: &impl Params, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, param_manifest(params).to_json())
+    }
+
+    /// Writes TypeScript declarations for `params` to `path`: a `ParamId`
+    /// union of the parameter IDs, the manifest's hash as `ParamManifestHash`
+    /// and the variants of enum parameters as `ParamVariants`. Pass the hash
+    /// to `window.plugin.params.verifyManifest`, which logs an error if the
+    /// parameters changed since the page was built.
+    pub fn write_param_types(params: &impl Params, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, param_manifest(params).to_declarations())
+    }
+
+    fn config_mut(&mut self) -> &mut Config {
+        Arc::get_mut(&mut self.config)
+            .expect("The editor's config can't be changed after it has been spawned.")
+    }
+
+    /// The options for the next window, which takes the size from
+    /// [`Editor::size`] and keeps the host's scale factor for as long as it's
+    /// open, see [`Editor::set_scale_factor`].
+    fn window_open_options(&self) -> WindowOpenOptions {
+        let (width, height) = self.size();
+        WindowOpenOptions {
+            scale: match self.scale_factor.load() {
+                Some(factor) => WindowScalePolicy::ScaleFactor(factor as f64),
+                None => WindowScalePolicy::SystemScaleFactor,
+            },
+            size: Size { width: width as f64, height: height as f64 },
+            title: self.config.title.clone(),
+            gl_config: None,
+        }
+    }
+}
+
+impl Editor for WebviewEditor {
+    fn spawn(
+        &self,
+        parent: nih_plug::prelude::ParentWindowHandle,
+        context: Arc<dyn GuiContext>,
+    ) -> Box<dyn std::any::Any + Send> {
+        // A state saved under a different resize policy may hold a size which
+        // is not allowed anymore.
+        let state = &self.config.state;
+        state.content_size.store(self.config.options.resize_policy.snap(state.content_size.load()));
+        let persist_undo_history = self.config.options.persist_undo_history;
+        state
+            .undo_history
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_persist(persist_undo_history);
+        // Fixed for as long as the window is open, see `set_scale_factor`.
+        let host_scale_factor = self.scale_factor.load();
+        let options = self.window_open_options();
+
+        let config = self.config.clone();
+        let params_changed = self.params_changed.clone();
+        let gestures = Arc::new(GestureTracker::new(context.clone()));
+        let window_gestures = gestures.clone();
+
+        if self.config.options.coalesce_param_updates && self.config.params.is_none() {
+            nih_warn!("`coalesce_param_updates` requires `WebviewEditor::with_params`.");
+        }
+
+        // The parameters may have changed while the editor was closed, and
+        // the host doesn't report those changes.
+        self.params_changed.store(true, Ordering::SeqCst);
+        if let Some(params) = &self.config.params {
+            params.mark_all_changed();
+        }
+
+        // baseview panics on windows it can't embed into.
+        if !platform::can_embed(&parent) {
+            let err = WebviewError::UnsupportedWindowing;
+            nih_error!("{err}");
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| self.config.handler().on_error(&err)));
+            return Box::new(EditorHandle {
+                config: self.config.clone(),
+                gestures,
+                open: self.open.clone(),
+                window_handle: None,
+            });
+        }
+
+        let window_handle = baseview::Window::open_parented(&parent, options, move |mut window| {
+            let Config {
+                title,
+                state,
+                source,
+                handler: _,
+                context_dir,
+                with_webview_fn,
+                options,
+                params: _,
+                param_sync: _,
+                visualizations,
+                audio_queues,
+                transport: _,
+                display_taps,
+                async_protocols,
+                can_resize: _,
+                assets,
+                instance_id,
+            } = &*config;
+
+            // Values sent while the editor was closed are stale by now.
+            audio_queues.iter().for_each(|queue| queue.clear());
+
+            // Hosts set the titles and positions of their plugin windows
+            // themselves, and own the menu bar.
+            if ExecutionMode::new(&*context).is_standalone() {
+                platform::set_window_title(window, title);
+                if let Some(position) = state.window_position.load() {
+                    platform::set_window_position(window, position);
+                }
+                platform::install_app_menu(title);
+            }
+
+            let (webview_to_editor_tx, webview_rx) = crossbeam::channel::unbounded();
+            let (wry_event_tx, wry_event_rx) = crossbeam::channel::unbounded::<WryEvent>();
+            // Checked first, so no directories or threads are created for a
+            // webview which can't be constructed, like during a host's plugin
+            // scan on a system without WebView2.
+            let runtime_missing = platform::webview_runtime_missing();
+            let protocol_pool = (!runtime_missing && !async_protocols.is_empty())
+                .then(|| Arc::new(ProtocolPool::new(options.max_async_requests)));
+            let trusted_navigation = Arc::new(AtomicBool::new(false));
+            let log_ipc = options.log_ipc;
+
+            let mut webview_builder = WebViewBuilder::new_as_child(window);
+
+            // Applied before the user configuration so that it can still be
+            // overridden there.
+            if let Some(color) = options.background_color {
+                webview_builder =
+                    webview_builder.with_transparent(color.3 < 255).with_background_color(color);
+            }
+            if let Some(user_agent) = &options.user_agent {
+                webview_builder = webview_builder.with_user_agent(user_agent);
+            }
+            #[cfg(target_os = "windows")]
+            if let Some(args) = options.gpu_acceleration.browser_args() {
+                use wry::WebViewBuilderExtWindows;
+                webview_builder = webview_builder.with_additional_browser_args(args);
+            }
+
+            // Apply user configuration.
+            webview_builder = with_webview_fn.lock().unwrap()(webview_builder);
+
+            //
+            // Configure the webview.
+
+            let (width, height) = state.size();
+            let scale_factor = host_scale_factor
+                .map(f64::from)
+                .or_else(|| platform::scale_factor(window))
+                .unwrap_or(1.0);
+            let bounds = physical_bounds((width, height), scale_factor);
+
+            let prepare = |policy| Workdir::prepare(context_dir.as_deref(), title, policy, state);
+            let workdir = (!runtime_missing).then(|| match prepare(options.workdir_policy) {
+                // Two unshared contexts using one directory can make WebView2
+                // fail, e.g. when a host loads the plugin in several formats.
+                Ok(workdir)
+                    if !options.share_web_context
+                        && options.workdir_policy == WorkdirPolicy::Shared
+                        && WebContextRegistry::in_use(workdir.path()) =>
+                {
+                    nih_log!("The data directory is in use, using one for this instance instead.");
+                    prepare(WorkdirPolicy::PerInstance)
+                }
+                workdir => workdir,
+            });
+            // An unusable directory is not worth failing the whole editor over.
+            let (workdir, workdir_error) = match workdir {
+                None => (None, None),
+                Some(Ok(workdir)) => (Some(workdir), None),
+                Some(Err(err)) => {
+                    nih_error!("{err}");
+                    (Workdir::temporary().ok(), Some(err))
+                }
+            };
+            let data_dir = workdir.as_ref().map(Workdir::path);
+            let web_context = match data_dir {
+                Some(dir) if options.share_web_context => WebContextRegistry::get(dir),
+                _ => WebContextRegistry::unshared(data_dir),
+            };
+            let mut web_context_ref = web_context.borrow_mut();
+
+            #[cfg(feature = "clipboard")]
+            let read_gate = clipboard::ReadGate::default();
+            #[cfg(feature = "clipboard")]
+            let clipboard_key = Some(read_gate.key());
+            #[cfg(not(feature = "clipboard"))]
+            let clipboard_key = None;
+            let config_script = script_config(options, &HostInfo::new(&*context), clipboard_key);
+            let webview_builder = initialization_scripts(&config_script, options)
+                .into_iter()
+                .fold(webview_builder.with_bounds(bounds), |builder, script| {
+                    builder.with_initialization_script(script)
+                })
+                .with_devtools(options.devtools)
+                .with_ipc_handler(move |msg: String| {
+                    // This may still be called while the editor is being torn
+                    // down, and panicking here would take the host with it.
+                    match serde_json::from_str::<Value>(&msg) {
+                        Ok(json_value) => {
+                            if log_ipc {
+                                let verb = json_value.get("t").and_then(Value::as_str);
+                                let summary =
+                                    ipc_summary(Direction::FromPage, verb.unwrap_or("?"), &msg);
+                                nih_log!("{summary}");
+                            }
+                            if webview_to_editor_tx.send(json_value).is_err() {
+                                nih_trace!("Dropping a message sent after the editor closed.");
+                            }
+                        }
+                        Err(err) => nih_warn!("Dropping invalid JSON from the webview: {err}."),
+                    }
+                })
+                .with_on_page_load_handler({
+                    let wry_event_tx = wry_event_tx.clone();
+                    let protocol_pool = protocol_pool.clone();
+                    move |event, url| {
+                        // The new page's requests are only made once it
+                        // started loading.
+                        if let (wry::PageLoadEvent::Started, Some(pool)) = (&event, &protocol_pool)
+                        {
+                            pool.cancel();
+                        }
+                        let event = match event {
+                            wry::PageLoadEvent::Started => PageLoadEvent::Started { url },
+                            wry::PageLoadEvent::Finished => PageLoadEvent::Finished { url },
+                        };
+                        let _ = wry_event_tx.send(WryEvent::PageLoad(event));
+                    }
+                })
+                .with_file_drop_handler(move |event| {
+                    let _ = wry_event_tx.send(WryEvent::FileDrop(event));
+                    // Keep the webview from navigating to the dropped file.
+                    true
+                })
+                .with_web_context(&mut web_context_ref);
+
+            let webview_builder = match options.navigation_policy {
+                NavigationPolicy::SameOrigin => {
+                    let origins = allowed_origins(source);
+                    let trusted_navigation = trusted_navigation.clone();
+                    webview_builder
+                        .with_navigation_handler(move |url| {
+                            // Started by `Context::load_html`.
+                            if trusted_navigation.swap(false, Ordering::SeqCst) {
+                                return true;
+                            }
+                            if is_allowed_origin(&origins, &url) {
+                                return true;
+                            }
+                            open_link_externally(&url);
+                            false
+                        })
+                        .with_new_window_req_handler(|url| {
+                            open_link_externally(&url);
+                            false
+                        })
+                }
+                NavigationPolicy::AllowAll => webview_builder,
+            };
+
+            let webview_builder = if visualizations.is_empty() {
+                webview_builder
+            } else {
+                let visualizations = visualizations.clone();
+                webview_builder.with_custom_protocol(
+                    VISUALIZATION_PROTOCOL.to_string(),
+                    move |request| {
+                        let name = request.uri().path().trim_start_matches('/');
+                        // The page is served from a different origin.
+                        let response =
+                            Response::builder().header("Access-Control-Allow-Origin", "*");
+                        match visualizations.get(name) {
+                            Some(source) => response
+                                .header(CONTENT_TYPE, "application/octet-stream")
+                                .body(source.latest_frame()),
+                            None => response.status(404).body(Vec::new()),
+                        }
+                        .unwrap()
+                        .map(Into::into)
+                    },
+                )
+            };
+
+            // Always registered, as assets can be published at any time.
+            let assets = assets.clone();
+            let webview_builder = webview_builder
+                .with_custom_protocol(ASSET_PROTOCOL.to_string(), move |request| {
+                    assets.serve(request)
+                });
+
+            let webview_builder = match &protocol_pool {
+                Some(pool) => {
+                    async_protocols.iter().fold(webview_builder, |builder, (name, handler)| {
+                        let handler = handler.clone();
+                        let pool = pool.clone();
+                        builder.with_asynchronous_custom_protocol(
+                            name.clone(),
+                            move |request, responder| pool.submit(&handler, request, responder),
+                        )
+                    })
+                }
+                // Only `None` without async protocols or a webview runtime.
+                None => webview_builder,
+            };
+
+            let webview = if runtime_missing {
+                Err(WebviewError::MissingRuntime { download_url: WEBVIEW2_DOWNLOAD_URL })
+            } else {
+                match (*source).clone() {
+                    WebviewSource::URL(url) => webview_builder.with_url(url.as_str()),
+                    WebviewSource::HTML(html) => webview_builder.with_html(html),
+                    WebviewSource::DirPath(root) => {
+                        let csp = options.content_security_policy.clone();
+                        webview_builder.with_custom_protocol("wry".to_string(), move |request| {
+                            serve_dir(&root, csp.as_deref(), request)
+                        })
+                    }
+                    .with_url("wry://localhost"),
+                    WebviewSource::CustomProtocol { url_path: url, protocol } => {
+                        webview_builder.with_url(format!("{protocol}://localhost/{url}").as_str())
+                    }
+                }
+                .and_then(WebViewBuilder::build)
+                .map_err(WebviewError::Build)
+            };
+            drop(web_context_ref);
+
+            // A plugin failing to create its GUI must never crash the host.
+            let mut runtime_missing_ui = false;
+            let (webview, error) = match webview {
+                Ok(webview) => (Some(webview), None),
+                Err(err @ WebviewError::MissingRuntime { .. }) => {
+                    nih_error!("{err}");
+                    let text = match options.runtime_missing_ui {
+                        RuntimeMissingUi::None => None,
+                        RuntimeMissingUi::BuiltIn => Some(
+                            "This plugin needs Microsoft Edge WebView2. Click here to download it."
+                                .to_string(),
+                        ),
+                        RuntimeMissingUi::Custom(text) => Some(text(&err)),
+                    };
+                    runtime_missing_ui = text.is_some_and(|text| {
+                        platform::show_native_message(window, &text, (bounds.width, bounds.height))
+                    });
+                    (None, Some(err))
+                }
+                Err(err) => {
+                    nih_error!("{err}");
+                    (error_page_webview(window, bounds, &err), Some(err))
+                }
+            };
+
+            let wheel_forwarding = match options.wheel_policy {
+                WheelPolicy::ForceToWebview if !cfg!(feature = "wheel-forwarding") => {
+                    nih_warn!(
+                        "`WheelPolicy::ForceToWebview` requires the `wheel-forwarding` feature."
+                    );
+                    None
+                }
+                WheelPolicy::ForceToWebview if webview.is_some() => platform::forward_wheel(window),
+                _ => None,
+            };
+
+            let window_handler = WindowHandler {
+                config: config.clone(),
+                context,
+                webview,
+                _wheel_forwarding: wheel_forwarding,
+                _web_context: web_context,
+                workdir,
+                trusted_navigation,
+                gestures: window_gestures,
+                last_transport: Cell::new(None),
+                runtime_missing_ui,
+                webview_rx,
+                wry_event_rx,
+                evals: PendingEvals::new(),
+                chunks: RefCell::new(Reassembler::new(options.max_message_size)),
+                next_transfer: Cell::new(0),
+                background_messages: crossbeam::channel::unbounded(),
+                #[cfg(feature = "file-dialog")]
+                dialog_results: crossbeam::channel::unbounded(),
+                #[cfg(feature = "file-dialog")]
+                next_dialog_token: Cell::new(0),
+                #[cfg(feature = "http")]
+                fetches: PendingFetches::new(),
+                #[cfg(feature = "clipboard")]
+                clipboard: clipboard::Clipboard::default(),
+                #[cfg(feature = "clipboard")]
+                read_gate,
+                peers: options
+                    .instance_group
+                    .as_deref()
+                    .map(|group| Membership::join(group, *instance_id)),
+                params_changed,
+                scale_factor: Cell::new(scale_factor),
+                last_frame: Cell::new(None),
+                last_activity: Cell::new(Instant::now()),
+                idle: Cell::new(false),
+                pending_resize: Cell::new(None),
+                resize_replies: RefCell::new(Vec::new()),
+                resize_refusals: Cell::new(None),
+                warnings: Warnings::default(),
+                resize_denied: Cell::new(None),
+                undo_availability: Cell::new(None),
+                last_resize: Cell::new(None),
+                poisoned: Cell::new(false),
+                sequence: Cell::new(0),
+                next_handler: RefCell::new(None),
+                cursor_hidden: Cell::new(false),
+                pointer_captured: Cell::new(false),
+                last_captured_pointer: Cell::new(None),
+                overlays: RefCell::new(HashMap::new()),
+                drop_zone: RefCell::new(None),
+                next_overlay: Cell::new(0),
+                overlay_messages: crossbeam::channel::unbounded(),
+                visible: Cell::new(platform::window_visible(window).unwrap_or(true)),
+                display_values: DisplayValues::new(display_taps),
+                user_agent: RefCell::new(None),
+                decimal_separator: Cell::new(None),
+                console_budget: ConsoleBudget::default(),
+                console_history: ConsoleHistory::default(),
+                last_error: workdir_error.iter().chain(&error).last().map(ToString::to_string),
+                diagnostics: Cell::new(None),
+                bridge: Cell::new(BridgeCheck::Loading),
+                last_page_message: Cell::new(Instant::now()),
+                unresponsive: Cell::new(false),
+            };
+
+            window_handler.catch_panics(|| {
+                let mut handler = config.handler();
+                for err in workdir_error.iter().chain(&error) {
+                    handler.on_error(err);
+                }
+
+                window_handler.apply_zoom();
+
+                let mut cx = window_handler.context(&mut window);
+                handler.init(&mut cx);
+                handler.on_window_open(&mut cx);
+                window_handler.attach_next_handler(&mut handler, &mut cx);
+            });
+
+            window_handler
+        });
+
+        self.open.store(true, Ordering::SeqCst);
+        return Box::new(EditorHandle {
+            config: self.config.clone(),
+            gestures,
+            open: self.open.clone(),
+            window_handle: Some(window_handle),
+        });
+    }
+
+    fn size(&self) -> (u32, u32) {
+        let state = &self.config.state;
+        let content = self.config.options.resize_policy.snap(state.content_size.load());
+        size::window_size(content, state.zoom())
+    }
+
+    fn set_scale_factor(&self, factor: f32) -> bool {
+        // The window is created with the factor, so changes only apply to the
+        // next one. Sizes are always reported in logical pixels, nih-plug
+        // applies the factor to them.
+        if self.open.load(Ordering::SeqCst) || !factor.is_finite() || factor <= 0.0 {
+            return false;
+        }
+        self.scale_factor.store(Some(factor));
+        true
+    }
+
+    fn param_values_changed(&self) {
+        self.params_changed.store(true, Ordering::SeqCst);
+        if let Some(params) = &self.config.params {
+            params.mark_all_changed();
+        }
+    }
+
+    fn param_value_changed(&self, id: &str, _normalized_value: f32) {
+        self.params_changed.store(true, Ordering::SeqCst);
+        if let Some(params) = &self.config.params {
+            params.mark_changed(id);
+        }
+    }
+
+    fn param_modulation_changed(&self, _id: &str, _modulation_offset: f32) {
+        self.params_changed.store(true, Ordering::SeqCst);
+    }
+}
+
+/// A handle to the editor window, returned from [`Editor::spawn`]. Host will
+/// call [`drop`] on it when the window is supposed to be closed.
+struct EditorHandle {
+    config: Arc<Config>,
+    gestures: Arc<GestureTracker>,
+    open: Arc<AtomicBool>,
+    /// `None` if the host's window could not be embedded into.
+    window_handle: Option<baseview::WindowHandle>,
+}
+
+unsafe impl Send for EditorHandle {}
+
+impl Drop for EditorHandle {
+    fn drop(&mut self) {
+        // Hosts expect every gesture to be ended.
+        self.gestures.end_all();
+        if let Some(window_handle) = &mut self.window_handle {
+            // The handler may have panicked while the editor was open.
+            let _ =
+                panic::catch_unwind(AssertUnwindSafe(|| self.config.handler().on_window_close()));
+            window_handle.close();
+        }
+        self.open.store(false, Ordering::SeqCst);
+    }
+}
+
+/// This structure manages the editor window's event loop.
+struct WindowHandler {
+    config: Arc<Config>,
+    /// `None` if the webview could not be constructed.
+    webview: Option<WebView>,
+    /// Kept alive for as long as the webview is, and dropped with the window.
+    _web_context: SharedWebContext,
+    /// See [`WheelPolicy::ForceToWebview`]. Ends with the window.
+    _wheel_forwarding: Option<platform::WheelForwarding>,
+    /// The context's data directory, deleted after the context if it's
+    /// temporary.
+    workdir: Option<Workdir>,
+    /// Lets the next navigation pass the [`NavigationPolicy`], see
+    /// [`Context::load_html`].
+    trusted_navigation: Arc<AtomicBool>,
+    /// Shared with the [`EditorHandle`], which ends the gestures left open.
+    gestures: Arc<GestureTracker>,
+    /// The transport snapshot last sent, see [`WebviewEditor::with_transport`].
+    last_transport: Cell<Option<TransportSnapshot>>,
+    /// Whether the [`RuntimeMissingUi`] message is shown, which opens the
+    /// download page when clicked.
+    runtime_missing_ui: bool,
+    context: Arc<dyn GuiContext>,
+    params_changed: Arc<AtomicBool>,
+    webview_rx: Receiver<Value>,
+    wry_event_rx: Receiver<WryEvent>,
+    /// Scripts evaluated with [`Context::eval_with_result`].
+    evals: PendingEvals,
+    /// Large messages from `lib.js` which haven't fully arrived yet.
+    chunks: RefCell<Reassembler>,
+    /// The id of the next large message sent to `lib.js` in chunks.
+    next_transfer: Cell<u64>,
+    /// Messages from tasks started with [`Context::spawn_background`].
+    background_messages:
+        (crossbeam::channel::Sender<BackgroundMessage>, Receiver<BackgroundMessage>),
+    /// Completions of closed file dialogs, sent from the threads waiting for
+    /// them.
+    #[cfg(feature = "file-dialog")]
+    dialog_results: (crossbeam::channel::Sender<DialogCompletion>, Receiver<DialogCompletion>),
+    #[cfg(feature = "file-dialog")]
+    next_dialog_token: Cell<u64>,
+    /// Requests sent with [`Context::fetch`] and `window.plugin.fetch`.
+    #[cfg(feature = "http")]
+    fetches: PendingFetches,
+    #[cfg(feature = "clipboard")]
+    clipboard: clipboard::Clipboard,
+    /// Refuses clipboard reads which weren't sent by `lib.js`.
+    #[cfg(feature = "clipboard")]
+    read_gate: clipboard::ReadGate,
+    /// The editor's place in its [`WebviewConfig::instance_group`].
+    peers: Option<Membership>,
+    /// The window's scale factor, as last reported by baseview.
+    scale_factor: Cell<f64>,
+    /// When [`EditorHandler::on_frame`] was last called.
+    last_frame: Cell<Option<Instant>>,
+    /// When `lib.js` last reported user input.
+    last_activity: Cell<Instant>,
+    /// Whether no input arrived for [`WebviewConfig::idle_timeout`].
+    idle: Cell<bool>,
+    /// The last size requested under a [`ResizeThrottle`], which hasn't been
+    /// passed to the host yet.
+    pending_resize: Cell<Option<(u32, u32)>>,
+    /// The `lib.js` resize requests waiting for `pending_resize`.
+    resize_replies: RefCell<Vec<u64>>,
+    /// When the last throttled resize request was passed to the host.
+    last_resize: Cell<Option<Instant>>,
+    /// The size the host last refused, and how often in a row it did.
+    resize_refusals: Cell<Option<((u32, u32), u32)>>,
+    /// A size the host refused repeatedly, to be reported to
+    /// [`EditorHandler::on_resize_denied`].
+    resize_denied: Cell<Option<(u32, u32)>>,
+    /// Whether the [`Context::undo_bridge`] history could be undone and redone
+    /// when [`EditorHandler::on_undo_state_changed`] was last called.
+    undo_availability: Cell<Option<(bool, bool)>>,
+    /// Warnings which may repeat every frame or with every message.
+    warnings: Warnings,
+    /// Set once the handler panicked under [`PanicPolicy::Recover`], after
+    /// which it's not called anymore.
+    poisoned: Cell<bool>,
+    /// See [`Context::sequence`].
+    sequence: Cell<u64>,
+    /// Set by [`Context::replace_handler`].
+    next_handler: RefCell<Option<Box<dyn EditorHandlerAny>>>,
+    /// Limits how many console messages are logged per second.
+    console_budget: ConsoleBudget,
+    /// The latest console messages, for [`Context::diagnostics_report`].
+    console_history: ConsoleHistory,
+    /// The last error creating the editor, for
+    /// [`Context::diagnostics_report`].
+    last_error: Option<String>,
+    /// The overlay opened with [`Context::open_diagnostics`].
+    diagnostics: Cell<Option<OverlayId>>,
+    bridge: Cell<BridgeCheck>,
+    /// When the last message from the page arrived, or when the
+    /// [`WebviewConfig::watchdog`] last paused.
+    last_page_message: Cell<Instant>,
+    /// Set once [`EditorHandler::on_unresponsive`] was called, until the page
+    /// responds again.
+    unresponsive: Cell<bool>,
+    /// The webviews opened with [`Context::create_overlay`].
+    overlays: RefCell<HashMap<OverlayId, WebView>>,
+    next_overlay: Cell<u64>,
+    /// Messages posted by the overlays' pages.
+    overlay_messages:
+        (crossbeam::channel::Sender<(OverlayId, String)>, Receiver<(OverlayId, String)>),
+    /// The drop zone under the cursor during a native drag, as last reported
+    /// by `lib.js`.
+    drop_zone: RefCell<Option<String>>,
+    /// Whether the window was visible on the last frame.
+    visible: Cell<bool>,
+    /// The values last sent for the [`WebviewEditor::with_display_value`]
+    /// taps.
+    display_values: DisplayValues,
+    /// The user agent reported by `lib.js`.
+    user_agent: RefCell<Option<String>>,
+    /// The decimal separator of the page's locale, reported by `lib.js`.
+    decimal_separator: Cell<Option<char>>,
+    /// Whether the cursor was hidden with [`Context::set_cursor_visible`].
+    cursor_hidden: Cell<bool>,
+    /// Whether a drag on a `data-plugin-capture` element is in progress.
+    pointer_captured: Cell<bool>,
+    /// The cursor position in CSS pixels and button state last sent during
+    /// that drag.
+    last_captured_pointer: Cell<Option<((f64, f64), bool)>>,
+}
+
+/// The reply to `window.plugin.presets` requests. Errors reject the promise
+/// in `lib.js`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+enum PresetReply {
+    Done,
+    Cancelled,
+    Error(String),
+}
+
+impl From<Result<(), StateError>> for PresetReply {
+    fn from(result: Result<(), StateError>) -> Self {
+        match result {
+            Ok(()) => PresetReply::Done,
+            Err(err) => PresetReply::Error(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "file-dialog")]
+fn preset_filter() -> FileFilter {
+    FileFilter {
+        name: "Preset".to_string(),
+        extensions: vec![preset::PRESET_EXTENSION.to_string()],
+    }
+}
+
+/// Events reported by `wry` callbacks, which are forwarded to the handler on
+/// the next frame.
+enum WryEvent {
+    PageLoad(PageLoadEvent),
+    FileDrop(wry::FileDropEvent),
+}
+
+/// Whether `lib.js` is known to run in the current page, see
+/// [`WindowHandler::check_bridge`].
+#[derive(Clone, Copy)]
+enum BridgeCheck {
+    /// The page is loading, or no page was loaded.
+    Loading,
+    /// `lib.js` said hello.
+    Ready,
+    /// The page loaded, and `lib.js` was asked to answer a probe in case
+    /// its hello arrived before the page load was reported. `retried` once
+    /// the crate's scripts were evaluated once more.
+    Waiting { since: Instant, retried: bool },
+    /// Reported to [`EditorHandler::on_bridge_unavailable`].
+    Unavailable,
+}
+
+/// Called on the editor thread once a file dialog was closed, see
+/// [`WindowHandler::open_file_dialog`].
+#[cfg(feature = "file-dialog")]
+type DialogCompletion = Box<dyn FnOnce(&mut dyn EditorHandlerAny, &mut Context<()>) + Send>;
+
+impl WindowHandler {
+    fn context<'a, 'b>(&'a self, window: &'a mut Window<'b>) -> Context<'a, 'b, ()> {
+        Context { handler: self, window, _p: PhantomData }
+    }
+
+    /// Runs `f`, which calls into the [`EditorHandler`], and applies the
+    /// configured [`PanicPolicy`] if it panics. Returns `None` if it panicked
+    /// or if the handler already panicked before.
+    fn catch_panics<R>(&self, f: impl FnOnce() -> R) -> Option<R> {
+        if self.poisoned.get() {
+            return None;
+        }
+
+        let payload = match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => return Some(result),
+            Err(payload) => payload,
+        };
+
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        nih_error!("The editor handler panicked: {message}");
+
+        if self.config.options.panic_policy == PanicPolicy::Abort {
+            std::process::abort();
+        }
+
+        // Only shown once, as the handler is not called anymore.
+        self.poisoned.set(true);
+        if let Some(webview) = &self.webview {
+            let detail = if cfg!(debug_assertions) { Some(message) } else { None };
+            let detail = serde_json::to_string(&detail).expect("Can't convert JSON to string.");
+            let _ = webview.evaluate_script(&format!("window.plugin.__ipc.showCrash({detail});"));
+        }
+        None
+    }
+
+    pub fn resize(&self, window: &mut baseview::Window, width: u32, height: u32) -> bool {
+        let (width, height) = self.config.options.resize_policy.snap((width, height));
+        if self.config.options.resize_throttle == ResizeThrottle::None {
+            return self.apply_resize(window, width, height);
+        }
+
+        self.pending_resize.set(Some((width, height)));
+        true
+    }
+
+    /// Passes the last throttled resize request to the host once the
+    /// [`ResizeThrottle`] allows it, and answers the `lib.js` requests which
+    /// were waiting for it.
+    fn flush_resize(&self, window: &mut baseview::Window) {
+        let Some((width, height)) = self.pending_resize.get() else { return };
+        let now = Instant::now();
+        if !self.config.options.resize_throttle.is_due(self.last_resize.get(), now) {
+            return;
+        }
+
+        self.pending_resize.set(None);
+        self.last_resize.set(Some(now));
+        let accepted = self.apply_resize(window, width, height);
+        for id in self.resize_replies.take() {
+            self.send_reply(id, accepted);
+        }
+    }
+
+    /// Asks the host for an already snapped content size. The
+    /// [`WebviewState`] only keeps the new size if the host accepted it.
+    fn apply_resize(&self, window: &mut baseview::Window, width: u32, height: u32) -> bool {
+        if !size::request_content_size(&*self.context, &self.config.state, (width, height)) {
+            self.warnings
+                .warn(format!("The host refused to resize the editor to {width}x{height}."));
+            self.refused_resize((width, height));
+            return false;
+        }
+        self.resize_refusals.set(None);
+        self.config.can_resize.store(Some(true));
+
+        let (width, height) = size::window_size((width, height), self.config.state.zoom());
+        window.resize(Size { width: width as f64, height: height as f64 });
+
+        if let Some(webview) = &self.webview {
+            webview.set_bounds(self.webview_bounds(width, height));
+        }
+
+        true
+    }
+
+    /// Counts consecutive refusals of the same size, and reports it to the
+    /// handler once there were [`RESIZE_REFUSALS_BEFORE_DENIED`]. Reported
+    /// from [`WindowHandler::on_frame`], as the handler may be the one
+    /// resizing.
+    fn refused_resize(&self, size: (u32, u32)) {
+        let refusals = match self.resize_refusals.get() {
+            Some((last, refusals)) if last == size => refusals + 1,
+            _ => 1,
+        };
+        self.resize_refusals.set(Some((size, refusals)));
+        if refusals == RESIZE_REFUSALS_BEFORE_DENIED {
+            self.config.can_resize.store(Some(false));
+            self.resize_denied.set(Some(size));
+        }
+    }
+
+    /// Applies a new scale factor after the window was moved to another
+    /// monitor. The content size in the [`WebviewState`] stays the same, so
+    /// the saved size doesn't depend on the monitor the editor was closed on.
+    /// Returns `false` if the factor did not change.
+    fn rescale(&self, window: &mut baseview::Window, factor: f64) -> bool {
+        if (factor - self.scale_factor.get()).abs() < 1e-6 {
+            return false;
+        }
+        self.scale_factor.set(factor);
+
+        let (width, height) = self.config.state.size();
+        if let Some(webview) = &self.webview {
+            webview.set_bounds(self.webview_bounds(width, height));
+        }
+        if self.context.request_resize() {
+            window.resize(Size { width: width as f64, height: height as f64 });
+        }
+        true
+    }
+
+    /// The webview's bounds for a logical window size.
+    fn webview_bounds(&self, width: u32, height: u32) -> wry::Rect {
+        physical_bounds((width, height), self.scale_factor.get())
+    }
+
+    pub fn send_json<T: serde::Serialize>(&self, json: T) -> Result<(), SendError> {
+        let Some(webview) = &self.webview else { return Err(SendError::NoWebview) };
+
+        let json_str = js_json_literal(&json)?;
+        self.call_ipc(webview, "recvMessage", &json_str).map_err(SendError::Script)
+    }
+
+    fn send_channel<T: serde::Serialize>(&self, name: &str, value: T) -> Result<(), SendError> {
+        let Some(webview) = &self.webview else { return Err(SendError::NoWebview) };
+
+        let name = serde_json::to_string(name)?;
+        let json_str = js_json_literal(&value)?;
+        self.call_ipc(webview, "recvChannel", &format!("{name}, {json_str}"))
+            .map_err(SendError::Script)
+    }
+
+    /// Calls `window.plugin.__ipc[function]` with `args`, a comma separated
+    /// list of JSON values. Large argument lists are sent in chunks, which
+    /// `lib.js` puts back together before making the call.
+    fn call_ipc(&self, webview: &WebView, function: &str, args: &str) -> Result<(), wry::Error> {
+        if self.config.options.log_ipc {
+            nih_log!("{}", ipc_summary(Direction::ToPage, function, args));
+        }
+        if args.len() <= chunk::CHUNK_SIZE {
+            return webview.evaluate_script(&format!("window.plugin.__ipc.{function}({args});"));
+        }
+
+        let id = self.next_transfer.get();
+        self.next_transfer.set(id + 1);
+        let chunks = chunk::split(args);
+        for (index, data) in chunks.iter().enumerate() {
+            let data = serde_json::to_string(data).expect("Can't convert JSON to string.");
+            webview.evaluate_script(&format!(
+                "window.plugin.__ipc.recvChunk({id}, {index}, {}, {data}, \"{function}\");",
+                chunks.len()
+            ))?;
+        }
+        Ok(())
+    }
+
+    pub fn next_message(&self) -> Result<Value, crossbeam::channel::TryRecvError> {
+        self.webview_rx.try_recv()
+    }
+
+    /// Opens a file dialog. `on_result` is called from a background thread
+    /// once it's closed, so it can do blocking work like reading the selected
+    /// file, and the completion it returns is run on the next frame.
+    #[cfg(feature = "file-dialog")]
+    fn open_file_dialog<F>(&self, window: &Window, options: FileDialogOptions, on_result: F)
+    where
+        F: FnOnce(Option<Vec<PathBuf>>) -> DialogCompletion + Send + 'static,
+    {
+        let results = self.dialog_results.0.clone();
+        dialog::open(window, options, move |paths| {
+            let _ = results.send(on_result(paths));
+        });
+    }
+
+    /// Opens a save dialog for `window.plugin.presets.saveToFile` and writes
+    /// the current state to the selected file.
+    #[cfg(feature = "file-dialog")]
+    fn save_preset(&self, window: &Window, id: u64, name: String) {
+        let state = preset::export(&*self.context);
+        let options = FileDialogOptions {
+            mode: FileDialogMode::Save,
+            filters: vec![preset_filter()],
+            file_name: Some(format!("{name}.{}", preset::PRESET_EXTENSION)),
+            ..Default::default()
+        };
+        self.open_file_dialog(window, options, move |paths| {
+            let reply = match paths.as_deref() {
+                Some([path, ..]) => {
+                    PresetReply::from(std::fs::write(path, state).map_err(StateError::Io))
+                }
+                _ => PresetReply::Cancelled,
+            };
+            Box::new(move |_, cx| cx.handler.send_reply(id, reply))
+        });
+    }
+
+    /// Opens a file dialog for `window.plugin.presets.loadFromFile` and
+    /// imports the state in the selected file.
+    #[cfg(feature = "file-dialog")]
+    fn load_preset(&self, window: &Window, id: u64) {
+        let options = FileDialogOptions { filters: vec![preset_filter()], ..Default::default() };
+        self.open_file_dialog(window, options, move |paths| {
+            let bytes = match paths.as_deref() {
+                Some([path, ..]) => Some(std::fs::read(path).map_err(StateError::Io)),
+                _ => None,
+            };
+            Box::new(move |_, cx| {
+                let reply = match bytes {
+                    Some(bytes) => {
+                        PresetReply::from(bytes.and_then(|bytes| cx.import_state(&bytes)))
+                    }
+                    None => PresetReply::Cancelled,
+                };
+                cx.handler.send_reply(id, reply);
+            })
+        });
+    }
+
+    /// Hides or shows the cursor, keeping the calls balanced.
+    fn set_cursor_visible(&self, visible: bool) {
+        if self.cursor_hidden.get() == visible {
+            platform::set_cursor_visible(visible);
+            self.cursor_hidden.set(!visible);
+        }
+    }
+
+    fn evaluate_script(&self, script: &str) {
+        if let Some(webview) = &self.webview {
+            let _ = webview.evaluate_script(script);
+        }
+    }
+
+    /// Applies the zoom factor stored in the [`WebviewState`].
+    fn apply_zoom(&self) {
+        if let Some(webview) = &self.webview {
+            webview.zoom(self.config.state.zoom());
+        }
+    }
+
+    /// Whether [`EditorHandler::on_frame`] should be called on this frame
+    /// under the configured [`FrameRate`] and
+    /// [`WebviewConfig::throttle_when_idle`].
+    fn frame_due(&self) -> bool {
+        let options = &self.config.options;
+        let mut interval = match options.frame_rate {
+            FrameRate::Full => Duration::ZERO,
+            FrameRate::Limited(0) | FrameRate::Disabled => return false,
+            FrameRate::Limited(fps) => Duration::from_secs_f64(1.0 / fps as f64),
+        };
+        match options.throttle_when_idle {
+            Some(0) if self.idle.get() => return false,
+            Some(fps) if self.idle.get() => {
+                interval = interval.max(Duration::from_secs_f64(1.0 / fps as f64));
+            }
+            _ => (),
+        }
+
+        let now = Instant::now();
+        if self.last_frame.get().is_some_and(|last| now - last < interval) {
+            return false;
+        }
+        self.last_frame.set(Some(now));
+        true
+    }
+
+    /// Records user input reported by `lib.js`, ending the idle state.
+    fn activity(&self, handler: &mut dyn EditorHandlerAny, cx: &mut Context<()>) {
+        self.last_activity.set(Instant::now());
+        if self.idle.replace(false) {
+            handler.on_idle_changed(cx, false);
+        }
+    }
+
+    /// Enters the idle state once no input arrived for
+    /// [`WebviewConfig::idle_timeout`].
+    fn check_idle(&self, handler: &mut dyn EditorHandlerAny, cx: &mut Context<()>) {
+        let timed_out = self.last_activity.get().elapsed() >= self.config.options.idle_timeout;
+        if timed_out && !self.idle.replace(true) {
+            handler.on_idle_changed(cx, true);
+        }
+    }
+
+    /// Reports a page which sent nothing for the [`WebviewConfig::watchdog`]
+    /// timeout. The time counts only while the window is visible and
+    /// `lib.js` runs, so neither throttled hidden pages nor loading pages
+    /// trip it.
+    fn check_watchdog(&self, handler: &mut dyn EditorHandlerAny, cx: &mut Context<()>) {
+        let policy = self.config.options.watchdog;
+        let Some(timeout) = policy.timeout() else { return };
+        let now = Instant::now();
+        if !self.visible.get() || !matches!(self.bridge.get(), BridgeCheck::Ready) {
+            self.last_page_message.set(now);
+            return;
+        }
+
+        let elapsed = now.duration_since(self.last_page_message.get());
+        if elapsed < timeout || self.unresponsive.replace(true) {
+            return;
+        }
+        nih_warn!("The page has not responded for {:.1} s.", elapsed.as_secs_f64());
+        handler.on_unresponsive(cx, elapsed);
+        if let WatchdogPolicy::Reload { .. } = policy {
+            self.restart_page();
+        }
+    }
+
+    /// Loads the [`WebviewSource`] again. Unlike [`Context::reload`], this
+    /// doesn't go through the page's scripts, which may not run anymore.
+    fn restart_page(&self) {
+        let Some(webview) = &self.webview else { return };
+        match &self.config.source {
+            WebviewSource::URL(url) => webview.load_url(url),
+            WebviewSource::HTML(html) => {
+                self.trusted_navigation.store(true, Ordering::SeqCst);
+                webview.load_url(&data_url(html));
+            }
+            WebviewSource::DirPath(_) => webview.load_url("wry://localhost"),
+            WebviewSource::CustomProtocol { url_path, protocol } => {
+                webview.load_url(&format!("{protocol}://localhost/{url_path}"))
+            }
+        }
+    }
+
+    /// Reports the cursor to `lib.js` during a drag on a `data-plugin-capture`
+    /// element, as the webview may stop sending pointer events once the
+    /// cursor leaves the window. The window doesn't capture the mouse itself,
+    /// which would take the events inside the window from the webview.
+    fn track_captured_pointer(&self, window: &Window) {
+        if !self.pointer_captured.get() {
+            return;
+        }
+        let Some(((x, y), down)) = platform::pointer_state(window, self.scale_factor.get()) else {
+            return;
+        };
+        let zoom = self.config.state.zoom();
+        let state = ((x / zoom, y / zoom), down);
+        if self.last_captured_pointer.replace(Some(state)) == Some(state) {
+            return;
+        }
+        if !down {
+            self.pointer_captured.set(false);
+        }
+
+        let Some(webview) = &self.webview else { return };
+        let ((x, y), down) = state;
+        if let Err(err) = self.call_ipc(webview, "capturedPointer", &format!("{x}, {y}, {down}")) {
+            self.warnings.warn(format!("Failed to report the captured pointer: {err}."));
+        }
+    }
+
+    /// Asks `lib.js` in a page which finished loading to answer, if it runs.
+    fn probe_bridge(&self) {
+        self.bridge.set(BridgeCheck::Waiting { since: Instant::now(), retried: false });
+        self.evaluate_script(
+            "if (window.plugin && window.ipc) \
+             window.ipc.postMessage(JSON.stringify({ t: \"bridgeProbe\" }));",
+        );
+    }
+
+    /// Evaluates the crate's scripts once more if `lib.js` did not answer
+    /// the probe within [`BRIDGE_TIMEOUT`], as a strict
+    /// `Content-Security-Policy` may block initialization scripts on some
+    /// platforms, but not scripts evaluated by the editor. Reports the
+    /// bridge as unavailable if that didn't help either.
+    fn check_bridge(&self, handler: &mut dyn EditorHandlerAny, cx: &mut Context<()>) {
+        let BridgeCheck::Waiting { since, retried } = self.bridge.get() else { return };
+        if since.elapsed() < BRIDGE_TIMEOUT {
+            return;
+        }
+
+        if !retried {
+            nih_warn!("lib.js did not start in the page, evaluating it once more.");
+            // Without the clipboard key, as the page is already running and
+            // could intercept it.
+            let config = script_config(&self.config.options, &HostInfo::new(&*self.context), None);
+            // Doesn't replace a `window.plugin` which exists but can't post.
+            self.evaluate_script(&format!("if (!window.plugin) {{\n{config}\n{LIB_JS}\n}}"));
+            self.bridge.set(BridgeCheck::Waiting { since: Instant::now(), retried: true });
+            return;
+        }
+
+        self.bridge.set(BridgeCheck::Unavailable);
+        let reason = "lib.js did not start in the page, so `window.plugin` is unavailable and \
+                      the page can't reach the editor. This is usually caused by a strict \
+                      Content-Security-Policy, see `nih_plug_webview::content_security_policy`.";
+        nih_error!("{reason}");
+        handler.on_bridge_unavailable(cx, reason);
+    }
+
+    /// See [`Context::broadcast_to_peers`].
+    fn broadcast_to_peers(&self, message: &str) -> usize {
+        let Some(peers) = &self.peers else { return 0 };
+        if message.len() > peers::MAX_MESSAGE_LEN {
+            self.warnings.warn(format!(
+                "Dropping a peer message of {} bytes, more than the {} allowed.",
+                message.len(),
+                peers::MAX_MESSAGE_LEN
+            ));
+            return 0;
+        }
+        peers.broadcast(message)
+    }
+
+    /// Whether `window.plugin.fetch` may request `url`, see
+    /// [`WebviewConfig::fetch_allowed_urls`].
+    #[cfg(feature = "http")]
+    fn fetch_allowed(&self, url: &str) -> bool {
+        fetch::is_allowed_url(&self.config.options.fetch_allowed_urls, url)
+    }
+
+    /// Resolves the promise returned by the `lib.js` request with the given id.
+    fn send_reply<T: serde::Serialize>(&self, id: u64, value: T) {
+        let Some(webview) = &self.webview else { return };
+
+        let json_str = match serde_json::to_string(&value) {
+            Ok(json_str) => json_str,
+            Err(err) => {
+                self.warnings.warn(format!("Failed to serialize a reply to the webview: {err}."));
+                return;
+            }
+        };
+        if let Err(err) = self.call_ipc(webview, "recvReply", &format!("{id}, {json_str}")) {
+            self.warnings.warn(format!("Failed to reply to the webview: {err}."));
+        }
+    }
+
+    /// Pushes the values of changed parameters to `window.plugin.params`.
+    fn sync_params(&self) {
+        let (Some(webview), Some(params)) = (&self.webview, &self.config.params) else { return };
+        if !self.config.param_sync {
+            return;
+        }
+
+        let updates = params.take_updates();
+        if updates.is_empty() {
+            return;
+        }
+
+        let json_str = match serde_json::to_string(&updates) {
+            Ok(json_str) => json_str,
+            Err(err) => {
+                self.warnings.warn(format!("Failed to serialize parameter values: {err}."));
+                return;
+            }
+        };
+        let sequence = self.next_sequence();
+        if let Err(err) = self.call_ipc(webview, "recvParams", &format!("{json_str}, {sequence}")) {
+            self.warnings.warn(format!("Failed to send parameter values to the webview: {err}."));
+        }
+    }
+
+    fn diagnostics_report(&self) -> String {
+        diagnostics::report(diagnostics::Facts {
+            host: HostInfo::new(&*self.context),
+            user_agent: self.user_agent.borrow().clone(),
+            gpu_acceleration: format!("{:?}", self.config.options.gpu_acceleration),
+            workdir: self.workdir.as_ref().map(Workdir::path),
+            last_error: self.last_error.clone(),
+            console: &self.console_history,
+        })
+    }
+
+    /// Swaps in the handler passed to [`Context::replace_handler`], if any.
+    /// Called between callbacks, with the current handler locked.
+    fn attach_next_handler(&self, handler: &mut Box<dyn EditorHandlerAny>, cx: &mut Context<()>) {
+        // A handler may replace itself again while attaching.
+        while let Some(next) = self.next_handler.take() {
+            handler.on_detach(cx);
+            *handler = next;
+            handler.on_attach(cx);
+        }
+    }
+
+    /// Advances [`Context::sequence`].
+    fn next_sequence(&self) -> u64 {
+        let sequence = self.sequence.get() + 1;
+        self.sequence.set(sequence);
+        sequence
+    }
+
+    /// Sends the display values which changed since the last frame.
+    fn sync_display_values(&self) {
+        let updates = self.display_values.sample(&self.config.display_taps);
+        let (false, Some(webview)) = (updates.is_empty(), &self.webview) else { return };
+
+        let json_str = serde_json::to_string(&updates).expect("Can't convert JSON to string.");
+        if let Err(err) = self.call_ipc(webview, "recvDisplayValues", &json_str) {
+            self.warnings.warn(format!("Failed to send display values to the webview: {err}."));
+        }
+    }
+
+    fn param_by_id(&self, id: &str) -> Result<ParamPtr, ParamIdError> {
+        let params = self.config.params.as_ref().ok_or(ParamIdError::ParamsNotProvided)?;
+        params.get(id).ok_or_else(|| ParamIdError::UnknownId(id.to_string()))
+    }
+
+    #[cfg(any(debug_assertions, feature = "devtools"))]
+    fn open_devtools(&self) {
+        if let Some(webview) = &self.webview {
+            if self.config.options.devtools {
+                webview.open_devtools();
+            }
+        }
+    }
+
+    /// Converts `wry`'s drop event, whose position is in physical pixels
+    /// everywhere but macOS, to a [`DropEvent`].
+    fn drop_event(&self, event: wry::FileDropEvent) -> Option<DropEvent> {
+        let scale = if cfg!(target_os = "macos") { 1.0 } else { self.scale_factor.get() };
+        let logical = |(x, y): (i32, i32)| (x as f64 / scale, y as f64 / scale);
+
+        match event {
+            wry::FileDropEvent::Hovered { paths, position } => Some(DropEvent::Hovered {
+                paths,
+                position: logical(position),
+                zone: self.drop_zone.borrow().clone(),
+            }),
+            wry::FileDropEvent::Dropped { paths, position } => Some(DropEvent::Dropped {
+                paths,
+                position: logical(position),
+                zone: self.drop_zone.borrow().clone(),
+            }),
+            wry::FileDropEvent::Cancelled => Some(DropEvent::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Lets `lib.js` find and highlight the drop zone under the cursor.
+    fn track_drop_zone(&self, event: &DropEvent) {
+        match event {
+            DropEvent::Hovered { position: (x, y), .. } => {
+                // CSS pixels are scaled by the page's zoom factor.
+                let zoom = self.config.state.zoom();
+                self.evaluate_script(&format!(
+                    "window.plugin.__ipc.dropHover({}, {});",
+                    x / zoom,
+                    y / zoom
+                ));
+            }
+            DropEvent::Dropped { .. } | DropEvent::Cancelled => {
+                self.drop_zone.replace(None);
+                self.evaluate_script("window.plugin.__ipc.dropEnd();");
+            }
+        }
+    }
+
+    /// Parses a message envelope from `lib.js` and dispatches it by its verb.
+    fn handle_ipc(&self, handler: &mut dyn EditorHandlerAny, cx: &mut Context<()>, message: Value) {
+        let event = match WebviewEvent::from_envelope(message) {
+            Ok(event) => event,
+            Err(ParseError::MissingEnvelope) => {
+                nih_error!(
+                    "Ignoring a message from the webview without an envelope. The page is either \
+                     using an outdated copy of lib.js, or posting to `window.ipc` directly instead \
+                     of using `window.plugin.send`."
+                );
+                return;
+            }
+            Err(err) => {
+                self.warnings.warn(format!("Ignoring a message from the webview: {err}."));
+                return;
+            }
+        };
+
+        self.handle_event(handler, cx, event);
+    }
+
+    /// Dispatches a parsed message from `lib.js`.
+    fn handle_event(
+        &self,
+        handler: &mut dyn EditorHandlerAny,
+        cx: &mut Context<()>,
+        event: WebviewEvent,
+    ) {
+        self.next_sequence();
+        match event {
+            WebviewEvent::Message(payload) => handler.on_message(cx, payload),
+            WebviewEvent::Unknown { verb, payload } => {
+                handler.on_unknown_ipc(cx, &verb, payload.to_string())
+            }
+            // Never returned by `from_envelope`.
+            WebviewEvent::UnknownVerb => {}
+            WebviewEvent::Chunk { id, index, count, data } => {
+                let message = self.chunks.borrow_mut().push(id, index, count, data);
+                if let Some(message) = message {
+                    match serde_json::from_str(&message) {
+                        Ok(message) => self.handle_ipc(handler, cx, message),
+                        Err(err) => {
+                            nih_warn!("Ignoring a chunked message from the webview: {err}.")
+                        }
+                    }
+                }
+            }
+            WebviewEvent::BridgeProbe => self.bridge.set(BridgeCheck::Ready),
+            WebviewEvent::Hello { version, user_agent, decimal_separator } => {
+                if version != PROTOCOL_VERSION {
+                    nih_error!(
+                        "The page uses lib.js protocol version {version}, but this version of \
+                         nih_plug_webview expects version {PROTOCOL_VERSION}. Messages may not \
+                         arrive as expected."
+                    );
+                }
+
+                // A reloaded page has lost the values sent before, and
+                // `onReady` callbacks expect every parameter to be known.
+                if let Some(params) = &self.config.params {
+                    params.resync();
+                }
+                self.bridge.set(BridgeCheck::Ready);
+                *self.user_agent.borrow_mut() = user_agent;
+                self.decimal_separator.set(decimal_separator);
+                self.last_transport.set(None);
+                self.display_values.reset();
+                self.sync_params();
+                if let Some(webview) = &self.webview {
+                    let mode = match cx.execution_mode() {
+                        ExecutionMode::Standalone => "standalone",
+                        ExecutionMode::Hosted(_) => "hosted",
+                    };
+                    let _ = webview.evaluate_script(&format!(
+                        "window.plugin.__ipc.ready({PROTOCOL_VERSION}, \"{mode}\");"
+                    ));
+                }
+            }
+            WebviewEvent::Resize { id, width, height } => {
+                let accepted = match resize_request(width, height) {
+                    Some((width, height)) => cx.resize_window(width, height),
+                    None => {
+                        self.warnings.warn(format!(
+                            "Ignoring invalid resize request: {width:?}x{height:?}."
+                        ));
+                        false
+                    }
+                };
+                // A throttled request is answered once it reached the host.
+                if accepted && self.pending_resize.get().is_some() {
+                    self.resize_replies.borrow_mut().push(id);
+                } else {
+                    self.send_reply(id, accepted);
+                }
+            }
+            WebviewEvent::SetParam { id, normalized } => {
+                if !normalized.is_finite() {
+                    self.warnings.warn(format!("Ignoring non-finite value for parameter {id}."));
+                } else if let Err(err) = cx.set_param_normalized_by_id(&id, normalized) {
+                    self.warnings.warn(format!("Could not set parameter from webview: {err}."));
+                }
+            }
+            WebviewEvent::BeginGesture { id } => {
+                if let Err(err) = cx.begin_gesture_by_id(&id) {
+                    self.warnings.warn(format!("Could not begin gesture from webview: {err}."));
+                }
+            }
+            WebviewEvent::EndGesture { id } => {
+                if let Err(err) = cx.end_gesture_by_id(&id) {
+                    self.warnings.warn(format!("Could not end gesture from webview: {err}."));
+                }
+            }
+            WebviewEvent::Key { down, key, code, key_code, repeat, shift, ctrl, alt, meta } => {
+                let mut modifiers = Modifiers::empty();
+                modifiers.set(Modifiers::SHIFT, shift);
+                modifiers.set(Modifiers::CONTROL, ctrl);
+                modifiers.set(Modifiers::ALT, alt);
+                modifiers.set(Modifiers::META, meta);
+
+                let event = KeyboardEvent {
+                    state: if down { KeyState::Down } else { KeyState::Up },
+                    key: key.parse().unwrap_or(Key::Unidentified),
+                    code: code.parse().unwrap_or(Code::Unidentified),
+                    modifiers,
+                    repeat,
+                    ..Default::default()
+                };
+
+                // Passing keys on to the host is not supported on every
+                // platform, in which case they're dropped.
+                if handler.on_key_event(cx, &event) == EventStatus::Ignored {
+                    platform::forward_key_to_host(cx.window, &event, key_code);
+                }
+            }
+            WebviewEvent::VerifyParamManifest { id, hash } => {
+                let expected = self.config.params.as_ref().map(|params| params.manifest().hash);
+                match &expected {
+                    Some(expected) if *expected == hash => {}
+                    Some(expected) => nih_error!(
+                        "The page was built for different parameters than the plugin has: its \
+                         parameter manifest has the hash {hash}, the plugin's has {expected}. \
+                         Regenerate it with `WebviewEditor::write_param_manifest`."
+                    ),
+                    None => nih_error!(
+                        "`window.plugin.params.verifyManifest` requires `WebviewEditor::with_params`."
+                    ),
+                }
+                self.send_reply(id, expected == Some(hash));
+            }
+            #[cfg(feature = "file-dialog")]
+            WebviewEvent::PickFile { id, options } => match serde_json::from_value(options) {
+                Ok(options) => self.open_file_dialog(cx.window, options, move |paths| {
+                    Box::new(move |_, cx| cx.handler.send_reply(id, paths))
+                }),
+                Err(err) => {
+                    nih_warn!("Invalid file dialog options: {err}.");
+                    self.send_reply(id, Value::Null);
+                }
+            },
+            #[cfg(not(feature = "file-dialog"))]
+            WebviewEvent::PickFile { id, .. } => {
+                nih_warn!("`window.plugin.pickFile` requires the `file-dialog` feature.");
+                self.send_reply(id, Value::Null);
+            }
+            #[cfg(feature = "http")]
+            WebviewEvent::Fetch { id, request } => {
+                match serde_json::from_value::<fetch::PageRequest>(request) {
+                    Ok(request) if self.fetch_allowed(request.url()) => {
+                        let allowed_urls = self.config.options.fetch_allowed_urls.clone();
+                        self.fetches.fetch_for_page(request.into_request(), id, allowed_urls)
+                    }
+                    Ok(request) => {
+                        let err = FetchError::NotAllowed(request.url().to_string());
+                        self.warnings.warn(format!("`window.plugin.fetch`: {err}."));
+                        self.send_reply(id, fetch::PageReply::from(Err(err)));
+                    }
+                    Err(err) => {
+                        let err = FetchError::Transport(format!("invalid request: {err}"));
+                        self.send_reply(id, fetch::PageReply::from(Err(err)));
+                    }
+                }
+            }
+            #[cfg(not(feature = "http"))]
+            WebviewEvent::Fetch { id, .. } => {
+                let err = "`window.plugin.fetch` requires the `http` feature.";
+                nih_warn!("{err}");
+                self.send_reply(id, serde_json::json!({ "error": err }));
+            }
+            #[cfg(feature = "file-dialog")]
+            WebviewEvent::SavePreset { id, name } => self.save_preset(cx.window, id, name),
+            #[cfg(feature = "file-dialog")]
+            WebviewEvent::LoadPreset { id } => self.load_preset(cx.window, id),
+            #[cfg(not(feature = "file-dialog"))]
+            WebviewEvent::SavePreset { id, .. } | WebviewEvent::LoadPreset { id } => {
+                let err = "`window.plugin.presets` requires the `file-dialog` feature.";
+                self.send_reply(id, PresetReply::Error(err.to_string()));
+            }
+            WebviewEvent::Channel { name, message } => {
+                handler.on_channel_message(cx, &name, message.to_string())
+            }
+            WebviewEvent::PeerBroadcast { message } => {
+                self.broadcast_to_peers(&message);
+            }
+            WebviewEvent::ContextMenu { x, y, element } => {
+                handler.on_context_menu(cx, x, y, element)
+            }
+            WebviewEvent::SaveUiState { value } => self.config.state.set_ui_state(value),
+            WebviewEvent::LoadUiState { id } => self.send_reply(id, self.config.state.ui_state()),
+            #[cfg(feature = "clipboard")]
+            WebviewEvent::ClipboardWrite { id, text } => match self.clipboard.write_text(&text) {
+                Ok(()) => self.send_reply(id, serde_json::json!({ "done": true })),
+                Err(err) => self.send_reply(id, serde_json::json!({ "error": err })),
+            },
+            #[cfg(feature = "clipboard")]
+            WebviewEvent::ClipboardRead { id, key } => {
+                let reply = if !self.read_gate.admits(key) {
+                    let err = "`window.plugin.clipboard.readText` requires a recent click or key \
+                               press.";
+                    serde_json::json!({ "error": err })
+                } else {
+                    match self.clipboard.read_text() {
+                        Ok(text) => serde_json::json!({ "text": text }),
+                        Err(err) => serde_json::json!({ "error": err }),
+                    }
+                };
+                self.send_reply(id, reply);
+            }
+            #[cfg(not(feature = "clipboard"))]
+            WebviewEvent::ClipboardWrite { id, .. } | WebviewEvent::ClipboardRead { id, .. } => {
+                let err = "`window.plugin.clipboard` requires the `clipboard` feature.";
+                nih_warn!("{err}");
+                self.send_reply(id, serde_json::json!({ "error": err }));
+            }
+            WebviewEvent::OpenDevtools => {
+                #[cfg(any(debug_assertions, feature = "devtools"))]
+                self.open_devtools();
+            }
+            WebviewEvent::OpenDiagnostics => {
+                if let Err(err) = cx.open_diagnostics() {
+                    nih_error!("Could not open the diagnostics: {err}");
+                }
+            }
+            WebviewEvent::Console { level, message } => {
+                self.console_history.push(level, &message);
+                if self.console_budget.take() {
+                    match level {
+                        ConsoleLevel::Debug => nih_trace!("[webview] {message}"),
+                        ConsoleLevel::Log | ConsoleLevel::Info => nih_log!("[webview] {message}"),
+                        ConsoleLevel::Warn => nih_warn!("[webview] {message}"),
+                        ConsoleLevel::Error => nih_error!("[webview] {message}"),
+                    }
+                    handler.on_console(cx, level, message);
+                }
+            }
+            WebviewEvent::DropZone { zone } => {
+                self.drop_zone.replace(zone);
+            }
+            WebviewEvent::Focus { focused } => handler.on_focus_changed(cx, focused),
+            WebviewEvent::FocusHost => {
+                if !platform::focus_host(cx.window) {
+                    platform::focus_window(cx.window);
+                }
+            }
+            WebviewEvent::Activity => self.activity(handler, cx),
+            // Only counts as a message, see `check_watchdog`.
+            WebviewEvent::Heartbeat => {}
+            WebviewEvent::PointerCapture { captured } => {
+                self.pointer_captured.set(captured);
+                self.last_captured_pointer.set(None);
+            }
+            WebviewEvent::SetCursorVisible { visible } => cx.set_cursor_visible(visible),
+            WebviewEvent::SetCursorPosition { x, y } => match (x, y) {
+                (Some(x), Some(y)) if x.is_finite() && y.is_finite() => {
+                    // CSS pixels are scaled by the page's zoom factor.
+                    let zoom = self.config.state.zoom();
+                    if !cx.set_cursor_position(x * zoom, y * zoom) {
+                        self.warnings.warn(
+                            "Moving the cursor is not supported on this platform.".to_string(),
+                        );
+                    }
+                }
+                _ => self.warnings.warn(format!("Ignoring invalid cursor position: {x:?}, {y:?}.")),
+            },
+        }
+    }
+}
+
+impl Drop for WindowHandler {
+    fn drop(&mut self) {
+        // A drag may still be in progress when the editor closes.
+        self.set_cursor_visible(true);
+    }
+}
+
+impl baseview::WindowHandler for WindowHandler {
+    fn on_frame(&mut self, window: &mut baseview::Window) {
+        if self.poisoned.get() {
+            // Keep the channels from piling up.
+            while self.next_message().is_ok() {}
+            while self.wry_event_rx.try_recv().is_ok() {}
+            while self.overlay_messages.1.try_recv().is_ok() {}
+            return;
+        }
+
+        // Child windows are not notified of DPI changes, so the scale factor
+        // is polled where that's possible.
+        let rescaled =
+            platform::scale_factor(window).filter(|&factor| self.rescale(window, factor));
+        let visibility_changed = platform::window_visible(window)
+            .filter(|&visible| self.visible.replace(visible) != visible);
+        // Child windows don't receive move events either. Minimized windows
+        // are moved out of sight on Windows, which is not worth keeping.
+        if ExecutionMode::new(&*self.context).is_standalone() && self.visible.get() {
+            if let Some(position) = platform::window_position(window) {
+                self.config.state.window_position.store(Some(position));
+            }
+        }
+
+        self.catch_panics(|| {
+            let mut handler = self.config.handler();
+            let mut cx = self.context(window);
+
+            if let Some(factor) = rescaled {
+                handler.on_scale_factor_changed(&mut cx, factor);
+            }
+            if let Some(visible) = visibility_changed {
+                handler.on_visibility_changed(&mut cx, visible);
+            }
+
+            while let Ok(message) = self.next_message() {
+                self.last_page_message.set(Instant::now());
+                self.unresponsive.set(false);
+                self.handle_ipc(&mut **handler, &mut cx, message);
+                // So the following messages reach the new handler.
+                self.attach_next_handler(&mut handler, &mut cx);
+            }
+            self.check_idle(&mut **handler, &mut cx);
+            self.check_watchdog(&mut **handler, &mut cx);
+            self.chunks.borrow_mut().expire();
+            while let Ok((overlay, message)) = self.overlay_messages.1.try_recv() {
+                if self.diagnostics.get() == Some(overlay) {
+                    if message == "close" {
+                        self.diagnostics.set(None);
+                        self.overlays.borrow_mut().remove(&overlay);
+                    }
+                    continue;
+                }
+                handler.on_message_from(&mut cx, overlay, message);
+            }
+            self.flush_resize(cx.window);
+            self.track_captured_pointer(cx.window);
+            if let Some((width, height)) = self.resize_denied.take() {
+                let size = serde_json::json!({ "width": width, "height": height });
+                if let Err(err @ SendError::Script(_)) =
+                    self.send_channel(RESIZE_DENIED_CHANNEL, size)
+                {
+                    nih_warn!("Failed to notify the webview of a denied resize: {err}.");
+                }
+                handler.on_resize_denied(&mut cx, (width as f64, height as f64));
+            }
+
+            if let Some(peers) = &self.peers {
+                while let Some(message) = peers.try_recv() {
+                    if let Err(err @ SendError::Script(_)) =
+                        self.send_channel(PEERS_CHANNEL, &message)
+                    {
+                        self.warnings.warn(format!("Failed to pass a peer message on: {err}."));
+                    }
+                    handler.on_peer_message(&mut cx, message.from, message.message);
+                }
+            }
+
+            self.sync_params();
+
+            if let (true, Some(params)) =
+                (self.config.options.coalesce_param_updates, &self.config.params)
+            {
+                let changes = params.take_batch();
+                if !changes.is_empty() {
+                    self.next_sequence();
+                    handler.on_params_batch_changed(&mut cx, &changes);
+                }
+            }
+
+            for queue in &self.config.audio_queues {
+                queue.drain(&mut |message| handler.on_audio_message(&mut cx, message));
+            }
+
+            if let Some(transport) = &self.config.transport {
+                let snapshot = transport.load();
+                if self.last_transport.replace(Some(snapshot)) != Some(snapshot) {
+                    match self.send_channel(TRANSPORT_CHANNEL, snapshot) {
+                        Ok(()) | Err(SendError::NoWebview) => {}
+                        Err(err) => self
+                            .warnings
+                            .warn(format!("Failed to send the transport to the webview: {err}.")),
+                    }
+                    handler.on_transport(&mut cx, &snapshot);
+                }
+            }
+
+            self.sync_display_values();
+
+            while let Ok(message) = self.background_messages.1.try_recv() {
+                handler.on_background_message(&mut cx, message);
+            }
+
+            self.evals.dispatch();
+
+            #[cfg(feature = "file-dialog")]
+            while let Ok(complete) = self.dialog_results.1.try_recv() {
+                complete(&mut **handler, &mut cx);
+            }
+
+            #[cfg(feature = "http")]
+            self.fetches.dispatch(|id, reply| self.send_reply(id, reply));
+
+            let history = &self.config.state.undo_history;
+            let availability =
+                history.lock().unwrap_or_else(PoisonError::into_inner).availability();
+            if self.undo_availability.replace(Some(availability)) != Some(availability) {
+                let (can_undo, can_redo) = availability;
+                handler.on_undo_state_changed(&mut cx, can_undo, can_redo);
+            }
+
+            while let Ok(event) = self.wry_event_rx.try_recv() {
+                match event {
+                    WryEvent::PageLoad(event) => {
+                        match (&event, self.bridge.get()) {
+                            (PageLoadEvent::Started { .. }, _) => {
+                                self.bridge.set(BridgeCheck::Loading)
+                            }
+                            (PageLoadEvent::Finished { .. }, BridgeCheck::Loading) => {
+                                self.probe_bridge()
+                            }
+                            _ => {}
+                        }
+                        // Some webviews reset the zoom when navigating.
+                        if let PageLoadEvent::Finished { .. } = event {
+                            self.apply_zoom();
+                            if self.config.options.splash.as_ref().is_some_and(|s| s.hide_on_load) {
+                                self.evaluate_script("window.plugin.hideSplash();");
+                            }
+                        }
+                        handler.on_page_load(&mut cx, event)
+                    }
+                    WryEvent::FileDrop(event) => {
+                        if let Some(event) = self.drop_event(event) {
+                            self.track_drop_zone(&event);
+                            handler.on_file_drop(&mut cx, event);
+                        }
+                    }
+                }
+            }
+
+            self.check_bridge(&mut **handler, &mut cx);
+
+            // Decided after the messages, so input ending the idle state
+            // isn't held back by the throttled rate.
+            if self.frame_due() {
+                handler.on_frame(&mut cx);
+            }
+            self.attach_next_handler(&mut handler, &mut cx);
+        });
+    }
+
+    fn on_event(&mut self, window: &mut baseview::Window, event: Event) -> EventStatus {
+        // Focus the webview so that it can receive keyboard events.
+        if let Some(webview) = &self.webview {
+            webview.focus();
+        }
+
+        // The window only gets key events while the page doesn't have the
+        // focus, so this is Tab moving the focus into the editor.
+        if let Event::Keyboard(KeyboardEvent {
+            state: KeyState::Down,
+            key: Key::Tab,
+            modifiers,
+            ..
+        }) = &event
+        {
+            if self.config.options.keyboard_navigation && self.webview.is_some() {
+                let reverse = modifiers.contains(Modifiers::SHIFT);
+                self.evaluate_script(&format!("window.plugin.__ipc.focusEdge({reverse});"));
+                return EventStatus::Captured;
+            }
+        }
+
+        if self.runtime_missing_ui {
+            if let Event::Mouse(MouseEvent::ButtonPressed { .. }) = event {
+                if let Err(err) = open_external(WEBVIEW2_DOWNLOAD_URL) {
+                    nih_error!("Failed to open {WEBVIEW2_DOWNLOAD_URL}: {err}.");
+                }
+            }
+        }
+
+        let mut rescaled = None;
+        let resized = match &event {
+            // A changed DPI leaves the physical size as it was, which must not
+            // shrink or grow the saved logical size.
+            Event::Window(WindowEvent::Resized(info))
+                if cfg!(target_os = "windows") && self.rescale(window, info.scale()) =>
+            {
+                rescaled = Some(info.scale());
+                None
+            }
+            Event::Window(WindowEvent::Resized(info)) => {
+                // The host may resize the window on its own, so keep the state
+                // and the webview in sync with the actual window size.
+                let size = info.logical_size();
+                self.scale_factor.set(info.scale());
+                let (width, height) = (size.width.round() as u32, size.height.round() as u32);
+                let state = &self.config.state;
+                state.content_size.store(size::content_size(
+                    (size.width, size.height),
+                    state.zoom(),
+                    state.content_size.load(),
+                ));
+                if let Some(webview) = &self.webview {
+                    webview.set_bounds(self.webview_bounds(width, height));
+                }
+                Some(size)
+            }
+            _ => None,
+        };
+
+        self.catch_panics(|| {
+            let mut handler = self.config.handler();
+            let mut cx = self.context(window);
+
+            if let Some(factor) = rescaled {
+                handler.on_scale_factor_changed(&mut cx, factor);
+            }
+            if let Some(size) = resized {
+                handler.on_window_resized(&mut cx, size.width, size.height);
+            }
+
+            let status = handler.on_window_event(&mut cx, event);
+            self.attach_next_handler(&mut handler, &mut cx);
+            status
+        })
+        .unwrap_or(EventStatus::Ignored)
+    }
+}
+
+//
+//
+//
+
+impl EditorHandler for () {
+    type EditorRx = ();
     type EditorTx = ();
 
     fn init(&mut self, _cx: &mut Context<Self>) {}
@@ -426,6 +3988,40 @@ trait EditorHandlerAny: Send + Sync {
     fn on_frame(&mut self, cx: &mut Context<()>);
     fn on_message(&mut self, cx: &mut Context<()>, message: Value);
     fn on_window_event(&mut self, cx: &mut Context<()>, event: Event) -> EventStatus;
+    fn on_window_open(&mut self, cx: &mut Context<()>);
+    fn on_attach(&mut self, cx: &mut Context<()>);
+    fn on_detach(&mut self, cx: &mut Context<()>);
+    fn on_params_batch_changed(&mut self, cx: &mut Context<()>, changes: &[(String, f32)]);
+    fn on_key_event(&mut self, cx: &mut Context<()>, event: &KeyboardEvent) -> EventStatus;
+    fn on_context_menu(&mut self, cx: &mut Context<()>, x: f64, y: f64, element_info: String);
+    fn on_background_message(&mut self, cx: &mut Context<()>, message: BackgroundMessage);
+    fn on_audio_message(&mut self, cx: &mut Context<()>, message: AudioMessage);
+    fn on_unknown_ipc(&mut self, cx: &mut Context<()>, verb: &str, payload: String);
+    fn on_channel_message(&mut self, cx: &mut Context<()>, channel: &str, payload: String);
+    fn on_page_load(&mut self, cx: &mut Context<()>, event: PageLoadEvent);
+    fn on_bridge_unavailable(&mut self, cx: &mut Context<()>, reason: &str);
+    fn on_undo_state_changed(&mut self, cx: &mut Context<()>, can_undo: bool, can_redo: bool);
+    fn on_error(&mut self, err: &WebviewError);
+    fn on_file_drop(&mut self, cx: &mut Context<()>, event: DropEvent);
+    #[cfg(feature = "file-dialog")]
+    fn on_file_dialog_result(
+        &mut self,
+        cx: &mut Context<()>,
+        token: u64,
+        paths: Option<Vec<PathBuf>>,
+    );
+    fn on_window_resized(&mut self, cx: &mut Context<()>, width: f64, height: f64);
+    fn on_scale_factor_changed(&mut self, cx: &mut Context<()>, factor: f64);
+    fn on_transport(&mut self, cx: &mut Context<()>, transport: &TransportSnapshot);
+    fn on_console(&mut self, cx: &mut Context<()>, level: ConsoleLevel, message: String);
+    fn on_resize_denied(&mut self, cx: &mut Context<()>, requested: (f64, f64));
+    fn on_peer_message(&mut self, cx: &mut Context<()>, from: InstanceId, message: String);
+    fn on_visibility_changed(&mut self, cx: &mut Context<()>, visible: bool);
+    fn on_unresponsive(&mut self, cx: &mut Context<()>, elapsed: Duration);
+    fn on_message_from(&mut self, cx: &mut Context<()>, overlay: OverlayId, message: String);
+    fn on_focus_changed(&mut self, cx: &mut Context<()>, focused: bool);
+    fn on_idle_changed(&mut self, cx: &mut Context<()>, idle: bool);
+    fn on_window_close(&mut self);
 }
 
 impl<H: EditorHandler> EditorHandlerAny for H {
@@ -440,21 +4036,432 @@ impl<H: EditorHandler> EditorHandlerAny for H {
     }
 
     fn on_message(&mut self, cx: &mut Context<()>, message: Value) {
-        let message =
-            serde_json::from_value(message).expect("Could not parse event from webview into T.");
-        let cx = unsafe { std::mem::transmute(cx) };
-        EditorHandler::on_message(self, cx, message)
+        match parse_message::<H>(message) {
+            Ok(message) => {
+                let cx = unsafe { std::mem::transmute(cx) };
+                EditorHandler::on_message(self, cx, message)
+            }
+            Err(err) => {
+                cx.handler.warnings.warn(format!("Ignoring a message from the webview: {err}."))
+            }
+        }
     }
 
     fn on_window_event(&mut self, cx: &mut Context<()>, event: Event) -> EventStatus {
         let cx = unsafe { std::mem::transmute(cx) };
         EditorHandler::on_window_event(self, cx, event)
     }
+
+    fn on_window_open(&mut self, cx: &mut Context<()>) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_window_open(self, cx)
+    }
+
+    fn on_attach(&mut self, cx: &mut Context<()>) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_attach(self, cx)
+    }
+
+    fn on_detach(&mut self, cx: &mut Context<()>) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_detach(self, cx)
+    }
+
+    fn on_params_batch_changed(&mut self, cx: &mut Context<()>, changes: &[(String, f32)]) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_params_batch_changed(self, cx, changes)
+    }
+
+    fn on_key_event(&mut self, cx: &mut Context<()>, event: &KeyboardEvent) -> EventStatus {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_key_event(self, cx, event)
+    }
+
+    fn on_context_menu(&mut self, cx: &mut Context<()>, x: f64, y: f64, element_info: String) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_context_menu(self, cx, x, y, element_info)
+    }
+
+    fn on_background_message(&mut self, cx: &mut Context<()>, message: BackgroundMessage) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_background_message(self, cx, message)
+    }
+
+    fn on_audio_message(&mut self, cx: &mut Context<()>, message: AudioMessage) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_audio_message(self, cx, message)
+    }
+
+    fn on_unknown_ipc(&mut self, cx: &mut Context<()>, verb: &str, payload: String) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_unknown_ipc(self, cx, verb, payload)
+    }
+
+    fn on_channel_message(&mut self, cx: &mut Context<()>, channel: &str, payload: String) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_channel_message(self, cx, channel, payload)
+    }
+
+    fn on_page_load(&mut self, cx: &mut Context<()>, event: PageLoadEvent) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_page_load(self, cx, event)
+    }
+
+    fn on_bridge_unavailable(&mut self, cx: &mut Context<()>, reason: &str) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_bridge_unavailable(self, cx, reason)
+    }
+
+    fn on_undo_state_changed(&mut self, cx: &mut Context<()>, can_undo: bool, can_redo: bool) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_undo_state_changed(self, cx, can_undo, can_redo)
+    }
+
+    fn on_error(&mut self, err: &WebviewError) {
+        EditorHandler::on_error(self, err)
+    }
+
+    fn on_file_drop(&mut self, cx: &mut Context<()>, event: DropEvent) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_file_drop(self, cx, event)
+    }
+
+    #[cfg(feature = "file-dialog")]
+    fn on_file_dialog_result(
+        &mut self,
+        cx: &mut Context<()>,
+        token: u64,
+        paths: Option<Vec<PathBuf>>,
+    ) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_file_dialog_result(self, cx, token, paths)
+    }
+
+    fn on_window_resized(&mut self, cx: &mut Context<()>, width: f64, height: f64) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_window_resized(self, cx, width, height)
+    }
+
+    fn on_scale_factor_changed(&mut self, cx: &mut Context<()>, factor: f64) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_scale_factor_changed(self, cx, factor)
+    }
+
+    fn on_transport(&mut self, cx: &mut Context<()>, transport: &TransportSnapshot) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_transport(self, cx, transport)
+    }
+
+    fn on_console(&mut self, cx: &mut Context<()>, level: ConsoleLevel, message: String) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_console(self, cx, level, message)
+    }
+
+    fn on_resize_denied(&mut self, cx: &mut Context<()>, requested: (f64, f64)) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_resize_denied(self, cx, requested)
+    }
+
+    fn on_peer_message(&mut self, cx: &mut Context<()>, from: InstanceId, message: String) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_peer_message(self, cx, from, message)
+    }
+
+    fn on_visibility_changed(&mut self, cx: &mut Context<()>, visible: bool) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_visibility_changed(self, cx, visible)
+    }
+
+    fn on_unresponsive(&mut self, cx: &mut Context<()>, elapsed: Duration) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_unresponsive(self, cx, elapsed)
+    }
+
+    fn on_message_from(&mut self, cx: &mut Context<()>, overlay: OverlayId, message: String) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_message_from(self, cx, overlay, message)
+    }
+
+    fn on_focus_changed(&mut self, cx: &mut Context<()>, focused: bool) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_focus_changed(self, cx, focused)
+    }
+
+    fn on_idle_changed(&mut self, cx: &mut Context<()>, idle: bool) {
+        let cx = unsafe { std::mem::transmute(cx) };
+        EditorHandler::on_idle_changed(self, cx, idle)
+    }
+
+    fn on_window_close(&mut self) {
+        EditorHandler::on_window_close(self)
+    }
+}
+
+/// The manifest of the parameters of a plugin which is not necessarily
+/// running.
+fn param_manifest(params: &impl Params) -> ParamManifest {
+    let params = params.param_map();
+    let params = params.iter().map(|(id, ptr, group)| (id.as_str(), *ptr, group.as_str()));
+    // SAFETY: The pointers are valid for as long as `params` is borrowed.
+    unsafe { ParamManifest::new(params) }
+}
+
+/// The initialization scripts in the order they run. The crate's own come
+/// first, so `window.plugin` exists in the [`WebviewConfig::init_scripts`].
+fn initialization_scripts<'a>(config_script: &'a str, options: &'a WebviewConfig) -> Vec<&'a str> {
+    let own = [config_script, LIB_JS];
+    own.into_iter().chain(options.init_scripts.iter().map(String::as_str)).collect()
+}
+
+/// Exposes the options `lib.js` needs as `window.__nihPlugWebviewConfig`.
+fn script_config(options: &WebviewConfig, host: &HostInfo, clipboard_key: Option<u64>) -> String {
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct ScriptConfig<'a> {
+        forward_unhandled_keys: bool,
+        native_context_menu: bool,
+        capture_console: bool,
+        suppress_zoom: bool,
+        keyboard_navigation: bool,
+        /// How often `lib.js` sends a heartbeat for the
+        /// [`WebviewConfig::watchdog`], in milliseconds.
+        heartbeat_interval: Option<u64>,
+        /// Parsed from [`WebviewConfig::accept_language`].
+        languages: Option<Vec<&'a str>>,
+        visualization_url: String,
+        splash: Option<ScriptSplash>,
+        host: &'a HostInfo,
+        /// See `clipboard::ReadGate`.
+        clipboard_key: Option<u64>,
+    }
+
+    #[derive(Serialize)]
+    struct ScriptSplash {
+        background: String,
+        /// `None` for the spinner.
+        html: Option<String>,
+    }
+
+    let config = ScriptConfig {
+        forward_unhandled_keys: options.forward_unhandled_keys,
+        native_context_menu: options.native_context_menu,
+        capture_console: options.capture_console,
+        suppress_zoom: options.pinch_policy == PinchPolicy::ToPage,
+        keyboard_navigation: options.keyboard_navigation,
+        heartbeat_interval: options
+            .watchdog
+            .timeout()
+            .map(|timeout| (timeout.as_millis() as u64 / 4).clamp(50, 1000)),
+        languages: options.accept_language.as_deref().map(|header| {
+            header
+                .split(',')
+                .filter_map(|language| language.split(';').next())
+                .map(str::trim)
+                .filter(|language| !language.is_empty())
+                .collect()
+        }),
+        visualization_url: custom_protocol_url(VISUALIZATION_PROTOCOL, ""),
+        splash: options.splash.as_ref().map(|splash| {
+            let (r, g, b, a) = splash.background_color;
+            ScriptSplash {
+                background: format!("rgba({r}, {g}, {b}, {})", a as f32 / 255.0),
+                html: match &splash.content {
+                    SplashContent::Spinner => None,
+                    SplashContent::Html(html) => Some(html.clone()),
+                },
+            }
+        }),
+        host,
+        clipboard_key,
+    };
+    let json = serde_json::to_string(&config).expect("Can't convert JSON to string.");
+    format!("window.__nihPlugWebviewConfig = {json};")
+}
+
+/// Encodes `value` as a JS string literal holding its JSON, which `lib.js`
+/// parses again. The JSON encoding of a string is also a valid JS string
+/// literal, so backticks, quotes and backslashes don't need any further
+/// escaping.
+fn js_json_literal<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string(&serde_json::to_string(value)?)
+}
+
+/// Validates a size from `window.plugin.resizeWindow`. Sizes which aren't
+/// positive are refused, and large ones are clamped to [`MAX_EDITOR_SIZE`].
+fn resize_request(width: Option<f64>, height: Option<f64>) -> Option<(u32, u32)> {
+    let dimension = |v: Option<f64>| {
+        v.filter(|v| v.is_finite() && *v > 0.0)
+            .map(|v| v.clamp(1.0, MAX_EDITOR_SIZE).round() as u32)
+    };
+    Some((dimension(width)?, dimension(height)?))
+}
+
+/// Parses a message sent with `window.plugin.send` into the handler's
+/// [`EditorHandler::EditorRx`]. Messages of another shape are an error, not a
+/// panic, as any script on the page can send them.
+fn parse_message<H: EditorHandler>(message: Value) -> Result<H::EditorRx, String> {
+    serde_json::from_value(message).map_err(|err| {
+        format!("it doesn't match `{}`: {err}", std::any::type_name::<H::EditorRx>())
+    })
+}
+
+/// Builds a webview showing a minimal page explaining why the plugin's own
+/// interface failed to load. Returns `None` if even that is not possible, in
+/// which case the editor window stays empty.
+fn error_page_webview(window: &Window, bounds: wry::Rect, err: &WebviewError) -> Option<WebView> {
+    let message = err.to_string().replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let html = format!(
+        "<body style=\"font-family: sans-serif; padding: 1em\">\
+         <h3>The plugin's interface could not be loaded</h3><p>{message}</p></body>"
+    );
+
+    WebViewBuilder::new_as_child(window)
+        .with_bounds(bounds)
+        .with_html(html)
+        .and_then(WebViewBuilder::build)
+        .ok()
+}
+
+/// Returns the origins the webview may navigate to under
+/// [`NavigationPolicy::SameOrigin`], in the form returned by [`url_origin`].
+fn allowed_origins(source: &WebviewSource) -> Vec<String> {
+    let custom_protocol = |protocol: &str| {
+        let protocol = protocol.to_ascii_lowercase();
+        if cfg!(target_os = "windows") {
+            // WebView2 serves custom protocols over `http(s)://<protocol>.localhost`.
+            vec![format!("http://{protocol}.localhost"), format!("https://{protocol}.localhost")]
+        } else {
+            vec![format!("{protocol}://localhost")]
+        }
+    };
+
+    match source {
+        WebviewSource::URL(url) => url_origin(url).into_iter().collect(),
+        WebviewSource::HTML(_) => vec!["about:".to_string(), "data:".to_string()],
+        WebviewSource::DirPath(_) => custom_protocol("wry"),
+        WebviewSource::CustomProtocol { protocol, .. } => custom_protocol(protocol),
+    }
+}
+
+/// Returns the origin of `url` in lowercase, like `https://example.com:8080`,
+/// or only its scheme, like `data:`, for URLs without a host. `None` if `url`
+/// has no scheme.
+fn url_origin(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once(':')?;
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return None;
+    }
+
+    let origin = match rest.strip_prefix("//") {
+        // Browsers treat `\` like `/` in URLs with a host.
+        Some(authority) => {
+            let end = authority.find(['/', '\\', '?', '#']).unwrap_or(authority.len());
+            &url[..scheme.len() + 3 + end]
+        }
+        None => &url[..scheme.len() + 1],
+    };
+    Some(origin.to_ascii_lowercase())
+}
+
+/// Whether `url` has exactly one of the `origins` returned by
+/// [`allowed_origins`]. Hosts which merely start with an allowed host, like
+/// `example.com.evil.org`, don't count.
+fn is_allowed_origin(origins: &[String], url: &str) -> bool {
+    url_origin(url).is_some_and(|origin| origins.contains(&origin))
+}
+
+/// Whether `url` has no scheme and no host, like `license.html` or `/about`.
+fn is_relative_url(url: &str) -> bool {
+    let scheme_end = url.find(':');
+    let path_start = url.find(['/', '\\', '?', '#']);
+    let has_scheme = match (scheme_end, path_start) {
+        (Some(i), Some(j)) => i < j,
+        (scheme_end, _) => scheme_end.is_some(),
+    };
+    // Browsers read `\` like `/`, so `\\evil.org` has a host too.
+    let has_host = url.len() >= 2 && url.as_bytes()[..2].iter().all(|&b| b == b'/' || b == b'\\');
+    !has_scheme && !has_host
+}
+
+/// Encodes a page as a `data:` URL.
+fn data_url(html: &str) -> String {
+    let mut url = String::from("data:text/html;charset=utf-8,");
+    for byte in html.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~!*'()".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    url
+}
+
+/// Opens a link the webview was not allowed to navigate to, as long as it
+/// uses a scheme which is safe to hand over to the system.
+fn open_link_externally(url: &str) {
+    if !["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme)) {
+        nih_warn!("Blocked navigation to {url}.");
+        return;
+    }
+
+    if let Err(err) = open_external(url) {
+        nih_error!("Failed to open {url}: {err}.");
+    }
+}
+
+/// Opens `url` with the system's default handler.
+fn open_external(url: &str) -> std::io::Result<()> {
+    use std::process::Command;
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("rundll32");
+        command.args(["url.dll,FileProtocolHandler", url]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg(url);
+        command
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(url);
+        command
+    };
+
+    let mut child = command.spawn()?;
+    // Reap the process once it exits.
+    std::thread::spawn(move || child.wait());
+
+    Ok(())
 }
 
 /// TODO: Use async.
+/// Serves a file from `root` for [`WebviewSource::DirPath`].
+fn serve_dir(
+    root: &PathBuf,
+    csp: Option<&str>,
+    request: Request<Vec<u8>>,
+) -> http::Response<Cow<'static, [u8]>> {
+    match get_wry_response(root, csp, request) {
+        Ok(r) => r.map(Into::into),
+        Err(e) => http::Response::builder()
+            .header(CONTENT_TYPE, "text/plain")
+            .status(500)
+            .body(e.to_string().as_bytes().to_vec())
+            .unwrap()
+            .map(Into::into),
+    }
+}
+
 fn get_wry_response(
     root: &PathBuf,
+    csp: Option<&str>,
     request: Request<Vec<u8>>,
 ) -> Result<http::Response<Vec<u8>>, Box<dyn std::error::Error>> {
     let path = request.uri().path();
@@ -470,5 +4477,282 @@ fn get_wry_response(
     let mimetype =
         mime_guess::from_path(&path).first().map(|mime| mime.to_string()).unwrap_or("".to_string());
 
-    Response::builder().header(CONTENT_TYPE, mimetype).body(content).map_err(Into::into)
+    let mut response = Response::builder().header(CONTENT_TYPE, mimetype);
+    if let Some(csp) = csp {
+        response = response.header(CONTENT_SECURITY_POLICY, csp);
+    }
+    response.body(content).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(tag = "type")]
+    enum GainMessage {
+        SetGain { value: f32 },
+    }
+
+    struct GainHandler;
+
+    impl EditorHandler for GainHandler {
+        type EditorTx = ();
+        type EditorRx = GainMessage;
+
+        fn init(&mut self, _cx: &mut Context<Self>) {}
+        fn on_frame(&mut self, _cx: &mut Context<Self>) {}
+        fn on_message(&mut self, _cx: &mut Context<Self>, _message: Self::EditorRx) {}
+    }
+
+    #[test]
+    fn persistent_field_restores_every_field() {
+        let state = WebviewState::new(400, 300);
+        state.content_size.store((640, 480));
+        state.zoom.store(1.5);
+        state.window_position.store(Some((10.0, 20.0)));
+        state.set_ui_state(serde_json::json!({ "theme": "dark" }));
+        let instance_id = state.instance_id();
+
+        let saved = state.map(serde_json::to_string).unwrap();
+        let restored = WebviewState::new(400, 300);
+        restored.set(serde_json::from_str(&saved).unwrap());
+
+        let fields = |state: &WebviewState| {
+            (state.content_size(), state.zoom(), state.window_position(), state.ui_state())
+        };
+        assert_eq!(restored.map(fields), state.map(fields));
+        assert_eq!(restored.instance_id(), instance_id);
+    }
+
+    #[test]
+    fn invalid_restored_sizes_fall_back_to_the_default() {
+        let restore = |size: Value| {
+            let state = WebviewState::new(400, 300);
+            state.set(serde_json::from_value(serde_json::json!({ "size": size })).unwrap());
+            state.content_size()
+        };
+
+        assert_eq!(restore(serde_json::json!([640, 480])), (640, 480));
+        assert_eq!(restore(serde_json::json!([640.4, 479.6])), (640, 480));
+        for size in [
+            serde_json::json!([0, 480]),
+            serde_json::json!([640, 0]),
+            serde_json::json!([-640, 480]),
+            serde_json::json!([640, 1e9]),
+            serde_json::json!(["640", 480]),
+            serde_json::json!([640]),
+            serde_json::json!(null),
+        ] {
+            assert_eq!(restore(size.clone()), (400, 300), "{size}");
+        }
+    }
+
+    #[test]
+    fn json_literals_survive_tricky_strings() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Nested {
+            text: String,
+            children: Vec<Nested>,
+        }
+
+        let tricky =
+            ["`${alert(1)}`", "\"quoted\" \\ 'single'", "</script>\u{2028}\u{2029}", "🎛️ ünïcode"];
+        let value = Nested {
+            text: tricky.concat(),
+            children: tricky
+                .iter()
+                .map(|text| Nested { text: text.to_string(), children: Vec::new() })
+                .collect(),
+        };
+
+        let literal = js_json_literal(&value).unwrap();
+        assert!(literal.starts_with('"') && literal.ends_with('"'));
+        let json: String = serde_json::from_str(&literal).unwrap();
+        assert_eq!(serde_json::from_str::<Nested>(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn resize_requests_must_be_positive() {
+        assert_eq!(resize_request(Some(640.0), Some(480.4)), Some((640, 480)));
+        assert_eq!(resize_request(Some(0.2), Some(1e9)), Some((1, 16384)));
+        for invalid in
+            [None, Some(0.0), Some(-0.0), Some(-1.0), Some(f64::NAN), Some(f64::INFINITY)]
+        {
+            assert_eq!(resize_request(invalid, Some(480.0)), None, "{invalid:?}");
+            assert_eq!(resize_request(Some(640.0), invalid), None, "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn init_scripts_run_after_lib_js() {
+        let options = WebviewConfig {
+            init_scripts: vec!["first()".to_string(), "second()".to_string()],
+            ..WebviewConfig::default()
+        };
+        assert_eq!(
+            initialization_scripts("config()", &options),
+            ["config()", LIB_JS, "first()", "second()"]
+        );
+        assert_eq!(
+            initialization_scripts("config()", &WebviewConfig::default()),
+            ["config()", LIB_JS]
+        );
+    }
+
+    #[test]
+    fn throttled_resizes_wait_for_the_interval() {
+        let last = Instant::now();
+        let throttle = ResizeThrottle::Millis(50);
+        assert!(throttle.is_due(None, last));
+        assert!(!throttle.is_due(Some(last), last));
+        assert!(!throttle.is_due(Some(last), last + Duration::from_millis(49)));
+        assert!(throttle.is_due(Some(last), last + Duration::from_millis(50)));
+
+        // Flushed once per frame, and right away without a throttle.
+        for throttle in [ResizeThrottle::None, ResizeThrottle::PerFrame] {
+            assert!(throttle.is_due(Some(last), last));
+        }
+    }
+
+    #[test]
+    fn host_scale_factor_applies_to_the_next_window() {
+        let state = WebviewState::new(400, 300);
+        let editor =
+            WebviewEditor::new("Test".into(), WebviewSource::HTML(String::new()), state, (), None);
+        assert!(editor.set_scale_factor(1.5));
+        for factor in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(!editor.set_scale_factor(factor), "{factor}");
+        }
+
+        // Sizes stay logical, nih-plug applies the factor to them. The window
+        // is created at the right size, without a resize request afterwards.
+        assert_eq!(editor.size(), (400, 300));
+        let options = editor.window_open_options();
+        assert_eq!(options.scale, WindowScalePolicy::ScaleFactor(1.5));
+        assert_eq!((options.size.width, options.size.height), (400.0, 300.0));
+
+        // Changes while open only apply to the next window.
+        editor.open.store(true, Ordering::SeqCst);
+        assert!(!editor.set_scale_factor(2.0));
+        assert_eq!(editor.scale_factor.load(), Some(1.5));
+    }
+
+    #[test]
+    fn messages_of_another_shape_are_an_error() {
+        let message = serde_json::json!({ "type": "SetGain", "value": 0.5 });
+        assert_eq!(parse_message::<GainHandler>(message), Ok(GainMessage::SetGain { value: 0.5 }));
+
+        for message in [
+            serde_json::json!({ "type": "SetGain", "value": "loud" }),
+            serde_json::json!({ "type": "Explode" }),
+            serde_json::json!("SetGain"),
+            serde_json::json!(null),
+        ] {
+            let err = parse_message::<GainHandler>(message).unwrap_err();
+            assert!(err.contains("GainMessage"), "{err}");
+        }
+    }
+
+    fn allows(source: &WebviewSource, url: &str) -> bool {
+        is_allowed_origin(&allowed_origins(source), url)
+    }
+
+    #[test]
+    fn url_source_allows_its_own_origin() {
+        let source = WebviewSource::URL("https://example.com/app/index.html".to_string());
+        for url in [
+            "https://example.com",
+            "https://example.com/",
+            "https://example.com/license.html",
+            "https://example.com?page=2",
+            "https://example.com#settings",
+            "https://EXAMPLE.com/Other",
+        ] {
+            assert!(allows(&source, url), "{url}");
+        }
+    }
+
+    #[test]
+    fn url_source_rejects_other_origins() {
+        let source = WebviewSource::URL("https://example.com".to_string());
+        for url in [
+            "https://example.com.evil.org",
+            "https://example.com.evil.org/",
+            "https://example.community/",
+            "https://example.com:8443/",
+            "https://example.com@evil.org/",
+            "http://example.com/",
+            "https://evil.org/?https://example.com",
+            "example.com",
+            "",
+        ] {
+            assert!(!allows(&source, url), "{url}");
+        }
+    }
+
+    #[test]
+    fn url_source_keeps_the_port() {
+        let source = WebviewSource::URL("http://localhost:5173/".to_string());
+        assert!(allows(&source, "http://localhost:5173/src/main.js"));
+        assert!(!allows(&source, "http://localhost:51730/"));
+        assert!(!allows(&source, "http://localhost/"));
+    }
+
+    #[test]
+    fn custom_protocol_sources_only_allow_localhost() {
+        let dir = WebviewSource::DirPath(PathBuf::from("ui"));
+        let custom = WebviewSource::CustomProtocol {
+            protocol: "app".to_string(),
+            url_path: "index.html".to_string(),
+        };
+
+        assert!(allows(&dir, &custom_protocol_url("wry", "index.html")));
+        assert!(allows(&custom, &custom_protocol_url("app", "settings.html")));
+        for url in [
+            "http://wry.attacker.com/",
+            "https://wry.attacker.com",
+            "http://wry.localhost.attacker.com/",
+            "wry://localhost.attacker.com/",
+            "http://app.localhost/",
+        ] {
+            assert!(!allows(&dir, url), "{url}");
+        }
+        assert!(!allows(&custom, &custom_protocol_url("wry", "index.html")));
+    }
+
+    #[test]
+    fn html_sources_only_allow_data_and_about() {
+        let source = WebviewSource::HTML("<h1>Hello</h1>".to_string());
+        assert!(allows(&source, "about:blank"));
+        assert!(allows(&source, "data:text/html,<a href=\"https://example.com\">"));
+        assert!(!allows(&source, "https://example.com/"));
+    }
+
+    #[test]
+    fn only_urls_without_scheme_and_host_are_relative() {
+        for url in ["license.html", "/about", "./a:b", "?page=2", "#settings", ""] {
+            assert!(is_relative_url(url), "{url}");
+        }
+        for url in
+            ["https://evil.org", "//evil.org", "\\\\evil.org", "/\\evil.org", "javascript:alert(1)"]
+        {
+            assert!(!is_relative_url(url), "{url}");
+        }
+    }
+
+    #[test]
+    fn url_origin_stops_at_the_path() {
+        assert_eq!(
+            url_origin("HTTPS://Example.com:8080/a?b#c").as_deref(),
+            Some("https://example.com:8080")
+        );
+        assert_eq!(
+            url_origin("https://example.com\\@evil.org").as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(url_origin("data:text/html,http://example.com").as_deref(), Some("data:"));
+        assert_eq!(url_origin("license.html"), None);
+        assert_eq!(url_origin("/about:blank"), None);
+    }
 }
@@ -0,0 +1,77 @@
+//! Keeping the log readable on paths which run every frame or for every
+//! message, see [`WebviewConfig::log_ipc`](crate::WebviewConfig::log_ipc).
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use nih_plug::nih_warn;
+
+/// How long a warning is suppressed after it was logged.
+const WARNING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The most distinct warnings remembered. Older ones are forgotten once there
+/// are more, which at worst logs one of them again.
+const MAX_WARNINGS: usize = 64;
+
+/// The most characters of a payload shown by [`ipc_summary`].
+const PREVIEW_LEN: usize = 80;
+
+/// Logs each distinct warning at most once per [`WARNING_INTERVAL`], along
+/// with how often it was suppressed since it was last logged.
+#[derive(Default)]
+pub(crate) struct Warnings {
+    seen: RefCell<HashMap<String, (Instant, u32)>>,
+}
+
+impl Warnings {
+    pub fn warn(&self, message: String) {
+        let now = Instant::now();
+        let mut seen = self.seen.borrow_mut();
+        if let Some((logged, suppressed)) = seen.get_mut(&message) {
+            if now.duration_since(*logged) < WARNING_INTERVAL {
+                *suppressed += 1;
+                return;
+            }
+            match std::mem::take(suppressed) {
+                0 => nih_warn!("{message}"),
+                suppressed => nih_warn!("{message} (repeated {suppressed} more times)"),
+            }
+            *logged = now;
+            return;
+        }
+
+        nih_warn!("{message}");
+        if seen.len() == MAX_WARNINGS {
+            seen.retain(|_, (logged, _)| now.duration_since(*logged) < WARNING_INTERVAL);
+            if seen.len() == MAX_WARNINGS {
+                seen.clear();
+            }
+        }
+        seen.insert(message, (now, 0));
+    }
+}
+
+/// Which way a message crossed the IPC bridge.
+#[derive(Clone, Copy)]
+pub(crate) enum Direction {
+    ToPage,
+    FromPage,
+}
+
+/// A one-line summary of a message, e.g.
+/// `ipc <- param (42 bytes) {"id":"gain","value":0.5}`, with the payload cut
+/// off after [`PREVIEW_LEN`] characters.
+pub(crate) fn ipc_summary(direction: Direction, verb: &str, payload: &str) -> String {
+    let arrow = match direction {
+        Direction::ToPage => "->",
+        Direction::FromPage => "<-",
+    };
+    let bytes = payload.len();
+    match payload.char_indices().nth(PREVIEW_LEN) {
+        Some((end, _)) => format!("ipc {arrow} {verb} ({bytes} bytes) {}...", &payload[..end]),
+        None => format!("ipc {arrow} {verb} ({bytes} bytes) {payload}"),
+    }
+}
@@ -0,0 +1,80 @@
+//! Parsing numbers typed by users, see
+//! [`Context::parse_user_number`](crate::Context::parse_user_number).
+
+/// Parses a number written with `decimal_separator`, ignoring digit grouping
+/// like `1,000.5`, `1.000,5`, `1 000,5` or `1'000.5`. Fullwidth digits and
+/// punctuation, as typed by CJK input methods, are read like ASCII ones.
+///
+/// The other one of `.` and `,` is taken for grouping, unless it occurs once
+/// and isn't followed by exactly three digits. So `0.5` still works with a
+/// comma decimal separator, as many keyboards' number pads type a point.
+pub(crate) fn parse(text: &str, decimal_separator: char) -> Option<f64> {
+    let text: String = text.trim().chars().map(halfwidth).collect();
+    let text = text.as_str();
+    let group = if decimal_separator == ',' { '.' } else { ',' };
+    let group_is_decimal = !text.contains(decimal_separator)
+        && text.matches(group).count() == 1
+        && text
+            .split(group)
+            .nth(1)
+            .map_or(0, |rest| rest.chars().take_while(char::is_ascii_digit).count())
+            != 3;
+
+    let mut number = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if c == decimal_separator => number.push('.'),
+            c if c == group => {
+                if group_is_decimal {
+                    number.push('.');
+                }
+            }
+            ' ' | '\u{a0}' | '\u{202f}' | '\'' | '\u{2019}' => {}
+            '\u{2212}' => number.push('-'),
+            c => number.push(c),
+        }
+    }
+
+    number.parse::<f64>().ok().filter(|number| number.is_finite())
+}
+
+/// Maps the fullwidth forms of ASCII characters, like `５` or `，`, to ASCII.
+fn halfwidth(c: char) -> char {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_decimal_commas_and_points() {
+        assert_eq!(parse("0,5", ','), Some(0.5));
+        assert_eq!(parse("0.5", ','), Some(0.5));
+        assert_eq!(parse("-1.000,25", ','), Some(-1000.25));
+        assert_eq!(parse("1.000", ','), Some(1000.0));
+        assert_eq!(parse("1,000.5", '.'), Some(1000.5));
+        assert_eq!(parse("1,000", '.'), Some(1000.0));
+        assert_eq!(parse("0,5", '.'), Some(0.5));
+        assert_eq!(parse(" 1 000,5\u{a0}", ','), Some(1000.5));
+        assert_eq!(parse("1'000.5", '.'), Some(1000.5));
+        assert_eq!(parse("\u{2212}3", '.'), Some(-3.0));
+    }
+
+    #[test]
+    fn parses_fullwidth_digits() {
+        assert_eq!(parse("０，５", ','), Some(0.5));
+        assert_eq!(parse("－１２．５", '.'), Some(-12.5));
+        assert_eq!(parse("１，０００", '.'), Some(1000.0));
+    }
+
+    #[test]
+    fn rejects_text_which_isnt_a_number() {
+        for text in ["", "abc", "五", "一・五", "🎛", "5🎛", "inf", "NaN", "1e999"] {
+            assert_eq!(parse(text, '.'), None, "{text}");
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Secondary webviews shown on top of the editor's own, see
+//! [`Context::create_overlay`](crate::Context::create_overlay).
+
+use baseview::Window;
+use crossbeam::channel::Sender;
+use wry::{WebView, WebViewBuilder};
+
+use crate::{serve_dir, size::physical_bounds, EditorHandler, WebviewError, WebviewSource};
+
+/// Options for [`Context::create_overlay`](crate::Context::create_overlay).
+#[derive(Debug, Clone)]
+pub struct OverlayConfig {
+    /// The overlay's page. [`WebviewSource::CustomProtocol`] is not
+    /// supported, as protocols registered for the editor's webview are not
+    /// available to the overlay.
+    pub source: WebviewSource,
+    /// The top left corner relative to the editor's webview, in logical
+    /// pixels.
+    pub position: (i32, i32),
+    /// The size in logical pixels.
+    pub size: (u32, u32),
+    /// Makes the parts of the overlay the page doesn't paint transparent.
+    pub transparent: bool,
+}
+
+/// Identifies the overlay a message came from, see
+/// [`EditorHandler::on_message_from`](crate::EditorHandler::on_message_from).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OverlayId(pub(crate) u64);
+
+/// An overlay created with
+/// [`Context::create_overlay`](crate::Context::create_overlay). Overlays are
+/// closed together with the editor window, after which the methods here do
+/// nothing.
+#[derive(Debug)]
+pub struct OverlayHandle {
+    pub(crate) id: OverlayId,
+}
+
+impl OverlayHandle {
+    pub fn id(&self) -> OverlayId {
+        self.id
+    }
+
+    /// Moves and resizes the overlay, in logical pixels relative to the
+    /// editor's webview. Returns `false` if the overlay is closed.
+    pub fn set_bounds<H: EditorHandler>(
+        &self,
+        cx: &mut crate::Context<H>,
+        position: (i32, i32),
+        size: (u32, u32),
+    ) -> bool {
+        let scale_factor = cx.handler.scale_factor.get();
+        match cx.handler.overlays.borrow().get(&self.id) {
+            Some(webview) => {
+                webview.set_bounds(overlay_bounds(position, size, scale_factor));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Evaluates a script in the overlay's page, e.g. to pass a message to
+    /// it. Returns `false` if the overlay is closed.
+    pub fn evaluate_script<H: EditorHandler>(
+        &self,
+        cx: &mut crate::Context<H>,
+        script: &str,
+    ) -> bool {
+        match cx.handler.overlays.borrow().get(&self.id) {
+            Some(webview) => webview.evaluate_script(script).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Closes the overlay.
+    pub fn close<H: EditorHandler>(self, cx: &mut crate::Context<H>) {
+        cx.handler.overlays.borrow_mut().remove(&self.id);
+    }
+}
+
+/// Builds an overlay's webview. Messages its page posts with
+/// `window.ipc.postMessage` are sent to `messages`.
+pub(crate) fn build(
+    window: &Window,
+    id: OverlayId,
+    config: OverlayConfig,
+    scale_factor: f64,
+    messages: Sender<(OverlayId, String)>,
+) -> Result<WebView, WebviewError> {
+    let builder = WebViewBuilder::new_as_child(window)
+        .with_bounds(overlay_bounds(config.position, config.size, scale_factor))
+        .with_transparent(config.transparent)
+        .with_ipc_handler(move |message: String| {
+            let _ = messages.send((id, message));
+        });
+
+    match config.source {
+        WebviewSource::URL(url) => builder.with_url(&url),
+        WebviewSource::HTML(html) => builder.with_html(html),
+        WebviewSource::DirPath(root) => builder
+            .with_custom_protocol("wry".to_string(), move |request| serve_dir(&root, None, request))
+            .with_url("wry://localhost"),
+        WebviewSource::CustomProtocol { .. } => return Err(WebviewError::UnsupportedOverlaySource),
+    }
+    .and_then(WebViewBuilder::build)
+    .map_err(WebviewError::Build)
+}
+
+fn overlay_bounds((x, y): (i32, i32), size: (u32, u32), scale_factor: f64) -> wry::Rect {
+    let scale = if cfg!(target_os = "windows") { scale_factor } else { 1.0 };
+    wry::Rect {
+        x: (x as f64 * scale).round() as i32,
+        y: (y as f64 * scale).round() as i32,
+        ..physical_bounds(size, scale_factor)
+    }
+}
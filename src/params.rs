@@ -0,0 +1,410 @@
+//! Access to the plugin's parameters through their string IDs.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use nih_plug::prelude::{ParamFlags, ParamPtr, Params};
+use serde::Serialize;
+
+use crate::workdir::fnv1a;
+
+/// The plugin's parameters, indexed by their IDs.
+pub(crate) struct ParamMap {
+    /// Keeps the [`ParamPtr`]s below valid.
+    _params: Arc<dyn Params>,
+    params: Vec<MappedParam>,
+    index: HashMap<String, usize>,
+    /// Changes not yet pushed to the frontend by the parameter sync.
+    sync_changes: ChangeFlags,
+    /// Changes not yet delivered to
+    /// [`EditorHandler::on_params_batch_changed`](crate::EditorHandler::on_params_batch_changed).
+    batch_changes: ChangeFlags,
+}
+
+struct MappedParam {
+    id: String,
+    ptr: ParamPtr,
+    group: String,
+}
+
+/// Tracks which parameters changed. These are set from
+/// [`nih_plug::prelude::Editor::param_value_changed`], which may be called
+/// from the audio thread, hence no locking or allocations.
+struct ChangeFlags {
+    changed: Vec<AtomicBool>,
+    /// Set when every parameter has changed, e.g. when the editor opens or the
+    /// host loads a preset.
+    all_changed: AtomicBool,
+}
+
+impl ChangeFlags {
+    fn new(len: usize) -> ChangeFlags {
+        ChangeFlags {
+            changed: (0..len).map(|_| AtomicBool::new(false)).collect(),
+            all_changed: AtomicBool::new(true),
+        }
+    }
+
+    fn mark(&self, i: usize) {
+        self.changed[i].store(true, Ordering::Relaxed);
+    }
+
+    fn mark_all(&self) {
+        self.all_changed.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the indices of the parameters changed since the last call.
+    fn take(&self) -> impl Iterator<Item = usize> + '_ {
+        let all = self.all_changed.swap(false, Ordering::Relaxed);
+        (0..self.changed.len())
+            .filter(move |&i| self.changed[i].swap(false, Ordering::Relaxed) || all)
+    }
+}
+
+/// Metadata about one of the plugin's parameters, see
+/// [`Context::param_info`](crate::Context::param_info).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ParamInfo {
+    /// The parameter's stable ID.
+    pub id: String,
+    /// The parameter's display name.
+    pub name: String,
+    /// The unit appended to the parameter's value, e.g. `" dB"`.
+    pub unit: String,
+    /// The parameter's group path, with groups separated by slashes. Empty for
+    /// top level parameters.
+    pub group: String,
+    /// The number of steps for discrete parameters, `None` for continuous ones.
+    pub step_count: Option<usize>,
+    /// The parameter's default value, normalized.
+    pub default_normalized: f32,
+    /// Whether this is the plugin's bypass parameter.
+    pub is_bypass: bool,
+}
+
+/// Describes every parameter for generating frontend code, see
+/// [`WebviewEditor::write_param_manifest`](crate::WebviewEditor::write_param_manifest).
+#[derive(Serialize)]
+pub(crate) struct ParamManifest {
+    /// Identifies the parameters, so that a page built against an outdated
+    /// manifest can be told apart.
+    pub hash: String,
+    params: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+    id: String,
+    name: String,
+    /// `"float"`, `"int"`, `"bool"` or `"enum"`.
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// The plain values at the ends of the parameter's range.
+    min: f32,
+    max: f32,
+    /// The plain default value.
+    default: f32,
+    unit: String,
+    step_count: Option<usize>,
+    group: String,
+    /// The names of an enum parameter's variants, in order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variants: Option<Vec<String>>,
+}
+
+impl ParamManifest {
+    /// # Safety
+    ///
+    /// The pointers must be valid for the duration of the call.
+    pub unsafe fn new<'a>(
+        params: impl IntoIterator<Item = (&'a str, ParamPtr, &'a str)>,
+    ) -> ParamManifest {
+        let params: Vec<_> = params
+            .into_iter()
+            .map(|(id, ptr, group)| {
+                let kind = match ptr {
+                    ParamPtr::FloatParam(_) => "float",
+                    ParamPtr::IntParam(_) => "int",
+                    ParamPtr::BoolParam(_) => "bool",
+                    ParamPtr::EnumParam(_) => "enum",
+                };
+                let step_count = ptr.step_count();
+                let variants = match (ptr, step_count) {
+                    (ParamPtr::EnumParam(_), Some(steps)) => Some(
+                        (0..=steps)
+                            .map(|step| {
+                                let normalized = step as f32 / steps.max(1) as f32;
+                                ptr.normalized_value_to_string(normalized, false)
+                            })
+                            .collect(),
+                    ),
+                    _ => None,
+                };
+                ManifestEntry {
+                    id: id.to_string(),
+                    name: ptr.name().to_string(),
+                    kind,
+                    min: ptr.preview_plain(0.0),
+                    max: ptr.preview_plain(1.0),
+                    default: ptr.preview_plain(ptr.default_normalized_value()),
+                    unit: ptr.unit().to_string(),
+                    step_count,
+                    group: group.to_string(),
+                    variants,
+                }
+            })
+            .collect();
+
+        let json = serde_json::to_string(&params).expect("Can't convert JSON to string.");
+        ParamManifest { hash: format!("{:016x}", fnv1a(&json)), params }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Can't convert JSON to string.")
+    }
+
+    /// TypeScript declarations of the parameter IDs, the hash, and the
+    /// variants of enum parameters.
+    pub fn to_declarations(&self) -> String {
+        let literal =
+            |text: &str| serde_json::to_string(text).expect("Can't convert JSON to string.");
+        let union = |names: &mut dyn Iterator<Item = &str>| {
+            let union: Vec<_> = names.map(literal).collect();
+            if union.is_empty() {
+                "never".to_string()
+            } else {
+                union.join(" | ")
+            }
+        };
+
+        let mut out = String::from(
+            "// Generated by nih_plug_webview from the plugin's parameters. Do not edit.\n\n",
+        );
+        out += &format!(
+            "export type ParamId = {};\n\n",
+            union(&mut self.params.iter().map(|param| param.id.as_str()))
+        );
+        out += "/** Pass to `window.plugin.params.verifyManifest`. */\n";
+        out += &format!("export type ParamManifestHash = {};\n\n", literal(&self.hash));
+        out += "export interface ParamVariants {\n";
+        for param in &self.params {
+            if let Some(variants) = &param.variants {
+                let variants = union(&mut variants.iter().map(String::as_str));
+                out += &format!("  {}: {variants};\n", literal(&param.id));
+            }
+        }
+        out += "}\n";
+        out
+    }
+}
+
+/// Returned when a parameter can't be resolved from its ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamIdError {
+    /// None of the plugin's parameters has this ID.
+    UnknownId(String),
+    /// The editor was not given the plugin's parameters with
+    /// [`WebviewEditor::with_params`](crate::WebviewEditor::with_params).
+    ParamsNotProvided,
+}
+
+impl std::fmt::Display for ParamIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamIdError::UnknownId(id) => write!(f, "unknown parameter ID: {id}"),
+            ParamIdError::ParamsNotProvided => {
+                write!(f, "the editor was not given the plugin's parameters")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParamIdError {}
+
+/// A parameter value pushed to the frontend by the parameter sync.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ParamUpdate {
+    id: String,
+    normalized: f32,
+    plain: f32,
+    text: String,
+}
+
+impl ParamMap {
+    pub fn new(params: Arc<dyn Params>) -> ParamMap {
+        let params_vec: Vec<_> = params
+            .param_map()
+            .into_iter()
+            .map(|(id, ptr, group)| MappedParam { id, ptr, group })
+            .collect();
+        let index = params_vec.iter().enumerate().map(|(i, p)| (p.id.clone(), i)).collect();
+
+        ParamMap {
+            _params: params,
+            sync_changes: ChangeFlags::new(params_vec.len()),
+            batch_changes: ChangeFlags::new(params_vec.len()),
+            params: params_vec,
+            index,
+        }
+    }
+
+    /// Returns the parameter with the given ID.
+    pub fn get(&self, id: &str) -> Option<ParamPtr> {
+        self.index.get(id).map(|&i| self.params[i].ptr)
+    }
+
+    /// Returns the metadata for the parameter with the given ID.
+    pub fn info(&self, id: &str) -> Option<ParamInfo> {
+        self.index.get(id).map(|&i| self.params[i].info())
+    }
+
+    /// Returns the metadata for all parameters, in the order they were
+    /// declared in.
+    pub fn infos(&self) -> Vec<ParamInfo> {
+        self.params.iter().map(MappedParam::info).collect()
+    }
+
+    pub fn manifest(&self) -> ParamManifest {
+        let params =
+            self.params.iter().map(|param| (param.id.as_str(), param.ptr, param.group.as_str()));
+        // SAFETY: The pointers stay valid for as long as `_params` is alive.
+        unsafe { ParamManifest::new(params) }
+    }
+
+    /// Returns the parameter's current normalized value, without modulation.
+    pub fn normalized(&self, id: &str) -> Option<f32> {
+        // SAFETY: The pointers stay valid for as long as `_params` is alive.
+        self.get(id).map(|ptr| unsafe { ptr.unmodulated_normalized_value() })
+    }
+
+    /// Returns the parameter's current plain value, without modulation.
+    pub fn plain(&self, id: &str) -> Option<f32> {
+        // SAFETY: The pointers stay valid for as long as `_params` is alive.
+        self.get(id).map(|ptr| unsafe { ptr.preview_plain(ptr.unmodulated_normalized_value()) })
+    }
+
+    /// Formats a normalized value the same way the host's generic editor does.
+    pub fn normalized_to_string(&self, id: &str, normalized: f32) -> Option<String> {
+        // SAFETY: The pointers stay valid for as long as `_params` is alive.
+        self.get(id).map(|ptr| unsafe { ptr.normalized_value_to_string(normalized, true) })
+    }
+
+    /// Parses a string into a normalized value the same way the host does.
+    pub fn string_to_normalized(&self, id: &str, text: &str) -> Option<f32> {
+        // SAFETY: The pointers stay valid for as long as `_params` is alive.
+        self.get(id).and_then(|ptr| unsafe { ptr.string_to_normalized_value(text) })
+    }
+
+    /// Marks the parameter with the given ID as changed.
+    pub fn mark_changed(&self, id: &str) {
+        if let Some(&i) = self.index.get(id) {
+            self.sync_changes.mark(i);
+            self.batch_changes.mark(i);
+        }
+    }
+
+    /// Marks every parameter as changed.
+    pub fn mark_all_changed(&self) {
+        self.sync_changes.mark_all();
+        self.batch_changes.mark_all();
+    }
+
+    /// Sends every parameter to the page again on the next sync, without
+    /// reporting them to `on_params_batch_changed`.
+    pub fn resync(&self) {
+        self.sync_changes.mark_all();
+    }
+
+    /// Returns the current values of all parameters changed since the last
+    /// call, for the parameter sync.
+    pub fn take_updates(&self) -> Vec<ParamUpdate> {
+        self.sync_changes
+            .take()
+            .map(|i| &self.params[i])
+            .map(|param| {
+                // SAFETY: The pointers stay valid for as long as `_params` is alive.
+                unsafe {
+                    let normalized = param.ptr.unmodulated_normalized_value();
+                    ParamUpdate {
+                        id: param.id.clone(),
+                        normalized,
+                        plain: param.ptr.preview_plain(normalized),
+                        text: param.ptr.normalized_value_to_string(normalized, true),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the IDs and normalized values of all parameters changed since
+    /// the last call, for the coalesced change notifications.
+    pub fn take_batch(&self) -> Vec<(String, f32)> {
+        self.batch_changes
+            .take()
+            .map(|i| &self.params[i])
+            // SAFETY: The pointers stay valid for as long as `_params` is alive.
+            .map(|param| (param.id.clone(), unsafe { param.ptr.unmodulated_normalized_value() }))
+            .collect()
+    }
+}
+
+impl MappedParam {
+    fn info(&self) -> ParamInfo {
+        // SAFETY: The pointers stay valid for as long as the `ParamMap` is alive.
+        unsafe {
+            ParamInfo {
+                id: self.id.clone(),
+                name: self.ptr.name().to_string(),
+                unit: self.ptr.unit().to_string(),
+                group: self.group.clone(),
+                step_count: self.ptr.step_count(),
+                default_normalized: self.ptr.default_normalized_value(),
+                is_bypass: self.ptr.flags().contains(ParamFlags::BYPASS),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taken(flags: &ChangeFlags) -> Vec<usize> {
+        flags.take().collect()
+    }
+
+    #[test]
+    fn changes_are_coalesced_until_taken() {
+        let flags = ChangeFlags::new(4);
+        // Everything is reported once when the editor opens.
+        assert_eq!(taken(&flags), [0, 1, 2, 3]);
+        assert_eq!(taken(&flags), [] as [usize; 0]);
+
+        for _ in 0..100 {
+            flags.mark(2);
+            flags.mark(0);
+        }
+        assert_eq!(taken(&flags), [0, 2]);
+        assert_eq!(taken(&flags), [] as [usize; 0]);
+    }
+
+    #[test]
+    fn marking_everything_includes_single_changes() {
+        let flags = ChangeFlags::new(3);
+        taken(&flags);
+
+        flags.mark(1);
+        flags.mark_all();
+        assert_eq!(taken(&flags), [0, 1, 2]);
+        // The single change was cleared along with it.
+        assert_eq!(taken(&flags), [] as [usize; 0]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Messages between the editors of plugin instances in the same process, see
+//! [`WebviewConfig::instance_group`](crate::WebviewConfig::instance_group).
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex, PoisonError,
+};
+
+use crossbeam::channel::{Receiver, Sender, TrySendError};
+use nih_plug::nih_warn;
+use serde::Serialize;
+
+/// The most messages waiting for an editor. Further ones are dropped, so a
+/// stalled editor can't make its peers use up memory.
+const QUEUE_CAPACITY: usize = 256;
+
+/// The longest message in bytes, as messages are meant to be small.
+pub(crate) const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+/// The editors with an open window, of every group. Only touched from the
+/// editors' GUI threads, never from an audio thread.
+static MEMBERS: Mutex<Vec<Member>> = Mutex::new(Vec::new());
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Tells apart the memberships of one instance, as a closed window's handler
+/// may only be dropped after the editor was opened again.
+static NEXT_MEMBERSHIP: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies a plugin instance among its peers, see
+/// [`Context::instance_id`](crate::Context::instance_id). Unique within the
+/// process for as long as the [`WebviewEditor`](crate::WebviewEditor)
+/// exists, but not saved with the plugin's state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(transparent)]
+pub struct InstanceId(u64);
+
+impl InstanceId {
+    pub(crate) fn next() -> InstanceId {
+        InstanceId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for InstanceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A message from a peer, also sent to the page like this.
+#[derive(Serialize)]
+pub(crate) struct PeerMessage {
+    pub from: InstanceId,
+    pub message: String,
+}
+
+struct Member {
+    group: String,
+    id: InstanceId,
+    membership: u64,
+    sender: Sender<PeerMessage>,
+}
+
+fn members() -> std::sync::MutexGuard<'static, Vec<Member>> {
+    MEMBERS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// An open editor's place in its group. Leaves the group when it's dropped
+/// together with the editor window, and the messages still waiting for it
+/// are dropped with it.
+pub(crate) struct Membership {
+    id: InstanceId,
+    membership: u64,
+    group: String,
+    receiver: Receiver<PeerMessage>,
+}
+
+impl Membership {
+    pub fn join(group: &str, id: InstanceId) -> Membership {
+        let (sender, receiver) = crossbeam::channel::bounded(QUEUE_CAPACITY);
+        let membership = NEXT_MEMBERSHIP.fetch_add(1, Ordering::Relaxed);
+        members().push(Member { group: group.to_string(), id, membership, sender });
+        Membership { id, membership, group: group.to_string(), receiver }
+    }
+
+    /// Sends `message` to the other editors of the group, returning how many
+    /// it reached.
+    pub fn broadcast(&self, message: &str) -> usize {
+        let members = members();
+        let peers = members.iter().filter(|member| member.group == self.group);
+        let mut reached = 0;
+        for member in peers.filter(|member| member.id != self.id) {
+            let message = PeerMessage { from: self.id, message: message.to_string() };
+            match member.sender.try_send(message) {
+                Ok(()) => reached += 1,
+                Err(TrySendError::Full(_)) => {
+                    nih_warn!("Dropping a message for the stalled editor {}.", member.id);
+                }
+                // Left while the lock was not held.
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+        reached
+    }
+
+    pub fn try_recv(&self) -> Option<PeerMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        members().retain(|member| member.membership != self.membership);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reopened_windows_keep_their_membership() {
+        let group = "reopened_windows_keep_their_membership";
+        let id = InstanceId::next();
+        let closed = Membership::join(group, id);
+        let reopened = Membership::join(group, id);
+        // The closed window's handler is dropped after the editor reopened.
+        drop(closed);
+
+        let peer = Membership::join(group, InstanceId::next());
+        assert_eq!(peer.broadcast("hello"), 1);
+        let message = reopened.try_recv().unwrap();
+        assert_eq!((message.from, message.message.as_str()), (peer.id, "hello"));
+    }
+
+    #[test]
+    fn messages_only_reach_the_group() {
+        let (sender, other) = (InstanceId::next(), InstanceId::next());
+        let sender = Membership::join("messages_only_reach_the_group", sender);
+        let other = Membership::join("messages_only_reach_the_group (other)", other);
+        assert_eq!(sender.broadcast("hello"), 0);
+        assert!(other.try_recv().is_none());
+        assert!(sender.try_recv().is_none());
+    }
+}
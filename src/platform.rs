@@ -0,0 +1,803 @@
+//! Small platform specific helpers for things neither baseview nor wry expose.
+
+use baseview::Window;
+use keyboard_types::KeyboardEvent;
+use raw_window_handle::HasRawWindowHandle;
+
+/// Re-posts a key event the webview did not handle to the host's window, so
+/// that e.g. transport shortcuts keep working while the editor has focus.
+/// `key_code` is the event's legacy DOM `keyCode`, which matches the Windows
+/// virtual key codes. Returns `false` if this is not supported on the current
+/// platform.
+pub fn forward_key_to_host(window: &Window, event: &KeyboardEvent, key_code: u32) -> bool {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            let (message, lparam) = match event.state {
+                keyboard_types::KeyState::Down => (win32::WM_KEYDOWN, 1),
+                // Repeat count 1, previous key state and transition state set.
+                keyboard_types::KeyState::Up => (win32::WM_KEYUP, 0xC000_0001u32 as isize),
+            };
+
+            unsafe {
+                let parent = win32::GetParent(handle.hwnd);
+                !parent.is_null()
+                    && win32::PostMessageW(parent, message, key_code as usize, lparam) != 0
+            }
+        }
+        _ => {
+            let _ = (event, key_code);
+            false
+        }
+    }
+}
+
+/// The scale factor of the monitor the window is on, or `None` if it can't be
+/// queried on the current platform.
+pub fn scale_factor(window: &Window) -> Option<f64> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            let get_dpi_for_window = win32::get_dpi_for_window()?;
+            let dpi = unsafe { get_dpi_for_window(handle.hwnd) };
+            (dpi != 0).then(|| dpi as f64 / 96.0)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the window can currently be seen, i.e. it's shown and its
+/// top-level window isn't minimized. `None` if this can't be queried on the
+/// current platform.
+pub fn window_visible(window: &Window) -> Option<bool> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
+            // Also checks the visibility of all parent windows.
+            let shown = win32::IsWindowVisible(handle.hwnd) != 0;
+            let root = win32::GetAncestor(handle.hwnd, win32::GA_ROOT);
+            Some(shown && (root.is_null() || win32::IsIconic(root) == 0))
+        },
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            Some(appkit::window_visible(handle.ns_view.cast()))
+        },
+        _ => None,
+    }
+}
+
+/// Whether the WebView2 runtime is missing, checked the way Microsoft
+/// recommends in its distribution guide. Always `false` on other platforms,
+/// where the webview is part of the system.
+pub fn webview_runtime_missing() -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        // Set by plugins shipping a fixed version of the runtime.
+        if std::env::var_os("WEBVIEW2_BROWSER_EXECUTABLE_FOLDER").is_some() {
+            return false;
+        }
+        !win32::webview2_installed()
+    }
+    #[cfg(not(target_os = "windows"))]
+    false
+}
+
+/// Shows `text` centered in the window with a native label, for when there
+/// is no webview to show anything with. The label lets mouse clicks through to
+/// the window. Returns `false` if this is not supported on the current
+/// platform.
+pub fn show_native_message(window: &Window, text: &str, (width, height): (u32, u32)) -> bool {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            let class: Vec<u16> = "STATIC\0".encode_utf16().collect();
+            let text: Vec<u16> = text.encode_utf16().chain([0]).collect();
+            let label = unsafe {
+                win32::CreateWindowExW(
+                    0,
+                    class.as_ptr(),
+                    text.as_ptr(),
+                    win32::WS_CHILD | win32::WS_VISIBLE | win32::SS_CENTER,
+                    0,
+                    (height / 2).saturating_sub(20) as i32,
+                    width as i32,
+                    height.min(40) as i32,
+                    handle.hwnd,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                )
+            };
+            // The label is destroyed together with the window.
+            !label.is_null()
+        }
+        _ => {
+            let _ = (text, width, height);
+            false
+        }
+    }
+}
+
+/// Whether the editor can be embedded into `parent`. On Linux, baseview and
+/// wry only support X11 windows, so this is `false` for e.g. Wayland surfaces.
+/// Under XWayland, hosts pass X11 windows and are supported. An X11 window ID
+/// of 0 is never a valid parent.
+pub fn can_embed(parent: &impl HasRawWindowHandle) -> bool {
+    match parent.raw_window_handle() {
+        #[cfg(target_os = "linux")]
+        raw_window_handle::RawWindowHandle::Xlib(handle) => handle.window != 0,
+        #[cfg(target_os = "linux")]
+        raw_window_handle::RawWindowHandle::Xcb(handle) => handle.window != 0,
+        #[cfg(target_os = "linux")]
+        _ => false,
+        #[cfg(not(target_os = "linux"))]
+        _ => true,
+    }
+}
+
+/// Sets the title of the top-level window containing the editor. Returns
+/// `false` if this is not supported on the current platform.
+pub fn set_window_title(window: &Window, title: &str) -> bool {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            let title: Vec<u16> = title.encode_utf16().chain([0]).collect();
+            unsafe {
+                let root = win32::GetAncestor(handle.hwnd, win32::GA_ROOT);
+                !root.is_null() && win32::SetWindowTextW(root, title.as_ptr()) != 0
+            }
+        }
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            appkit::set_window_title(handle.ns_view.cast(), title)
+        },
+        _ => {
+            let _ = title;
+            false
+        }
+    }
+}
+
+/// Gives the standalone application a menu bar with a "Quit" item, which
+/// nothing else sets up on macOS. Does nothing if the application already
+/// has a menu bar. Returns `false` on other platforms, where applications
+/// don't have one.
+pub fn install_app_menu(title: &str) -> bool {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        appkit::install_app_menu(title)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = title;
+        false
+    }
+}
+
+/// The position of the top-level window containing the editor, in the
+/// platform's screen coordinates: physical pixels from the top left on
+/// Windows, points from the bottom left on macOS. `None` if this can't be
+/// queried on the current platform.
+pub fn window_position(window: &Window) -> Option<(f64, f64)> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
+            let root = win32::GetAncestor(handle.hwnd, win32::GA_ROOT);
+            let mut rect = win32::Rect { left: 0, top: 0, right: 0, bottom: 0 };
+            (!root.is_null() && win32::GetWindowRect(root, &mut rect) != 0)
+                .then(|| (rect.left as f64, rect.top as f64))
+        },
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            appkit::window_origin(handle.ns_view.cast())
+        },
+        _ => None,
+    }
+}
+
+/// Moves the top-level window containing the editor to a position returned by
+/// [`window_position`]. Positions which are not on any monitor anymore are
+/// ignored on Windows, macOS moves the window back on screen by itself.
+/// Returns `false` if the window was not moved.
+pub fn set_window_position(window: &Window, (x, y): (f64, f64)) -> bool {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
+            let (x, y) = (x.round() as i32, y.round() as i32);
+            let root = win32::GetAncestor(handle.hwnd, win32::GA_ROOT);
+            !root.is_null()
+                && !win32::MonitorFromPoint(win32::Point { x, y }, win32::MONITOR_DEFAULTTONULL)
+                    .is_null()
+                && win32::SetWindowPos(
+                    root,
+                    std::ptr::null_mut(),
+                    x,
+                    y,
+                    0,
+                    0,
+                    win32::SWP_NOSIZE | win32::SWP_NOZORDER | win32::SWP_NOACTIVATE,
+                ) != 0
+        },
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            appkit::set_window_origin(handle.ns_view.cast(), x, y)
+        },
+        _ => {
+            let _ = (x, y);
+            false
+        }
+    }
+}
+
+/// Moves the keyboard focus from the webview to the editor's own window, so
+/// that key events reach the window and the host instead. Returns `false` if
+/// this is not supported on the current platform.
+pub fn focus_window(window: &Window) -> bool {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
+            !win32::SetFocus(handle.hwnd).is_null()
+        },
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            appkit::make_first_responder(handle.ns_view.cast())
+        },
+        _ => false,
+    }
+}
+
+/// Moves the keyboard focus to the host's window the editor is embedded in.
+/// Returns `false` if this is not supported on the current platform.
+pub fn focus_host(window: &Window) -> bool {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
+            let parent = win32::GetParent(handle.hwnd);
+            !parent.is_null() && !win32::SetFocus(parent).is_null()
+        },
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            appkit::focus_superview(handle.ns_view.cast())
+        },
+        _ => false,
+    }
+}
+
+/// Hides or shows the mouse cursor. Calls must alternate between hiding and
+/// showing it, as some platforms count them. Returns `false` if this is not
+/// supported on the current platform.
+pub fn set_cursor_visible(visible: bool) -> bool {
+    #[cfg(target_os = "windows")]
+    unsafe {
+        win32::ShowCursor(visible as i32);
+        true
+    }
+    #[cfg(target_os = "macos")]
+    unsafe {
+        let display = appkit::CGMainDisplayID();
+        let result = if visible {
+            appkit::CGDisplayShowCursor(display)
+        } else {
+            appkit::CGDisplayHideCursor(display)
+        };
+        result == 0
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let _ = visible;
+        false
+    }
+}
+
+/// Moves the mouse cursor to a position in logical pixels relative to the
+/// window's top left corner. Returns `false` if this is not supported on the
+/// current platform.
+pub fn set_cursor_position(window: &Window, (x, y): (f64, f64), scale_factor: f64) -> bool {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            let mut point = win32::Point {
+                x: (x * scale_factor).round() as i32,
+                y: (y * scale_factor).round() as i32,
+            };
+            unsafe {
+                win32::ClientToScreen(handle.hwnd, &mut point) != 0
+                    && win32::SetCursorPos(point.x, point.y) != 0
+            }
+        }
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            // Points are already logical on macOS.
+            let _ = scale_factor;
+            appkit::set_cursor_position(handle.ns_view.cast(), x, y)
+        },
+        _ => {
+            let _ = (x, y, scale_factor);
+            false
+        }
+    }
+}
+
+/// The mouse cursor's position in logical pixels relative to the window's top
+/// left corner, which may be outside of the window, and whether the primary
+/// button is held. `None` if this can't be queried on the current platform.
+pub fn pointer_state(window: &Window, scale_factor: f64) -> Option<((f64, f64), bool)> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
+            let mut point = win32::Point { x: 0, y: 0 };
+            if win32::GetCursorPos(&mut point) == 0
+                || win32::ScreenToClient(handle.hwnd, &mut point) == 0
+            {
+                return None;
+            }
+            // The key state is of the physical button, which may be swapped.
+            let button = match win32::GetSystemMetrics(win32::SM_SWAPBUTTON) {
+                0 => win32::VK_LBUTTON,
+                _ => win32::VK_RBUTTON,
+            };
+            let down = win32::GetAsyncKeyState(button) as u16 & 0x8000 != 0;
+            Some(((point.x as f64 / scale_factor, point.y as f64 / scale_factor), down))
+        },
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => unsafe {
+            // Points are already logical on macOS.
+            let _ = scale_factor;
+            appkit::pointer_state(handle.ns_view.cast())
+        },
+        _ => {
+            let _ = scale_factor;
+            None
+        }
+    }
+}
+
+/// Wheel messages forwarded by [`forward_wheel`], until this is dropped.
+#[cfg(all(target_os = "windows", feature = "wheel-forwarding"))]
+pub use win32::WheelForwarding;
+/// Nothing is forwarded on other platforms, or without the
+/// `wheel-forwarding` feature.
+#[cfg(not(all(target_os = "windows", feature = "wheel-forwarding")))]
+pub type WheelForwarding = std::convert::Infallible;
+
+/// Passes mouse wheel messages the host's windows receive while the cursor is
+/// over the editor on to the window under the cursor, see
+/// [`WheelPolicy::ForceToWebview`](crate::WheelPolicy::ForceToWebview).
+/// Returns `None` if this is not supported on the current platform, or
+/// without the `wheel-forwarding` feature. On macOS scroll events always go
+/// to the view under the cursor, so there is nothing to forward.
+pub fn forward_wheel(window: &Window) -> Option<WheelForwarding> {
+    match window.raw_window_handle() {
+        #[cfg(all(target_os = "windows", feature = "wheel-forwarding"))]
+        raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
+            win32::WheelForwarding::new(handle.hwnd)
+        },
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod appkit {
+    use std::ffi::CString;
+
+    use objc::{
+        class, msg_send,
+        runtime::{Object, BOOL, NO},
+        sel, sel_impl,
+    };
+
+    type Id = *mut Object;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct CGSize {
+        width: f64,
+        height: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct CGRect {
+        origin: CGPoint,
+        size: CGSize,
+    }
+
+    #[link(name = "Foundation", kind = "framework")]
+    extern "C" {}
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        pub fn CGMainDisplayID() -> u32;
+        pub fn CGDisplayHideCursor(display: u32) -> i32;
+        pub fn CGDisplayShowCursor(display: u32) -> i32;
+        fn CGDisplayBounds(display: u32) -> CGRect;
+        fn CGWarpMouseCursorPosition(point: CGPoint) -> i32;
+        fn CGAssociateMouseAndMouseCursorPosition(connected: u32) -> i32;
+    }
+
+    /// The window holding `view`, if it's in one.
+    unsafe fn window_of(view: Id) -> Option<Id> {
+        let window: Id = msg_send![view, window];
+        (!window.is_null()).then_some(window)
+    }
+
+    /// Whether `view` is in a window which is shown and not minimized.
+    pub unsafe fn window_visible(view: Id) -> bool {
+        let Some(window) = window_of(view) else { return false };
+        let visible: BOOL = msg_send![window, isVisible];
+        let minimized: BOOL = msg_send![window, isMiniaturized];
+        visible != NO && minimized == NO
+    }
+
+    /// Makes `view` the first responder of its window.
+    pub unsafe fn make_first_responder(view: Id) -> bool {
+        let Some(window) = window_of(view) else { return false };
+        let accepted: BOOL = msg_send![window, makeFirstResponder: view];
+        accepted != NO
+    }
+
+    /// Makes the view `view` is embedded in the first responder.
+    pub unsafe fn focus_superview(view: Id) -> bool {
+        let superview: Id = msg_send![view, superview];
+        !superview.is_null() && make_first_responder(superview)
+    }
+
+    /// The bounds of `view` in AppKit's screen coordinates.
+    unsafe fn frame_on_screen(view: Id) -> Option<CGRect> {
+        let window = window_of(view)?;
+        // `msg_send!` picks `objc_msgSend_stret` for structs this large on
+        // x86_64.
+        let bounds: CGRect = msg_send![view, bounds];
+        let nil: Id = std::ptr::null_mut();
+        let in_window: CGRect = msg_send![view, convertRect: bounds toView: nil];
+        let on_screen: CGRect = msg_send![window, convertRectToScreen: in_window];
+        Some(on_screen)
+    }
+
+    /// Moves the cursor to `(x, y)` points from the top left corner of `view`.
+    pub unsafe fn set_cursor_position(view: Id, x: f64, y: f64) -> bool {
+        let Some(on_screen) = frame_on_screen(view) else { return false };
+
+        // AppKit's screen coordinates start at the bottom of the main
+        // display, Core Graphics' at its top.
+        let main_height = CGDisplayBounds(CGMainDisplayID()).size.height;
+        let point = CGPoint {
+            x: on_screen.origin.x + x,
+            y: main_height - (on_screen.origin.y + on_screen.size.height - y),
+        };
+        if CGWarpMouseCursorPosition(point) != 0 {
+            return false;
+        }
+        // Warping otherwise suppresses mouse movement for a moment.
+        CGAssociateMouseAndMouseCursorPosition(1);
+        true
+    }
+
+    /// The cursor's position in points from the top left corner of `view`,
+    /// and whether the left mouse button is held.
+    pub unsafe fn pointer_state(view: Id) -> Option<((f64, f64), bool)> {
+        let on_screen = frame_on_screen(view)?;
+        let location: CGPoint = msg_send![class!(NSEvent), mouseLocation];
+        let buttons: usize = msg_send![class!(NSEvent), pressedMouseButtons];
+        // Both are in AppKit's screen coordinates, which grow upwards.
+        let x = location.x - on_screen.origin.x;
+        let y = on_screen.origin.y + on_screen.size.height - location.y;
+        Some(((x, y), buttons & 1 != 0))
+    }
+
+    /// The origin of the frame of the `NSWindow` containing `view`.
+    pub unsafe fn window_origin(view: Id) -> Option<(f64, f64)> {
+        let window = window_of(view)?;
+        let frame: CGRect = msg_send![window, frame];
+        Some((frame.origin.x, frame.origin.y))
+    }
+
+    /// Moves the `NSWindow` containing `view` so that its frame starts at
+    /// `(x, y)`.
+    pub unsafe fn set_window_origin(view: Id, x: f64, y: f64) -> bool {
+        let Some(window) = window_of(view) else { return false };
+        let () = msg_send![window, setFrameOrigin: CGPoint { x, y }];
+        true
+    }
+
+    /// Sets the title of the `NSWindow` containing `view`.
+    pub unsafe fn set_window_title(view: Id, title: &str) -> bool {
+        let (Some(window), Some(title)) = (window_of(view), ns_string(title)) else {
+            return false;
+        };
+        let () = msg_send![window, setTitle: title];
+        true
+    }
+
+    /// An autoreleased `NSString` holding `text`, or `None` if `text` holds
+    /// a null character.
+    unsafe fn ns_string(text: &str) -> Option<Id> {
+        let text = CString::new(text).ok()?;
+        let string: Id = msg_send![class!(NSString), stringWithUTF8String: text.as_ptr()];
+        (!string.is_null()).then_some(string)
+    }
+
+    /// Sets a main menu holding the application menu with "Quit <title>",
+    /// unless the application has one.
+    pub unsafe fn install_app_menu(title: &str) -> bool {
+        let (Some(quit_title), Some(q)) = (ns_string(&format!("Quit {title}")), ns_string("q"))
+        else {
+            return false;
+        };
+        let app: Id = msg_send![class!(NSApplication), sharedApplication];
+        if app.is_null() {
+            return false;
+        }
+        let main_menu: Id = msg_send![app, mainMenu];
+        if !main_menu.is_null() {
+            return false;
+        }
+
+        let quit: Id = msg_send![class!(NSMenuItem), alloc];
+        let quit: Id = msg_send![
+            quit,
+            initWithTitle: quit_title
+            action: sel!(terminate:)
+            keyEquivalent: q
+        ];
+        let app_menu: Id = msg_send![class!(NSMenu), new];
+        let () = msg_send![app_menu, addItem: quit];
+        let app_item: Id = msg_send![class!(NSMenuItem), new];
+        let () = msg_send![app_item, setSubmenu: app_menu];
+        let menu_bar: Id = msg_send![class!(NSMenu), new];
+        let () = msg_send![menu_bar, addItem: app_item];
+        let () = msg_send![app, setMainMenu: menu_bar];
+        true
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use std::{ffi::c_void, sync::OnceLock};
+
+    pub type Hwnd = *mut c_void;
+
+    #[repr(C)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[repr(C)]
+    pub struct Rect {
+        pub left: i32,
+        pub top: i32,
+        pub right: i32,
+        pub bottom: i32,
+    }
+    pub type GetDpiForWindow = unsafe extern "system" fn(Hwnd) -> u32;
+
+    pub const WM_KEYDOWN: u32 = 0x0100;
+    pub const WM_KEYUP: u32 = 0x0101;
+    pub const WS_CHILD: u32 = 0x4000_0000;
+    pub const WS_VISIBLE: u32 = 0x1000_0000;
+    pub const SS_CENTER: u32 = 0x0000_0001;
+    pub const GA_ROOT: u32 = 2;
+    pub const SWP_NOSIZE: u32 = 0x0001;
+    pub const SWP_NOZORDER: u32 = 0x0004;
+    pub const SWP_NOACTIVATE: u32 = 0x0010;
+    pub const MONITOR_DEFAULTTONULL: u32 = 0;
+    pub const SM_SWAPBUTTON: i32 = 23;
+    pub const VK_LBUTTON: i32 = 0x01;
+    pub const VK_RBUTTON: i32 = 0x02;
+
+    const HKEY_CURRENT_USER: isize = 0x8000_0001u32 as i32 as isize;
+    const HKEY_LOCAL_MACHINE: isize = 0x8000_0002u32 as i32 as isize;
+    const RRF_RT_REG_SZ: u32 = 0x0000_0002;
+
+    #[link(name = "user32")]
+    extern "system" {
+        pub fn GetParent(hwnd: Hwnd) -> Hwnd;
+        pub fn PostMessageW(hwnd: Hwnd, msg: u32, wparam: usize, lparam: isize) -> i32;
+        pub fn GetAncestor(hwnd: Hwnd, flags: u32) -> Hwnd;
+        pub fn SetWindowTextW(hwnd: Hwnd, text: *const u16) -> i32;
+        pub fn ShowCursor(show: i32) -> i32;
+        pub fn SetFocus(hwnd: Hwnd) -> Hwnd;
+        pub fn IsWindowVisible(hwnd: Hwnd) -> i32;
+        pub fn IsIconic(hwnd: Hwnd) -> i32;
+        pub fn ClientToScreen(hwnd: Hwnd, point: *mut Point) -> i32;
+        pub fn SetCursorPos(x: i32, y: i32) -> i32;
+        pub fn GetCursorPos(point: *mut Point) -> i32;
+        pub fn ScreenToClient(hwnd: Hwnd, point: *mut Point) -> i32;
+        pub fn GetSystemMetrics(index: i32) -> i32;
+        pub fn GetAsyncKeyState(key: i32) -> i16;
+        pub fn GetWindowRect(hwnd: Hwnd, rect: *mut Rect) -> i32;
+        pub fn MonitorFromPoint(point: Point, flags: u32) -> *mut c_void;
+        pub fn SetWindowPos(
+            hwnd: Hwnd,
+            insert_after: Hwnd,
+            x: i32,
+            y: i32,
+            width: i32,
+            height: i32,
+            flags: u32,
+        ) -> i32;
+        #[allow(clippy::too_many_arguments)]
+        pub fn CreateWindowExW(
+            ex_style: u32,
+            class_name: *const u16,
+            window_name: *const u16,
+            style: u32,
+            x: i32,
+            y: i32,
+            width: i32,
+            height: i32,
+            parent: Hwnd,
+            menu: *mut c_void,
+            instance: *mut c_void,
+            param: *mut c_void,
+        ) -> Hwnd;
+    }
+
+    #[cfg(feature = "wheel-forwarding")]
+    pub use wheel::WheelForwarding;
+
+    #[cfg(feature = "wheel-forwarding")]
+    mod wheel {
+        use super::{GetAncestor, GetParent, Hwnd, Point, PostMessageW, GA_ROOT};
+
+        const WM_MOUSEWHEEL: u32 = 0x020A;
+        const WM_MOUSEHWHEEL: u32 = 0x020E;
+
+        #[link(name = "user32")]
+        extern "system" {
+            fn WindowFromPoint(point: Point) -> Hwnd;
+            fn IsChild(parent: Hwnd, hwnd: Hwnd) -> i32;
+        }
+
+        type SubclassProc =
+            unsafe extern "system" fn(Hwnd, u32, usize, isize, usize, usize) -> isize;
+
+        #[link(name = "comctl32")]
+        extern "system" {
+            fn SetWindowSubclass(hwnd: Hwnd, proc: SubclassProc, id: usize, data: usize) -> i32;
+            fn RemoveWindowSubclass(hwnd: Hwnd, proc: SubclassProc, id: usize) -> i32;
+            fn DefSubclassProc(hwnd: Hwnd, msg: u32, wparam: usize, lparam: isize) -> isize;
+        }
+
+        /// Subclasses of the editor's parent and top-level windows, which
+        /// are removed on drop. The top-level window gets the wheel messages
+        /// which the host's windows pass on to their parents, while some
+        /// hosts handle them in the editor's parent window itself.
+        ///
+        /// The editor's handle is the subclass ID, so editors docked in the
+        /// same host window each install and remove their own subclass.
+        pub struct WheelForwarding {
+            editor: Hwnd,
+            windows: Vec<Hwnd>,
+        }
+
+        impl WheelForwarding {
+            pub unsafe fn new(editor: Hwnd) -> Option<WheelForwarding> {
+                let mut windows = vec![GetParent(editor), GetAncestor(editor, GA_ROOT)];
+                windows.dedup();
+                windows.retain(|&window| {
+                    !window.is_null()
+                        && window != editor
+                        && SetWindowSubclass(
+                            window,
+                            forward_wheel,
+                            editor as usize,
+                            editor as usize,
+                        ) != 0
+                });
+                (!windows.is_empty()).then_some(WheelForwarding { editor, windows })
+            }
+        }
+
+        impl Drop for WheelForwarding {
+            fn drop(&mut self) {
+                for &window in &self.windows {
+                    unsafe { RemoveWindowSubclass(window, forward_wheel, self.editor as usize) };
+                }
+            }
+        }
+
+        /// Posts wheel messages to the window under the cursor if that's
+        /// within the editor, usually WebView2's render widget.
+        unsafe extern "system" fn forward_wheel(
+            hwnd: Hwnd,
+            msg: u32,
+            wparam: usize,
+            lparam: isize,
+            _id: usize,
+            editor: usize,
+        ) -> isize {
+            if msg == WM_MOUSEWHEEL || msg == WM_MOUSEHWHEEL {
+                // The cursor's position in screen coordinates.
+                let point = Point {
+                    x: lparam as u16 as i16 as i32,
+                    y: (lparam >> 16) as u16 as i16 as i32,
+                };
+                let target = WindowFromPoint(point);
+                if !target.is_null() && IsChild(editor as Hwnd, target) != 0 {
+                    PostMessageW(target, msg, wparam, lparam);
+                    return 0;
+                }
+            }
+            DefSubclassProc(hwnd, msg, wparam, lparam)
+        }
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn RegGetValueW(
+            key: isize,
+            sub_key: *const u16,
+            value: *const u16,
+            flags: u32,
+            value_type: *mut u32,
+            data: *mut c_void,
+            data_len: *mut u32,
+        ) -> i32;
+    }
+
+    /// Whether the WebView2 runtime's version is registered for the machine
+    /// or the current user.
+    pub fn webview2_installed() -> bool {
+        const CLIENT: &str = r"Microsoft\EdgeUpdate\Clients\{F3017226-FE2A-4295-8BDF-00C3A9A7E4C5}";
+        [
+            (HKEY_LOCAL_MACHINE, format!(r"SOFTWARE\WOW6432Node\{CLIENT}")),
+            (HKEY_LOCAL_MACHINE, format!(r"SOFTWARE\{CLIENT}")),
+            (HKEY_CURRENT_USER, format!(r"Software\{CLIENT}")),
+        ]
+        .iter()
+        .any(|(key, sub_key)| {
+            let sub_key: Vec<u16> = sub_key.encode_utf16().chain([0]).collect();
+            let value: Vec<u16> = "pv\0".encode_utf16().collect();
+            let mut data = [0u16; 64];
+            let mut data_len = std::mem::size_of_val(&data) as u32;
+            let result = unsafe {
+                RegGetValueW(
+                    *key,
+                    sub_key.as_ptr(),
+                    value.as_ptr(),
+                    RRF_RT_REG_SZ,
+                    std::ptr::null_mut(),
+                    data.as_mut_ptr().cast(),
+                    &mut data_len,
+                )
+            };
+            if result != 0 {
+                return false;
+            }
+            let len = (data_len as usize / 2).min(data.len());
+            let version = String::from_utf16_lossy(&data[..len]);
+            let version = version.trim_end_matches('\0');
+            !version.is_empty() && version != "0.0.0.0"
+        })
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+        fn GetProcAddress(module: *mut c_void, name: *const u8) -> *const c_void;
+    }
+
+    /// `GetDpiForWindow` requires Windows 10 1607, so it's looked up at
+    /// runtime instead of being linked.
+    pub fn get_dpi_for_window() -> Option<GetDpiForWindow> {
+        static ADDRESS: OnceLock<usize> = OnceLock::new();
+        let address = *ADDRESS.get_or_init(|| unsafe {
+            let user32: Vec<u16> = "user32.dll\0".encode_utf16().collect();
+            let module = GetModuleHandleW(user32.as_ptr());
+            if module.is_null() {
+                return 0;
+            }
+            GetProcAddress(module, b"GetDpiForWindow\0".as_ptr()) as usize
+        });
+        (address != 0).then(|| unsafe { std::mem::transmute::<usize, GetDpiForWindow>(address) })
+    }
+}
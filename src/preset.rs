@@ -0,0 +1,51 @@
+//! Exporting and importing the plugin's state, e.g. for preset files. See
+//! [`Context::export_state`](crate::Context::export_state).
+
+use nih_plug::prelude::{GuiContext, PluginState};
+
+/// Returned when a plugin state can't be imported or a preset file can't be
+/// read or written.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum StateError {
+    /// The data is not a state exported by
+    /// [`Context::export_state`](crate::Context::export_state).
+    Corrupt(serde_json::Error),
+    /// The preset file could not be read or written.
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateError::Corrupt(err) => write!(f, "not a valid plugin state: {err}"),
+            StateError::Io(err) => write!(f, "could not access the preset file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StateError::Corrupt(err) => Some(err),
+            StateError::Io(err) => Some(err),
+        }
+    }
+}
+
+/// The extension used for preset files saved through `window.plugin.presets`.
+#[cfg(feature = "file-dialog")]
+pub(crate) const PRESET_EXTENSION: &str = "json";
+
+pub(crate) fn export(context: &dyn GuiContext) -> Vec<u8> {
+    serde_json::to_vec_pretty(&context.get_state()).expect("Can't convert JSON to string.")
+}
+
+/// Restores a state exported with [`export`]. States saved by older versions
+/// of the plugin are migrated by nih-plug the same way as when the host loads
+/// them.
+pub(crate) fn import(context: &dyn GuiContext, bytes: &[u8]) -> Result<(), StateError> {
+    let state: PluginState = serde_json::from_slice(bytes).map_err(StateError::Corrupt)?;
+    context.set_state(state);
+    Ok(())
+}
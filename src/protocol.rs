@@ -0,0 +1,245 @@
+//! Custom protocols answered on worker threads, see
+//! [`WebviewEditor::with_async_protocol`](crate::WebviewEditor::with_async_protocol).
+
+use std::{
+    borrow::Cow,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crossbeam::channel::Sender;
+use nih_plug::{nih_error, nih_trace};
+use wry::{
+    http::{Request, Response},
+    RequestAsyncResponder,
+};
+
+/// The work of answering a request to an async protocol, run on a worker
+/// thread. Returned by the protocol's handler.
+pub type ProtocolJob = Box<dyn FnOnce() -> Response<Cow<'static, [u8]>> + Send>;
+
+/// A handler added with
+/// [`WebviewEditor::with_async_protocol`](crate::WebviewEditor::with_async_protocol).
+pub(crate) type AsyncProtocolHandler = Arc<dyn Fn(Request<Vec<u8>>) -> ProtocolJob + Send + Sync>;
+
+/// The number of threads answering requests to the async protocols of an
+/// editor window.
+const WORKER_THREADS: usize = 4;
+
+/// Returns the URL under which the webview requests `path` from the custom
+/// protocol `protocol`, e.g. to point an `<audio>` element at it from a page
+/// which is not itself served over that protocol.
+pub fn custom_protocol_url(protocol: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    // WebView2 serves custom protocols over `http://<protocol>.localhost`.
+    if cfg!(target_os = "windows") {
+        format!("http://{protocol}.localhost/{path}")
+    } else {
+        format!("{protocol}://localhost/{path}")
+    }
+}
+
+/// Returns a `Content-Security-Policy` for pages served from a
+/// [`WebviewSource::DirPath`](crate::WebviewSource::DirPath) or a custom
+/// protocol, which still allows everything `window.plugin` needs. The page
+/// may load scripts, styles, images, media and fonts from its own origin and
+/// from the custom `protocols` given, images and media from the
+/// [`Context::publish_asset`](crate::Context::publish_asset) assets, and
+/// nothing from the network. Inline scripts are blocked, inline styles are
+/// allowed, as the splash screen's HTML may use them. See
+/// [`WebviewConfig::content_security_policy`](crate::WebviewConfig::content_security_policy).
+pub fn content_security_policy(protocols: &[&str]) -> String {
+    let origin = |protocol: &str| {
+        let url = custom_protocol_url(protocol, "");
+        url.trim_end_matches('/').to_string()
+    };
+    let sources: String =
+        protocols.iter().map(|protocol| format!(" {}", origin(protocol))).collect();
+    // `window.plugin.visualization` fetches from its own protocol, and
+    // `Context::publish_asset` serves from another.
+    let visualizations = origin(crate::VISUALIZATION_PROTOCOL);
+    let assets = origin(crate::ASSET_PROTOCOL);
+    format!(
+        "default-src 'self'{sources}; script-src 'self'{sources}; \
+         style-src 'self' 'unsafe-inline'{sources}; \
+         img-src 'self' data: blob: {assets}{sources}; media-src 'self' blob: {assets}{sources}; \
+         font-src 'self' data:{sources}; connect-src 'self' {visualizations} {assets}{sources}; \
+         object-src 'none'; base-uri 'self'"
+    )
+}
+
+struct Task {
+    job: ProtocolJob,
+    responder: RequestAsyncResponder,
+    /// The page the request was made by, see [`ProtocolPool::cancel`].
+    page: u64,
+}
+
+/// The worker threads of an editor window's async protocols. They exit once
+/// the pool and the webview holding it were dropped.
+pub(crate) struct ProtocolPool {
+    tasks: Sender<Task>,
+    shared: Arc<Shared>,
+    max_in_flight: usize,
+}
+
+struct Shared {
+    /// Incremented whenever the requests made so far become pointless.
+    page: AtomicU64,
+    /// The requests which are queued or being answered.
+    in_flight: AtomicUsize,
+}
+
+impl ProtocolPool {
+    pub fn new(max_in_flight: usize) -> ProtocolPool {
+        let (tasks, receiver) = crossbeam::channel::unbounded::<Task>();
+        let shared = Arc::new(Shared { page: AtomicU64::new(0), in_flight: AtomicUsize::new(0) });
+        for _ in 0..WORKER_THREADS {
+            let receiver = receiver.clone();
+            let shared = shared.clone();
+            let spawned = std::thread::Builder::new()
+                .name("nih-plug-webview protocol".to_string())
+                .spawn(move || {
+                    while let Ok(task) = receiver.recv() {
+                        let response = if task.page == shared.page.load(Ordering::SeqCst) {
+                            std::panic::catch_unwind(AssertUnwindSafe(task.job)).unwrap_or_else(
+                                |_| {
+                                    nih_error!("An async protocol handler panicked.");
+                                    status(500)
+                                },
+                            )
+                        } else {
+                            nih_trace!("Cancelling a request made by a page which is gone.");
+                            status(503)
+                        };
+                        task.responder.respond(response);
+                        shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    }
+                });
+            if let Err(err) = spawned {
+                nih_error!("Could not start a thread for the async protocols: {err}");
+            }
+        }
+
+        ProtocolPool { tasks, shared, max_in_flight }
+    }
+
+    /// Answers `request` with the job returned by `handler`, or with `503
+    /// Service Unavailable` if
+    /// [`WebviewConfig::max_async_requests`](crate::WebviewConfig::max_async_requests)
+    /// are already in flight.
+    pub fn submit(
+        &self,
+        handler: &AsyncProtocolHandler,
+        request: Request<Vec<u8>>,
+        responder: RequestAsyncResponder,
+    ) {
+        if self.shared.in_flight.fetch_add(1, Ordering::SeqCst) >= self.max_in_flight {
+            self.shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+            nih_trace!("Rejecting a request to an async protocol, too many are in flight.");
+            responder.respond(status(503));
+            return;
+        }
+
+        // This is called by the webview, which a panic must not unwind into.
+        let job = match std::panic::catch_unwind(AssertUnwindSafe(|| handler(request))) {
+            Ok(job) => job,
+            Err(_) => {
+                nih_error!("An async protocol handler panicked.");
+                self.shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+                responder.respond(status(500));
+                return;
+            }
+        };
+
+        let task = Task { job, responder, page: self.shared.page.load(Ordering::SeqCst) };
+        if let Err(err) = self.tasks.send(task) {
+            // Only possible if no worker thread could be started.
+            self.shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+            err.0.responder.respond(status(503));
+        }
+    }
+
+    /// Answers the queued requests with `503 Service Unavailable` instead of
+    /// running their jobs, e.g. because the page made them navigated away.
+    /// Jobs which are already running still finish.
+    pub fn cancel(&self) {
+        self.shared.page.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Drop for ProtocolPool {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+fn status(status: u16) -> Response<Cow<'static, [u8]>> {
+    Response::builder().status(status).body(Cow::Borrowed(&[][..])).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// The sources of each directive.
+    fn directives(policy: &str) -> HashMap<&str, Vec<&str>> {
+        policy
+            .split(';')
+            .map(|directive| {
+                let mut parts = directive.split_whitespace();
+                (parts.next().unwrap(), parts.collect())
+            })
+            .collect()
+    }
+
+    fn origin(protocol: &str) -> String {
+        custom_protocol_url(protocol, "").trim_end_matches('/').to_string()
+    }
+
+    #[test]
+    fn allows_the_given_protocols_everywhere() {
+        for protocols in [&[][..], &["samples"], &["samples", "presets", "ui"]] {
+            let policy = content_security_policy(protocols);
+            let directives = directives(&policy);
+            for name in [
+                "default-src",
+                "script-src",
+                "style-src",
+                "img-src",
+                "media-src",
+                "font-src",
+                "connect-src",
+            ] {
+                let sources = &directives[name];
+                assert!(sources.contains(&"'self'"), "{name} in {policy}");
+                for protocol in protocols {
+                    assert!(sources.contains(&origin(protocol).as_str()), "{name} in {policy}");
+                }
+            }
+            assert_eq!(directives.len(), 9, "{policy}");
+        }
+    }
+
+    #[test]
+    fn allows_what_the_bridge_needs() {
+        let policy = content_security_policy(&[]);
+        let directives = directives(&policy);
+        let (assets, visualizations) =
+            (origin(crate::ASSET_PROTOCOL), origin(crate::VISUALIZATION_PROTOCOL));
+
+        for name in ["img-src", "media-src", "connect-src"] {
+            assert!(directives[name].contains(&assets.as_str()), "{name} in {policy}");
+        }
+        assert!(directives["connect-src"].contains(&visualizations.as_str()), "{policy}");
+        assert!(directives["style-src"].contains(&"'unsafe-inline'"), "{policy}");
+        assert!(!directives["script-src"].contains(&"'unsafe-inline'"), "{policy}");
+        assert_eq!(directives["object-src"], ["'none'"]);
+        assert_eq!(directives["base-uri"], ["'self'"]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Conversions between the editor's sizes, which all go through here.
+//!
+//! The page is laid out in content pixels, the size saved in the
+//! [`WebviewState`](crate::WebviewState). The zoom factor, see
+//! [`Context::set_zoom`](crate::Context::set_zoom), scales them to the
+//! window's logical pixels. The host's scale factor scales those to physical
+//! pixels, which nih-plug and baseview do for the window itself.
+
+use nih_plug::prelude::GuiContext;
+
+use crate::WebviewState;
+
+/// The logical window size showing `content` at `zoom`.
+pub(crate) fn window_size((width, height): (u32, u32), zoom: f64) -> (u32, u32) {
+    let scale = |v: u32| ((v as f64 * zoom).round() as u32).max(1);
+    (scale(width), scale(height))
+}
+
+/// The content size shown by a logical window size at `zoom`. `current` is
+/// kept if it's shown by that window size, as below a zoom factor of 1 more
+/// than one content size maps to the same window size. Otherwise a host
+/// reporting back the size it was asked for could make the content size
+/// drift.
+pub(crate) fn content_size(
+    (width, height): (f64, f64),
+    zoom: f64,
+    current: (u32, u32),
+) -> (u32, u32) {
+    let window = (width.round() as u32, height.round() as u32);
+    if window_size(current, zoom) == window {
+        return current;
+    }
+    let unscale = |v: f64| ((v / zoom).round() as u32).max(1);
+    (unscale(width), unscale(height))
+}
+
+/// The webview's bounds for a logical window size. The webview is sized in
+/// physical pixels on Windows and in logical pixels elsewhere.
+pub(crate) fn physical_bounds((width, height): (u32, u32), scale_factor: f64) -> wry::Rect {
+    let scale = if cfg!(target_os = "windows") { scale_factor } else { 1.0 };
+    wry::Rect {
+        x: 0,
+        y: 0,
+        width: (width as f64 * scale).round() as u32,
+        height: (height as f64 * scale).round() as u32,
+    }
+}
+
+/// Asks the host to resize the window for a new content size. nih-plug reads
+/// the size back through [`Editor::size`](nih_plug::prelude::Editor::size),
+/// so it's stored in `state` first, and the old size restored if the host
+/// refuses.
+pub(crate) fn request_content_size(
+    context: &dyn GuiContext,
+    state: &WebviewState,
+    content: (u32, u32),
+) -> bool {
+    let old = state.content_size.swap(content);
+    if !context.request_resize() {
+        state.content_size.store(old);
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: (u32, u32) = (800, 600);
+
+    #[test]
+    fn converts_between_content_window_and_physical_sizes() {
+        // Host scale factor, zoom, logical window size, physical size on Windows.
+        let cases = [
+            (1.0, 0.75, (600, 450), (600, 450)),
+            (1.0, 1.0, (800, 600), (800, 600)),
+            (1.0, 1.5, (1200, 900), (1200, 900)),
+            (1.25, 0.75, (600, 450), (750, 563)),
+            (1.25, 1.0, (800, 600), (1000, 750)),
+            (1.25, 1.5, (1200, 900), (1500, 1125)),
+            (2.0, 0.75, (600, 450), (1200, 900)),
+            (2.0, 1.0, (800, 600), (1600, 1200)),
+            (2.0, 1.5, (1200, 900), (2400, 1800)),
+        ];
+        for (scale_factor, zoom, window, physical) in cases {
+            let case = format!("scale factor {scale_factor}, zoom {zoom}");
+            assert_eq!(window_size(CONTENT, zoom), window, "{case}");
+
+            let bounds = physical_bounds(window, scale_factor);
+            let expected = if cfg!(target_os = "windows") { physical } else { window };
+            assert_eq!((bounds.x, bounds.y), (0, 0), "{case}");
+            assert_eq!((bounds.width, bounds.height), expected, "{case}");
+
+            // What the host reports back doesn't depend on the scale factor.
+            let reported = (window.0 as f64, window.1 as f64);
+            assert_eq!(content_size(reported, zoom, (1, 1)), CONTENT, "{case}");
+            assert_eq!(content_size(reported, zoom, CONTENT), CONTENT, "{case}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_current_content_size_below_a_zoom_of_one() {
+        // 802 and 803 content pixels are both 602 window pixels at 75%.
+        assert_eq!(window_size((802, 600), 0.75), (602, 450));
+        assert_eq!(window_size((803, 600), 0.75), (602, 450));
+        assert_eq!(content_size((602.0, 450.0), 0.75, (803, 600)), (803, 600));
+        assert_eq!(content_size((602.0, 450.0), 0.75, (802, 600)), (802, 600));
+        // Another size is converted back, rounding to the nearest pixel.
+        assert_eq!(content_size((602.0, 450.0), 0.75, (400, 300)), (803, 600));
+    }
+
+    #[test]
+    fn sizes_never_reach_zero() {
+        assert_eq!(window_size((1, 1), 0.25), (1, 1));
+        assert_eq!(content_size((0.0, 0.4), 1.5, (10, 10)), (1, 1));
+    }
+}
@@ -0,0 +1,330 @@
+//! Helpers for unit testing [`EditorHandler`] implementations without a
+//! webview or a host. Requires the `testing` feature.
+//!
+//! Write the message handling against [`EditorContext`] so that it can be
+//! called both from the handler with the real [`Context`](crate::Context) and
+//! from tests with a [`MockContext`]:
+//!
+//! ```rust
+//! use nih_plug_webview::{testing::*, EditorContext, EditorHandler};
+//! # use nih_plug_webview::Context;
+//!
+//! struct Handler;
+//!
+//! impl Handler {
+//!     fn handle(&mut self, cx: &mut impl EditorContext<Self>, message: String) {
+//!         if message == "reset" {
+//!             cx.begin_gesture_by_id("gain").unwrap();
+//!             cx.set_param_normalized_by_id("gain", 0.5).unwrap();
+//!             cx.end_gesture_by_id("gain").unwrap();
+//!             cx.send_message("done".to_string()).unwrap();
+//!         }
+//!     }
+//! }
+//!
+//! impl EditorHandler for Handler {
+//!     type EditorTx = String;
+//!     type EditorRx = String;
+//!
+//!     fn init(&mut self, _cx: &mut Context<Self>) {}
+//!     fn on_frame(&mut self, _cx: &mut Context<Self>) {}
+//!
+//!     fn on_message(&mut self, cx: &mut Context<Self>, message: Self::EditorRx) {
+//!         self.handle(cx, message);
+//!     }
+//! }
+//!
+//! let mut cx = MockContext::<Handler>::new().with_param_ids(["gain"]);
+//! Handler.handle(&mut cx, "reset".to_string());
+//!
+//! assert_eq!(cx.sent_messages(), ["done"]);
+//! assert_eq!(
+//!     cx.param_operations(),
+//!     [
+//!         ParamOperation::BeginGesture("gain".to_string()),
+//!         ParamOperation::SetNormalized("gain".to_string(), 0.5),
+//!         ParamOperation::EndGesture("gain".to_string()),
+//!     ]
+//! );
+//! ```
+//!
+//! Code using nih-plug's [`GuiContext`] directly, like a plugin's own glue
+//! around the editor, can be tested with a [`FakeGuiContext`] instead of a
+//! host.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    sync::Mutex,
+};
+
+use nih_plug::prelude::{GuiContext, ParamPtr, PluginApi, PluginState};
+
+use crate::{EditorContext, EditorHandler, ParamIdError, SendError};
+
+/// A parameter operation recorded by a [`MockContext`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamOperation {
+    BeginGesture(String),
+    SetNormalized(String, f32),
+    EndGesture(String),
+}
+
+/// An [`EditorContext`] recording everything the handler does with it.
+pub struct MockContext<H: EditorHandler> {
+    sent_messages: Vec<H::EditorTx>,
+    resize_requests: Vec<(u32, u32)>,
+    param_operations: RefCell<Vec<ParamOperation>>,
+    /// `None` if any parameter ID is accepted.
+    param_ids: Option<HashSet<String>>,
+    accept_resizes: bool,
+}
+
+impl<H: EditorHandler> Default for MockContext<H> {
+    fn default() -> Self {
+        MockContext {
+            sent_messages: Vec::new(),
+            resize_requests: Vec::new(),
+            param_operations: RefCell::new(Vec::new()),
+            param_ids: None,
+            accept_resizes: true,
+        }
+    }
+}
+
+impl<H: EditorHandler> MockContext<H> {
+    /// Creates a context accepting every parameter ID and resize request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accepts these parameter IDs, returning
+    /// [`ParamIdError::UnknownId`] for others like the real context does.
+    pub fn with_param_ids<S: Into<String>>(mut self, ids: impl IntoIterator<Item = S>) -> Self {
+        self.param_ids = Some(ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Makes [`EditorContext::resize_window`] return `false`, as if the host
+    /// refused the new size. Requests are still recorded.
+    pub fn reject_resizes(mut self) -> Self {
+        self.accept_resizes = false;
+        self
+    }
+
+    /// The messages sent with [`EditorContext::send_message`], oldest first.
+    pub fn sent_messages(&self) -> &[H::EditorTx] {
+        &self.sent_messages
+    }
+
+    /// The sizes passed to [`EditorContext::resize_window`], oldest first.
+    pub fn resize_requests(&self) -> &[(u32, u32)] {
+        &self.resize_requests
+    }
+
+    /// The gestures and parameter changes, oldest first. Operations on
+    /// unknown IDs are not recorded.
+    pub fn param_operations(&self) -> Vec<ParamOperation> {
+        self.param_operations.borrow().clone()
+    }
+
+    /// Forgets everything recorded so far.
+    pub fn clear(&mut self) {
+        self.sent_messages.clear();
+        self.resize_requests.clear();
+        self.param_operations.get_mut().clear();
+    }
+
+    fn record(&self, id: &str, operation: ParamOperation) -> Result<(), ParamIdError> {
+        if self.param_ids.as_ref().is_some_and(|ids| !ids.contains(id)) {
+            return Err(ParamIdError::UnknownId(id.to_string()));
+        }
+        self.param_operations.borrow_mut().push(operation);
+        Ok(())
+    }
+}
+
+impl<H: EditorHandler> EditorContext<H> for MockContext<H> {
+    fn send_message(&mut self, message: H::EditorTx) -> Result<(), SendError> {
+        self.sent_messages.push(message);
+        Ok(())
+    }
+
+    fn resize_window(&mut self, width: u32, height: u32) -> bool {
+        self.resize_requests.push((width, height));
+        self.accept_resizes
+    }
+
+    fn begin_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError> {
+        self.record(id, ParamOperation::BeginGesture(id.to_string()))
+    }
+
+    fn set_param_normalized_by_id(&self, id: &str, normalized: f32) -> Result<(), ParamIdError> {
+        let normalized = normalized.clamp(0.0, 1.0);
+        self.record(id, ParamOperation::SetNormalized(id.to_string(), normalized))
+    }
+
+    fn end_gesture_by_id(&self, id: &str) -> Result<(), ParamIdError> {
+        self.record(id, ParamOperation::EndGesture(id.to_string()))
+    }
+}
+
+/// A call to a [`FakeGuiContext`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuiCall {
+    RequestResize,
+    BeginSetParameter(ParamPtr),
+    SetParameterNormalized(ParamPtr, f32),
+    EndSetParameter(ParamPtr),
+    SetState,
+}
+
+/// A [`GuiContext`] standing in for the host, recording the resize requests,
+/// gestures and parameter changes made through it. Parameters are not
+/// actually changed.
+pub struct FakeGuiContext {
+    plugin_api: PluginApi,
+    accept_resizes: bool,
+    calls: Mutex<Vec<GuiCall>>,
+    /// Returned from [`GuiContext::get_state`].
+    state: Mutex<Option<PluginState>>,
+}
+
+impl Default for FakeGuiContext {
+    fn default() -> Self {
+        FakeGuiContext {
+            plugin_api: PluginApi::Clap,
+            accept_resizes: true,
+            calls: Mutex::new(Vec::new()),
+            state: Mutex::new(None),
+        }
+    }
+}
+
+impl FakeGuiContext {
+    /// Creates a CLAP host accepting every resize request.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports `plugin_api` from [`GuiContext::plugin_api`].
+    pub fn with_plugin_api(mut self, plugin_api: PluginApi) -> Self {
+        self.plugin_api = plugin_api;
+        self
+    }
+
+    /// Makes [`GuiContext::request_resize`] return `false`, like hosts with a
+    /// fixed editor size. Requests are still recorded.
+    pub fn reject_resizes(mut self) -> Self {
+        self.accept_resizes = false;
+        self
+    }
+
+    /// Returns `state` from [`GuiContext::get_state`] until another state is
+    /// set.
+    pub fn with_state(self, state: PluginState) -> Self {
+        *self.state.lock().unwrap() = Some(state);
+        self
+    }
+
+    /// The calls made so far, oldest first.
+    pub fn calls(&self) -> Vec<GuiCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Forgets the calls recorded so far.
+    pub fn clear(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    fn record(&self, call: GuiCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+impl GuiContext for FakeGuiContext {
+    fn plugin_api(&self) -> PluginApi {
+        self.plugin_api
+    }
+
+    fn request_resize(&self) -> bool {
+        self.record(GuiCall::RequestResize);
+        self.accept_resizes
+    }
+
+    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+        self.record(GuiCall::BeginSetParameter(param));
+    }
+
+    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+        self.record(GuiCall::SetParameterNormalized(param, normalized));
+    }
+
+    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+        self.record(GuiCall::EndSetParameter(param));
+    }
+
+    /// The state last set, or an empty state without any parameters.
+    fn get_state(&self) -> PluginState {
+        self.state.lock().unwrap().clone().unwrap_or_else(|| PluginState {
+            version: String::new(),
+            params: BTreeMap::new(),
+            fields: BTreeMap::new(),
+        })
+    }
+
+    fn set_state(&self, state: PluginState) {
+        self.record(GuiCall::SetState);
+        *self.state.lock().unwrap() = Some(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nih_plug::params::persist::PersistentField;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{size, WebviewState};
+
+    #[test]
+    fn accepted_resizes_keep_the_new_size() {
+        let host = FakeGuiContext::new();
+        let state = WebviewState::new(400, 300);
+
+        assert!(size::request_content_size(&host, &state, (640, 480)));
+        assert_eq!(host.calls(), [GuiCall::RequestResize]);
+        // What nih-plug reads back while handling the request.
+        assert_eq!(state.content_size(), (640, 480));
+    }
+
+    #[test]
+    fn refused_resizes_restore_the_old_size() {
+        let host = FakeGuiContext::new().reject_resizes();
+        let state = WebviewState::new(400, 300);
+
+        assert!(!size::request_content_size(&host, &state, (640, 480)));
+        assert_eq!(host.calls(), [GuiCall::RequestResize]);
+        assert_eq!(state.content_size(), (400, 300));
+    }
+
+    #[test]
+    fn state_round_trips_through_the_host() {
+        let state = WebviewState::new(400, 300);
+        state.content_size.store((640, 480));
+        state.set_ui_state(json!({ "tab": "mixer" }));
+
+        let host = FakeGuiContext::new();
+        let mut fields = BTreeMap::new();
+        fields.insert("editor-state".to_string(), serde_json::to_string(&*state).unwrap());
+        host.set_state(PluginState { version: String::new(), params: BTreeMap::new(), fields });
+        assert_eq!(host.calls(), [GuiCall::SetState]);
+
+        let saved = host.get_state().fields.remove("editor-state").unwrap();
+        let restored = WebviewState::new(400, 300);
+        restored.set(serde_json::from_str(&saved).unwrap());
+        assert_eq!(restored.content_size(), (640, 480));
+        assert_eq!(restored.zoom(), 1.0);
+        assert_eq!(restored.ui_state(), json!({ "tab": "mixer" }));
+    }
+}
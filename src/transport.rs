@@ -0,0 +1,56 @@
+//! Passing the host's transport from the audio thread to the editor, see
+//! [`WebviewEditor::with_transport`](crate::WebviewEditor::with_transport).
+
+use std::sync::Arc;
+
+use crossbeam::atomic::AtomicCell;
+use nih_plug::prelude::Transport;
+use serde::Serialize;
+
+/// The state of the host's transport. Store it into the cell passed to
+/// [`WebviewEditor::with_transport`](crate::WebviewEditor::with_transport)
+/// from `Plugin::process`:
+///
+/// ```ignore
+/// self.transport.store(TransportSnapshot::from(context.transport()));
+/// ```
+///
+/// Fields the host doesn't report are `None`, or `null` in the page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransportSnapshot {
+    pub playing: bool,
+    pub recording: bool,
+    /// In beats per minute.
+    pub tempo: Option<f64>,
+    /// The time signature as `(numerator, denominator)`.
+    pub time_signature: Option<(i32, i32)>,
+    /// The playhead's position in samples.
+    pub pos_samples: Option<i64>,
+    /// The playhead's position in quarter notes.
+    pub pos_beats: Option<f64>,
+    /// The position of the current bar's start in quarter notes.
+    pub bar_start_pos_beats: Option<f64>,
+    /// The current bar, starting at 0.
+    pub bar_number: Option<i32>,
+}
+
+impl From<&Transport> for TransportSnapshot {
+    fn from(transport: &Transport) -> Self {
+        TransportSnapshot {
+            playing: transport.playing,
+            recording: transport.recording,
+            tempo: transport.tempo,
+            time_signature: transport.time_sig_numerator.zip(transport.time_sig_denominator),
+            pos_samples: transport.pos_samples(),
+            pos_beats: transport.pos_beats(),
+            bar_start_pos_beats: transport.bar_start_pos_beats(),
+            bar_number: transport.bar_number(),
+        }
+    }
+}
+
+/// The cell shared between the audio thread and the editor. A snapshot is too
+/// large for `AtomicCell` to be lock-free, but storing one only takes a short
+/// spin lock and never allocates.
+pub type TransportCell = Arc<AtomicCell<TransportSnapshot>>;
@@ -0,0 +1,150 @@
+//! An undo history for state which is not made of parameters, see
+//! [`Context::undo_bridge`](crate::Context::undo_bridge). Parameter changes
+//! are undone by the host.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UndoEntry {
+    label: String,
+    state: Vec<u8>,
+}
+
+/// The states committed through an [`UndoBridge`], oldest first, kept in the
+/// [`WebviewState`](crate::WebviewState) so it outlives the editor window.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct UndoHistory {
+    entries: Vec<UndoEntry>,
+    /// The entry of the current state.
+    current: usize,
+    /// Whether the history is saved with the plugin's state, see
+    /// [`WebviewConfig::persist_undo_history`](crate::WebviewConfig::persist_undo_history).
+    #[serde(skip)]
+    persist: bool,
+    /// The label passed to [`UndoBridge::begin_edit`].
+    #[serde(skip)]
+    pending_label: Option<String>,
+}
+
+impl UndoHistory {
+    pub fn set_persist(&mut self, persist: bool) {
+        self.persist = persist;
+    }
+
+    /// Whether there's something to undo and something to redo.
+    pub fn availability(&self) -> (bool, bool) {
+        (self.current > 0, self.current + 1 < self.entries.len())
+    }
+
+    /// Replaces the history with a restored one, which is empty unless it
+    /// was saved. A saved history stays saved until an editor opens with
+    /// other options.
+    pub fn restore(&mut self, restored: UndoHistory) {
+        self.persist |= !restored.entries.is_empty();
+        self.current = restored.current.min(restored.entries.len().saturating_sub(1));
+        self.entries = restored.entries;
+        self.pending_label = None;
+    }
+}
+
+/// Only saves the history when it's meant to be saved, as the states may be
+/// large.
+pub(crate) fn skip_saving(history: &Mutex<UndoHistory>) -> bool {
+    let history = history.lock().unwrap_or_else(PoisonError::into_inner);
+    !history.persist || history.entries.is_empty()
+}
+
+/// Records edits to state the host doesn't know about, like a drawn
+/// wavetable, so they can be undone. Returned by
+/// [`Context::undo_bridge`](crate::Context::undo_bridge).
+///
+/// The states are opaque bytes, usually the serialized state after each edit.
+/// [`UndoBridge::undo`] and [`UndoBridge::redo`] return the state to restore,
+/// which the handler applies itself. Changes to [`UndoBridge::can_undo`] and
+/// [`UndoBridge::can_redo`] are reported to
+/// [`EditorHandler::on_undo_state_changed`](crate::EditorHandler::on_undo_state_changed).
+pub struct UndoBridge<'a> {
+    pub(crate) history: &'a Mutex<UndoHistory>,
+    pub(crate) limit: usize,
+}
+
+impl UndoBridge<'_> {
+    fn history(&self) -> MutexGuard<'_, UndoHistory> {
+        self.history.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Clears the history and makes `state` the one the first edit can be
+    /// undone to. Without it, the first committed state is that one.
+    pub fn reset(&self, state: Vec<u8>) {
+        let mut history = self.history();
+        history.entries = vec![UndoEntry { label: String::new(), state }];
+        history.current = 0;
+        history.pending_label = None;
+    }
+
+    /// Starts an edit named `label`, e.g. `"Draw wavetable"`, when the user
+    /// starts dragging. Finished with [`UndoBridge::commit_edit`].
+    pub fn begin_edit(&self, label: impl Into<String>) {
+        self.history().pending_label = Some(label.into());
+    }
+
+    /// Finishes the edit, with `state` as the state after it. Removes the
+    /// states which could have been redone, and the oldest state once there
+    /// are more than [`WebviewConfig::undo_limit`](crate::WebviewConfig::undo_limit)
+    /// edits.
+    pub fn commit_edit(&self, state: Vec<u8>) {
+        let mut history = self.history();
+        let label = history.pending_label.take().unwrap_or_default();
+        let current = history.current;
+        history.entries.truncate(current + 1);
+        history.entries.push(UndoEntry { label, state });
+        let excess = history.entries.len().saturating_sub(self.limit + 1);
+        history.entries.drain(..excess);
+        history.current = history.entries.len() - 1;
+    }
+
+    /// Steps back one edit and returns the state before it, or `None` if
+    /// there's nothing to undo.
+    pub fn undo(&self) -> Option<Vec<u8>> {
+        let mut history = self.history();
+        if history.current == 0 {
+            return None;
+        }
+        history.current -= 1;
+        Some(history.entries[history.current].state.clone())
+    }
+
+    /// Redoes the last undone edit and returns the state after it, or `None`
+    /// if there's nothing to redo.
+    pub fn redo(&self) -> Option<Vec<u8>> {
+        let mut history = self.history();
+        if history.current + 1 >= history.entries.len() {
+            return None;
+        }
+        history.current += 1;
+        Some(history.entries[history.current].state.clone())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history().availability().0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history().availability().1
+    }
+
+    /// The label of the edit [`UndoBridge::undo`] would undo, e.g. for an
+    /// "Undo Draw wavetable" button.
+    pub fn undo_label(&self) -> Option<String> {
+        let history = self.history();
+        (history.current > 0).then(|| history.entries[history.current].label.clone())
+    }
+
+    /// The label of the edit [`UndoBridge::redo`] would redo.
+    pub fn redo_label(&self) -> Option<String> {
+        let history = self.history();
+        history.entries.get(history.current + 1).map(|entry| entry.label.clone())
+    }
+}
@@ -0,0 +1,135 @@
+//! A lock-free channel for streaming high-rate visualization data, like spectra
+//! or waveforms, from the audio thread to the webview.
+
+use std::{
+    cell::UnsafeCell,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Set on [`TripleBuffer::shared`] when the producer published a frame the
+/// consumer has not taken yet.
+const NEW_DATA: u8 = 0b100;
+const INDEX_MASK: u8 = 0b011;
+
+/// Creates a channel carrying frames of `len` values. The producer goes to the
+/// audio thread, the source is handed to
+/// [`WebviewEditor::with_visualization`](crate::WebviewEditor::with_visualization).
+pub fn visualization_channel<T: VisualizationSample>(
+    len: usize,
+) -> (VisualizationProducer<T>, VisualizationSource<T>) {
+    let buffer = Arc::new(TripleBuffer {
+        slots: std::array::from_fn(|_| UnsafeCell::new(vec![T::default(); len])),
+        shared: AtomicU8::new(1),
+    });
+
+    let producer = VisualizationProducer { buffer: buffer.clone(), write: 0 };
+    let source = VisualizationSource { buffer, read: Mutex::new(2) };
+    (producer, source)
+}
+
+/// A value which can be sent through a [`visualization_channel`]. Values
+/// arrive in the webview in little endian byte order, so they can be read
+/// with the matching typed array, e.g. a `Float32Array` for `f32`.
+pub trait VisualizationSample: Copy + Default + Send + 'static {
+    /// Appends the value's little endian bytes.
+    fn extend_bytes(self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_visualization_sample {
+    ($($t:ty),*) => {
+        $(impl VisualizationSample for $t {
+            fn extend_bytes(self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_le_bytes());
+            }
+        })*
+    };
+}
+
+impl_visualization_sample!(f32, f64, u8, i8, u16, i16, u32, i32);
+
+/// Three frame sized slots. At any time one belongs to the producer, one to
+/// the consumer, and the third is shared and swapped with either side.
+struct TripleBuffer<T> {
+    slots: [UnsafeCell<Vec<T>>; 3],
+    /// The index of the shared slot, plus [`NEW_DATA`].
+    shared: AtomicU8,
+}
+
+// SAFETY: Each slot is only ever accessed by the side currently owning it.
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+/// The writing end of a [`visualization_channel`]. Writing never blocks or
+/// allocates, so this can be used from the audio thread.
+pub struct VisualizationProducer<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    write: u8,
+}
+
+impl<T: VisualizationSample> VisualizationProducer<T> {
+    /// The number of values in a frame.
+    pub fn len(&self) -> usize {
+        self.slot().len()
+    }
+
+    /// Whether frames are empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Publishes a frame. A shorter frame is padded with default values, a
+    /// longer one is truncated.
+    pub fn write(&mut self, frame: &[T]) {
+        self.write_with(|slot| {
+            let n = slot.len().min(frame.len());
+            slot[..n].copy_from_slice(&frame[..n]);
+            slot[n..].fill(T::default());
+        });
+    }
+
+    /// Fills in a frame in place and publishes it. The slice still holds an
+    /// older frame, so `f` should overwrite all of it.
+    pub fn write_with(&mut self, f: impl FnOnce(&mut [T])) {
+        // SAFETY: The write slot is owned by the producer until it's swapped
+        // out below.
+        f(unsafe { &mut *self.buffer.slots[self.write as usize].get() });
+        self.write = self.buffer.shared.swap(self.write | NEW_DATA, Ordering::AcqRel) & INDEX_MASK;
+    }
+
+    fn slot(&self) -> &[T] {
+        // SAFETY: See above.
+        unsafe { &*self.buffer.slots[self.write as usize].get() }
+    }
+}
+
+/// The reading end of a [`visualization_channel`], see
+/// [`WebviewEditor::with_visualization`](crate::WebviewEditor::with_visualization).
+pub struct VisualizationSource<T> {
+    buffer: Arc<TripleBuffer<T>>,
+    /// The consumer's slot. Only locked by the GUI thread.
+    read: Mutex<u8>,
+}
+
+/// A [`VisualizationSource`] with the sample type erased.
+pub(crate) trait FrameSource: Send + Sync {
+    /// Returns the most recently published frame as bytes.
+    fn latest_frame(&self) -> Vec<u8>;
+}
+
+impl<T: VisualizationSample> FrameSource for VisualizationSource<T> {
+    fn latest_frame(&self) -> Vec<u8> {
+        let mut read = self.read.lock().unwrap();
+        if self.buffer.shared.load(Ordering::Relaxed) & NEW_DATA != 0 {
+            *read = self.buffer.shared.swap(*read, Ordering::AcqRel) & INDEX_MASK;
+        }
+
+        // SAFETY: The read slot is owned by the consumer, and the lock keeps it
+        // from being swapped out while it's being read.
+        let frame = unsafe { &*self.buffer.slots[*read as usize].get() };
+        let mut bytes = Vec::with_capacity(std::mem::size_of_val(frame.as_slice()));
+        frame.iter().for_each(|value| value.extend_bytes(&mut bytes));
+        bytes
+    }
+}
@@ -0,0 +1,77 @@
+//! Sharing a `WebContext` between editors, see
+//! [`WebviewConfig::share_web_context`](crate::WebviewConfig::share_web_context).
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::{Rc, Weak},
+};
+
+use wry::WebContext;
+
+/// A `WebContext` which may be used by several editors.
+pub(crate) type SharedWebContext = Rc<RefCell<WebContext>>;
+
+thread_local! {
+    /// `WebContext`s are not `Send`, so the registry is kept per thread. In
+    /// practice every editor is opened on the host's GUI thread. Apart from
+    /// the groups of `WebviewConfig::instance_group`, this is the only state
+    /// the crate shares between editors.
+    static REGISTRY: RefCell<HashMap<PathBuf, Weak<RefCell<WebContext>>>> =
+        RefCell::new(HashMap::new());
+    /// The contexts which are not shared, by data directory.
+    static UNSHARED: RefCell<Vec<(PathBuf, Weak<RefCell<WebContext>>)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// The contexts used by the open editors, by data directory. A context is
+/// dropped together with the last editor using it.
+pub(crate) struct WebContextRegistry;
+
+impl WebContextRegistry {
+    /// Returns the shared context for `data_directory`, creating it if no
+    /// open editor uses it.
+    pub fn get(data_directory: &Path) -> SharedWebContext {
+        REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            registry.retain(|_, context| context.strong_count() > 0);
+
+            if let Some(context) = registry.get(data_directory).and_then(Weak::upgrade) {
+                return context;
+            }
+            let context = WebContextRegistry::unshared(Some(data_directory));
+            registry.insert(data_directory.to_path_buf(), Rc::downgrade(&context));
+            context
+        })
+    }
+
+    /// Returns a new context used only by the calling editor. Without a data
+    /// directory, the webview's default one is used.
+    pub fn unshared(data_directory: Option<&Path>) -> SharedWebContext {
+        let context = Rc::new(RefCell::new(WebContext::new(data_directory.map(Path::to_path_buf))));
+        if let Some(data_directory) = data_directory {
+            UNSHARED.with(|unshared| {
+                let mut unshared = unshared.borrow_mut();
+                unshared.retain(|(_, context)| context.strong_count() > 0);
+                unshared.push((data_directory.to_path_buf(), Rc::downgrade(&context)));
+            });
+        }
+        context
+    }
+
+    /// Whether an open editor on this thread uses `data_directory`, e.g. the
+    /// same plugin loaded in another format.
+    pub fn in_use(data_directory: &Path) -> bool {
+        let shared = REGISTRY.with(|registry| {
+            registry.borrow().get(data_directory).is_some_and(|context| context.strong_count() > 0)
+        });
+        shared
+            || UNSHARED.with(|unshared| {
+                unshared
+                    .borrow()
+                    .iter()
+                    .any(|(path, context)| path == data_directory && context.strong_count() > 0)
+            })
+    }
+}
@@ -0,0 +1,147 @@
+//! The directory the webview keeps its data in, see
+//! [`WebviewConfig::workdir_policy`](crate::WebviewConfig::workdir_policy).
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{WebviewError, WebviewState};
+
+/// Where the webview keeps `localStorage`, IndexedDB, cookies and caches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorkdirPolicy {
+    /// Uses the `context_dir` itself, shared by every instance of the plugin.
+    /// Unless [`WebviewConfig::share_web_context`](crate::WebviewConfig::share_web_context)
+    /// is set, an editor opening while another open editor on the same thread
+    /// uses the directory, like the same plugin loaded in another format,
+    /// falls back to [`WorkdirPolicy::PerInstance`].
+    #[default]
+    Shared,
+    /// Uses a subdirectory of the `context_dir` per plugin instance, named
+    /// after an identifier saved in the [`WebviewState`]. An instance
+    /// duplicated by the host shares the directory with the original.
+    PerInstance,
+    /// Uses a new temporary directory every time the editor opens, which is
+    /// deleted when it closes. Nothing is kept between sessions.
+    Ephemeral,
+}
+
+/// A prepared data directory. Temporary ones are deleted on drop.
+pub(crate) struct Workdir {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl Workdir {
+    /// Creates the directory for `policy` if it doesn't exist yet. Without a
+    /// `context_dir`, one is derived from the editor's `title`.
+    pub fn prepare(
+        context_dir: Option<&Path>,
+        title: &str,
+        policy: WorkdirPolicy,
+        state: &WebviewState,
+    ) -> Result<Workdir, WebviewError> {
+        let default_dir;
+        let context_dir = match context_dir {
+            Some(dir) => dir,
+            None => {
+                default_dir = default_context_dir(title);
+                &default_dir
+            }
+        };
+        let path = match policy {
+            WorkdirPolicy::Shared => context_dir.to_path_buf(),
+            WorkdirPolicy::PerInstance => {
+                context_dir.join(format!("instance-{:016x}", state.instance_id()))
+            }
+            WorkdirPolicy::Ephemeral => return Workdir::temporary(),
+        };
+
+        if is_dangerous(&path) {
+            let err = io::Error::new(io::ErrorKind::InvalidInput, "refusing to store data here");
+            return Err(WebviewError::Workdir(path, err));
+        }
+        std::fs::create_dir_all(&path).map_err(|err| WebviewError::Workdir(path.clone(), err))?;
+        Ok(Workdir { path, temporary: false })
+    }
+
+    /// Creates a new temporary directory.
+    pub fn temporary() -> Result<Workdir, WebviewError> {
+        let path = std::env::temp_dir().join(format!("nih-plug-webview-{:016x}", random_id()));
+        std::fs::create_dir_all(&path).map_err(|err| WebviewError::Workdir(path.clone(), err))?;
+        Ok(Workdir { path, temporary: true })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Workdir {
+    fn drop(&mut self) {
+        if self.temporary {
+            // The webview's processes may still hold files for a moment, in
+            // which case the directory is left to the system's cleanup.
+            let _ = std::fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+/// A directory for the plugin in the user's cache directory, named after the
+/// editor's `title`. A hash of the full title tells apart plugins whose names
+/// only differ in characters which can't be used in file names.
+fn default_context_dir(title: &str) -> PathBuf {
+    let name: String = title
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("nih-plug-webview")
+        .join(format!("{name}-{:08x}", fnv1a(title) as u32))
+}
+
+/// The user's cache directory, `%LOCALAPPDATA%`, `~/Library/Caches` or
+/// `$XDG_CACHE_HOME`.
+fn cache_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        var("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Caches"))
+    } else {
+        var("XDG_CACHE_HOME").or_else(|| var("HOME").map(|home| home.join(".cache")))
+    }
+}
+
+/// A hash which, unlike the standard library's, is stable between Rust
+/// versions, so the directory doesn't move when the plugin is rebuilt.
+pub(crate) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Whether the webview writing its data directly into `path` would clutter
+/// or endanger the user's files, e.g. the file system root or home directory.
+fn is_dangerous(path: &Path) -> bool {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    path.as_os_str().is_empty()
+        || path.parent().is_none()
+        || home.is_some_and(|home| path == Path::new(&home))
+}
+
+/// A random identifier, from the randomly seeded hasher of the standard
+/// library.
+pub(crate) fn random_id() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos()),
+    );
+    hasher.finish()
+}